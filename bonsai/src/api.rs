@@ -0,0 +1,145 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Requests and responses of the Bonsai REST API, as used by the `alpha` client of `bonsai-sdk`.
+//!
+//! | Method | Path                           | Body            | Response                  |
+//! |--------|--------------------------------|-----------------|---------------------------|
+//! | GET    | `images/upload/{image_id}`     |                 | 204, or [ImgUploadRes]    |
+//! | GET    | `inputs/upload`                |                 | [UploadRes]               |
+//! | POST   | `sessions/create`              | [ProofReq]      | [CreateSessRes]           |
+//! | GET    | `sessions/status/{session_id}` |                 | [SessionStatusRes]        |
+//!
+//! Every request carries the API key in the `x-api-key` header. Images are addressed by the hex
+//! image ID of the guest, without a `0x` prefix. Bonsai answers `204 No Content` to an image it
+//! already has, and otherwise with a URL to `PUT` the ELF to. Inputs are likewise `PUT` to the URL
+//! of their [UploadRes], and the [Receipt] of a finished session is downloaded from its
+//! `receipt_url`. These URLs are presigned and take no API key.
+//!
+//! Errors are reported with a plain-text body.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Identifier of a proving session, as assigned by Bonsai.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SessionId(pub String);
+
+impl fmt::Display for SessionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Where to upload an image Bonsai does not have yet.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImgUploadRes {
+    pub url: String,
+}
+
+/// Where to upload an input, and the ID to refer to it by once uploaded.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadRes {
+    pub url: String,
+    pub uuid: String,
+}
+
+/// Start proving the uploaded image `img` on the uploaded input `input`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofReq {
+    /// Hex image ID.
+    pub img: String,
+    /// ID of the input, from its [UploadRes].
+    pub input: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreateSessRes {
+    pub uuid: String,
+}
+
+/// State of a proving session.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionStatusRes {
+    /// One of the `STATUS_*` constants.
+    pub status: String,
+    /// Where to download the [Receipt] from, once the session succeeded.
+    pub receipt_url: Option<String>,
+    /// Why the session did not succeed, if Bonsai says.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_msg: Option<String>,
+}
+
+/// The session is queued or proving.
+pub const STATUS_RUNNING: &str = "RUNNING";
+/// The receipt is ready to download.
+pub const STATUS_SUCCEEDED: &str = "SUCCEEDED";
+/// The guest or the prover failed.
+pub const STATUS_FAILED: &str = "FAILED";
+/// The session ran out of time.
+pub const STATUS_TIMED_OUT: &str = "TIMED_OUT";
+/// The session was aborted.
+pub const STATUS_ABORTED: &str = "ABORTED";
+
+/// Receipt of a finished session, as the `bincode` serialization of a RISC Zero 0.13
+/// `risc0_zkvm::Receipt`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Receipt {
+    pub journal: Vec<u8>,
+    /// Empty if the prover skipped the seal.
+    pub seal: Vec<u32>,
+}
+
+impl Receipt {
+    pub fn from_bincode(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+
+    pub fn to_bincode(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("receipt serializes")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_without_receipt() {
+        let status: SessionStatusRes =
+            serde_json::from_str(r#"{"status":"RUNNING","receipt_url":null}"#).unwrap();
+        assert_eq!(
+            status,
+            SessionStatusRes {
+                status: STATUS_RUNNING.into(),
+                receipt_url: None,
+                error_msg: None,
+            }
+        );
+    }
+
+    #[test]
+    fn receipt_round_trips_through_bincode() {
+        let receipt = Receipt {
+            journal: vec![1, 2, 3],
+            seal: vec![1, 256],
+        };
+        // Length-prefixed vectors, as bincode lays out the receipt of the zkVM.
+        let bytes = receipt.to_bincode();
+        assert_eq!(bytes[..8], 3u64.to_le_bytes());
+        assert_eq!(Receipt::from_bincode(&bytes).unwrap(), receipt);
+    }
+}
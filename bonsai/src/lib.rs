@@ -0,0 +1,344 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Client for the Bonsai REST API, to prove guests off-chain.
+//!
+//! [BonsaiClient] uploads images and inputs, starts proving sessions, polls them with exponential
+//! backoff and downloads their receipts. The routes and wire types are in [api].
+
+pub mod api;
+
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use ethers_core::types::H256;
+use log::debug;
+use reqwest::{Client, RequestBuilder, Response, StatusCode, Url};
+
+use crate::api::{
+    CreateSessRes, ImgUploadRes, ProofReq, Receipt, SessionId, SessionStatusRes, UploadRes,
+    STATUS_RUNNING, STATUS_SUCCEEDED,
+};
+
+/// Header carrying the API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Error returned by [BonsaiClient].
+#[derive(Debug)]
+pub enum BonsaiError {
+    /// The Bonsai URL is not a valid base URL.
+    InvalidUrl(String),
+    /// The request could not be sent or the response could not be read.
+    Http(reqwest::Error),
+    /// Bonsai rejected the API key.
+    Unauthorized,
+    /// The image, input or session does not exist.
+    NotFound(String),
+    /// Bonsai answered with an unexpected status.
+    Status { status: StatusCode, message: String },
+    /// Bonsai answered with a body the client does not understand.
+    InvalidResponse(String),
+    /// The session finished without a receipt.
+    SessionFailed { id: SessionId, error: String },
+    /// The session was still running when the client stopped polling.
+    Timeout { id: SessionId, elapsed: Duration },
+}
+
+impl fmt::Display for BonsaiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BonsaiError::InvalidUrl(url) => write!(f, "invalid Bonsai URL {url}"),
+            BonsaiError::Http(err) => write!(f, "request to Bonsai failed: {err}"),
+            BonsaiError::Unauthorized => write!(f, "Bonsai rejected the API key"),
+            BonsaiError::NotFound(path) => write!(f, "{path} not found on Bonsai"),
+            BonsaiError::Status { status, message } => {
+                write!(f, "Bonsai answered {status}: {message}")
+            }
+            BonsaiError::InvalidResponse(err) => write!(f, "invalid response from Bonsai: {err}"),
+            BonsaiError::SessionFailed { id, error } => {
+                write!(f, "session {id} failed: {error}")
+            }
+            BonsaiError::Timeout { id, elapsed } => {
+                write!(f, "session {id} still running after {elapsed:?}")
+            }
+        }
+    }
+}
+
+impl Error for BonsaiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BonsaiError::Http(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for BonsaiError {
+    fn from(err: reqwest::Error) -> Self {
+        BonsaiError::Http(err)
+    }
+}
+
+/// How often to poll a running session: from `initial`, multiplied by `factor` after every poll
+/// up to `max`, giving up after `timeout`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    pub factor: f64,
+    /// Time after which to give up, or `None` to poll forever.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(30),
+            factor: 2.0,
+            timeout: Some(Duration::from_secs(60 * 60)),
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay after `delay`.
+    fn next(&self, delay: Duration) -> Duration {
+        delay.mul_f64(self.factor).min(self.max)
+    }
+}
+
+/// Client for a Bonsai service, or a compatible stand-in.
+#[derive(Clone, Debug)]
+pub struct BonsaiClient {
+    http: Client,
+    url: Url,
+    api_key: String,
+    backoff: Backoff,
+}
+
+impl BonsaiClient {
+    pub fn new(url: &str, api_key: impl Into<String>) -> Result<Self, BonsaiError> {
+        let mut url = Url::parse(url).map_err(|_| BonsaiError::InvalidUrl(url.into()))?;
+        if url.cannot_be_a_base() {
+            return Err(BonsaiError::InvalidUrl(url.to_string()));
+        }
+        // Endpoints are joined onto the path of the URL, which must end in a slash to be kept.
+        if !url.path().ends_with('/') {
+            let path = format!("{}/", url.path());
+            url.set_path(&path);
+        }
+        Ok(Self {
+            http: Client::new(),
+            url,
+            api_key: api_key.into(),
+            backoff: Backoff::default(),
+        })
+    }
+
+    /// Poll running sessions with `backoff`.
+    pub fn with_backoff(self, backoff: Backoff) -> Self {
+        Self { backoff, ..self }
+    }
+
+    /// Upload `elf` under `image_id` unless Bonsai already has it. Returns `true` if Bonsai already
+    /// had the image and nothing was uploaded.
+    pub async fn upload_img(&self, image_id: H256, elf: &[u8]) -> Result<bool, BonsaiError> {
+        let path = format!("images/upload/{}", hex::encode(image_id));
+        let response = check(self.get(&path)?.send().await?).await?;
+        if response.status() == StatusCode::NO_CONTENT {
+            debug!("image {image_id:?} is already uploaded");
+            return Ok(true);
+        }
+        let upload: ImgUploadRes = response.json().await?;
+        self.put(&upload.url, elf).await?;
+        Ok(false)
+    }
+
+    /// Upload the input of a guest, returning the ID to create a session with.
+    pub async fn upload_input(&self, input: &[u8]) -> Result<String, BonsaiError> {
+        let response = check(self.get("inputs/upload")?.send().await?).await?;
+        let upload: UploadRes = response.json().await?;
+        self.put(&upload.url, input).await?;
+        Ok(upload.uuid)
+    }
+
+    /// Start proving the uploaded image `image_id` on the uploaded input `input_id`.
+    pub async fn create_session(
+        &self,
+        image_id: H256,
+        input_id: &str,
+    ) -> Result<SessionId, BonsaiError> {
+        let request = ProofReq {
+            img: hex::encode(image_id),
+            input: input_id.into(),
+        };
+        let response = self
+            .http
+            .post(self.endpoint("sessions/create")?)
+            .header(API_KEY_HEADER, &self.api_key)
+            .json(&request)
+            .send()
+            .await?;
+        let created: CreateSessRes = check(response).await?.json().await?;
+        Ok(SessionId(created.uuid))
+    }
+
+    pub async fn session_status(&self, id: &SessionId) -> Result<SessionStatusRes, BonsaiError> {
+        let response = self.get(&format!("sessions/status/{id}"))?.send().await?;
+        Ok(check(response).await?.json().await?)
+    }
+
+    /// Poll session `id` until it finishes, returning the URL of its receipt.
+    pub async fn wait(&self, id: &SessionId) -> Result<String, BonsaiError> {
+        let start = Instant::now();
+        let mut delay = self.backoff.initial;
+        loop {
+            let status = self.session_status(id).await?;
+            match status.status.as_str() {
+                STATUS_SUCCEEDED => {
+                    return status.receipt_url.ok_or_else(|| {
+                        BonsaiError::InvalidResponse(format!("session {id} has no receipt URL"))
+                    })
+                }
+                STATUS_RUNNING => {}
+                other => {
+                    let error = match status.error_msg {
+                        Some(msg) => format!("{other}: {msg}"),
+                        None => other.to_string(),
+                    };
+                    return Err(BonsaiError::SessionFailed {
+                        id: id.clone(),
+                        error,
+                    });
+                }
+            }
+            let elapsed = start.elapsed();
+            if let Some(timeout) = self.backoff.timeout {
+                if elapsed >= timeout {
+                    return Err(BonsaiError::Timeout {
+                        id: id.clone(),
+                        elapsed,
+                    });
+                }
+            }
+            debug!("session {id} is running, polling again in {delay:?}");
+            tokio::time::sleep(delay).await;
+            delay = self.backoff.next(delay);
+        }
+    }
+
+    /// Download the receipt of a finished session from `receipt_url`.
+    pub async fn receipt(&self, receipt_url: &str) -> Result<Receipt, BonsaiError> {
+        let url = Url::parse(receipt_url)
+            .map_err(|_| BonsaiError::InvalidResponse(format!("receipt URL {receipt_url}")))?;
+        let response = check(self.http.get(url).send().await?).await?;
+        let bytes = response.bytes().await?;
+        Receipt::from_bincode(&bytes).map_err(|err| BonsaiError::InvalidResponse(err.to_string()))
+    }
+
+    /// Upload the guest `elf` with image ID `image_id` and `input`, prove them in a new session
+    /// and download the receipt.
+    pub async fn prove(
+        &self,
+        image_id: H256,
+        elf: &[u8],
+        input: &[u8],
+    ) -> Result<Receipt, BonsaiError> {
+        self.upload_img(image_id, elf).await?;
+        let input_id = self.upload_input(input).await?;
+        let id = self.create_session(image_id, &input_id).await?;
+        debug!("started session {id}");
+        let receipt_url = self.wait(&id).await?;
+        self.receipt(&receipt_url).await
+    }
+
+    /// `GET` an endpoint of the API.
+    fn get(&self, path: &str) -> Result<RequestBuilder, BonsaiError> {
+        Ok(self
+            .http
+            .get(self.endpoint(path)?)
+            .header(API_KEY_HEADER, &self.api_key))
+    }
+
+    /// Upload `body` to a presigned `url`.
+    async fn put(&self, url: &str, body: &[u8]) -> Result<(), BonsaiError> {
+        let url = Url::parse(url)
+            .map_err(|_| BonsaiError::InvalidResponse(format!("upload URL {url}")))?;
+        let response = self
+            .http
+            .put(url)
+            .header("Content-Type", "application/octet-stream")
+            .body(body.to_vec())
+            .send()
+            .await?;
+        check(response).await?;
+        Ok(())
+    }
+
+    fn endpoint(&self, path: &str) -> Result<Url, BonsaiError> {
+        self.url
+            .join(path)
+            .map_err(|_| BonsaiError::InvalidUrl(format!("{}{path}", self.url)))
+    }
+}
+
+/// Map error statuses of `response` to a [BonsaiError].
+async fn check(response: Response) -> Result<Response, BonsaiError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let path = response.url().path().to_string();
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(BonsaiError::Unauthorized),
+        StatusCode::NOT_FOUND => Err(BonsaiError::NotFound(path)),
+        _ => {
+            let message = response.text().await.unwrap_or_default();
+            Err(BonsaiError::Status { status, message })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_up_to_max() {
+        let backoff = Backoff {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(5),
+            factor: 2.0,
+            timeout: None,
+        };
+        let mut delays = vec![backoff.initial];
+        for _ in 0..4 {
+            delays.push(backoff.next(*delays.last().unwrap()));
+        }
+        assert_eq!(delays, [1, 2, 4, 5, 5].map(Duration::from_secs).to_vec());
+    }
+
+    #[test]
+    fn joins_endpoints_onto_base_path() {
+        let client = BonsaiClient::new("http://localhost:8081/bonsai", "key").unwrap();
+        assert_eq!(
+            client.endpoint("sessions/create").unwrap().as_str(),
+            "http://localhost:8081/bonsai/sessions/create"
+        );
+        assert!(BonsaiClient::new("not a url", "key").is_err());
+    }
+}
@@ -0,0 +1,209 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! [BonsaiClient::prove] against a mock of the Bonsai REST API.
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bonsai_client::api::{
+    CreateSessRes, ImgUploadRes, ProofReq, Receipt, SessionStatusRes, UploadRes,
+};
+use bonsai_client::{Backoff, BonsaiClient, BonsaiError, API_KEY_HEADER};
+use ethers_core::types::H256;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde::Serialize;
+
+const API_KEY: &str = "test-key";
+const ELF: &[u8] = b"guest elf";
+const INPUT: &[u8] = b"guest input";
+const IMAGE_ID: H256 = H256([7; 32]);
+
+/// Scripted Bonsai, recording every request it serves.
+struct MockBonsai {
+    addr: Mutex<Option<SocketAddr>>,
+    has_image: bool,
+    /// Statuses to answer polls with, the last one repeating.
+    statuses: Vec<&'static str>,
+    receipt: Receipt,
+    log: Mutex<Vec<String>>,
+}
+
+impl MockBonsai {
+    async fn handle(&self, req: Request<Body>) -> Response<Body> {
+        let line = format!("{} {}", req.method(), req.uri().path());
+        let authorized = req
+            .headers()
+            .get(API_KEY_HEADER)
+            .map_or(false, |key| key == API_KEY);
+        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+        self.log.lock().unwrap().push(line.clone());
+
+        let base = format!("http://{}", self.addr.lock().unwrap().unwrap());
+        let image_path = format!("GET /images/upload/{}", hex::encode(IMAGE_ID));
+        match line.as_str() {
+            // Presigned URLs take no API key.
+            "PUT /upload/image" => {
+                assert_eq!(body, ELF);
+                status(StatusCode::OK)
+            }
+            "PUT /upload/input" => {
+                assert_eq!(body, INPUT);
+                status(StatusCode::OK)
+            }
+            "GET /receipt" => Response::new(Body::from(self.receipt.to_bincode())),
+            _ if !authorized => status(StatusCode::UNAUTHORIZED),
+            path if path == image_path && self.has_image => status(StatusCode::NO_CONTENT),
+            path if path == image_path => json(&ImgUploadRes {
+                url: format!("{base}/upload/image"),
+            }),
+            "GET /inputs/upload" => json(&UploadRes {
+                url: format!("{base}/upload/input"),
+                uuid: "input-1".into(),
+            }),
+            "POST /sessions/create" => {
+                let request: ProofReq = serde_json::from_slice(&body).unwrap();
+                assert_eq!(request.img, hex::encode(IMAGE_ID));
+                assert_eq!(request.input, "input-1");
+                json(&CreateSessRes {
+                    uuid: "session-1".into(),
+                })
+            }
+            "GET /sessions/status/session-1" => {
+                let polls = self
+                    .log
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .filter(|line| line.starts_with("GET /sessions/status"))
+                    .count();
+                let status = self.statuses[(polls - 1).min(self.statuses.len() - 1)];
+                json(&SessionStatusRes {
+                    status: status.into(),
+                    receipt_url: (status == "SUCCEEDED").then(|| format!("{base}/receipt")),
+                    error_msg: None,
+                })
+            }
+            _ => status(StatusCode::NOT_FOUND),
+        }
+    }
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+fn json<T: Serialize>(body: &T) -> Response<Body> {
+    Response::new(Body::from(serde_json::to_vec(body).unwrap()))
+}
+
+/// Serve `mock` on an ephemeral port, returning a client for it.
+fn serve(mock: Arc<MockBonsai>, api_key: &str) -> BonsaiClient {
+    let service = {
+        let mock = mock.clone();
+        make_service_fn(move |_| {
+            let mock = mock.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let mock = mock.clone();
+                    async move { Ok::<_, Infallible>(mock.handle(req).await) }
+                }))
+            }
+        })
+    };
+    let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(service);
+    let addr = server.local_addr();
+    *mock.addr.lock().unwrap() = Some(addr);
+    tokio::spawn(server);
+
+    BonsaiClient::new(&format!("http://{addr}"), api_key)
+        .unwrap()
+        .with_backoff(Backoff {
+            initial: Duration::from_millis(1),
+            max: Duration::from_millis(1),
+            factor: 1.0,
+            timeout: Some(Duration::from_secs(10)),
+        })
+}
+
+fn mock(has_image: bool, statuses: Vec<&'static str>) -> Arc<MockBonsai> {
+    Arc::new(MockBonsai {
+        addr: Mutex::new(None),
+        has_image,
+        statuses,
+        receipt: Receipt {
+            journal: vec![1, 2, 3],
+            seal: vec![4, 5],
+        },
+        log: Mutex::new(Vec::new()),
+    })
+}
+
+#[tokio::test]
+async fn prove_uploads_and_polls_until_succeeded() {
+    let mock = mock(false, vec!["RUNNING", "RUNNING", "SUCCEEDED"]);
+    let client = serve(mock.clone(), API_KEY);
+
+    let receipt = client.prove(IMAGE_ID, ELF, INPUT).await.unwrap();
+    assert_eq!(receipt, mock.receipt);
+
+    let image_path = format!("GET /images/upload/{}", hex::encode(IMAGE_ID));
+    assert_eq!(
+        *mock.log.lock().unwrap(),
+        [
+            image_path.as_str(),
+            "PUT /upload/image",
+            "GET /inputs/upload",
+            "PUT /upload/input",
+            "POST /sessions/create",
+            "GET /sessions/status/session-1",
+            "GET /sessions/status/session-1",
+            "GET /sessions/status/session-1",
+            "GET /receipt",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn prove_skips_uploaded_images_and_reports_failures() {
+    let mock = mock(true, vec!["RUNNING", "FAILED"]);
+    let client = serve(mock.clone(), API_KEY);
+
+    match client.prove(IMAGE_ID, ELF, INPUT).await {
+        Err(BonsaiError::SessionFailed { id, error }) => {
+            assert_eq!(id.0, "session-1");
+            assert_eq!(error, "FAILED");
+        }
+        other => panic!("expected a failed session, got {other:?}"),
+    }
+    assert!(!mock
+        .log
+        .lock()
+        .unwrap()
+        .contains(&"PUT /upload/image".to_string()));
+}
+
+#[tokio::test]
+async fn prove_rejects_wrong_api_key() {
+    let client = serve(mock(false, vec!["SUCCEEDED"]), "wrong-key");
+    assert!(matches!(
+        client.prove(IMAGE_ID, ELF, INPUT).await,
+        Err(BonsaiError::Unauthorized)
+    ));
+}
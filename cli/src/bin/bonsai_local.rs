@@ -0,0 +1,338 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use bonsai_client::api::{
+    CreateSessRes, ImgUploadRes, ProofReq, SessionId, SessionStatusRes, UploadRes, STATUS_FAILED,
+    STATUS_RUNNING, STATUS_SUCCEEDED,
+};
+use bonsai_client::API_KEY_HEADER;
+use clap::Parser;
+use ethers::types::H256;
+use hello_bonsai_methods::manifest::GuestManifest;
+use hello_bonsai_methods::prover::ProvingClient;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use risc0_zkvm::sha::Digest;
+use risc0_zkvm::Receipt;
+use serde::Serialize;
+use sha2::{Digest as _, Sha256};
+
+/// Serve the Bonsai REST API locally, proving sessions with the local zkVM.
+///
+/// Only guests of the guest manifest can be uploaded, and their ELFs must match it. State is kept
+/// in memory and lost on exit.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Address to listen on. Port 0 picks a free port, which is printed on startup.
+    #[clap(short, long, default_value = "127.0.0.1:8081")]
+    listen: SocketAddr,
+
+    /// Skip generating the seal, for fast end-to-end tests. Receipts will not verify.
+    #[clap(long)]
+    skip_seal: bool,
+
+    /// API key clients must send. Any key is accepted if not set.
+    #[clap(long, env = "BONSAI_API_KEY")]
+    api_key: Option<String>,
+
+    /// Guest manifest to check uploaded images against, e.g. one written by
+    /// `deploy --write-manifest`. Defaults to the manifest of the guests built into this tool.
+    #[clap(long)]
+    manifest: Option<PathBuf>,
+}
+
+enum Session {
+    Running,
+    Succeeded(Receipt),
+    Failed(String),
+}
+
+struct LocalBonsai {
+    api_key: Option<String>,
+    manifest: GuestManifest,
+    prover: ProvingClient,
+    images: Mutex<HashMap<H256, Vec<u8>>>,
+    /// Inputs by ID, `None` until uploaded.
+    inputs: Mutex<HashMap<String, Option<Vec<u8>>>>,
+    sessions: Mutex<HashMap<SessionId, Session>>,
+    next_id: AtomicU64,
+}
+
+impl LocalBonsai {
+    async fn handle(self: Arc<Self>, req: Request<Body>) -> Response<Body> {
+        let method = req.method().clone();
+        let path = req.uri().path().trim_matches('/').to_string();
+        // Upload and receipt URLs point back at this server, at the address the client used.
+        let base = match req
+            .headers()
+            .get(hyper::header::HOST)
+            .and_then(|host| host.to_str().ok())
+        {
+            Some(host) => format!("http://{host}"),
+            None => return error(StatusCode::BAD_REQUEST, "missing Host header"),
+        };
+        let authorized = self.authorized(&req);
+        let body = match hyper::body::to_bytes(req.into_body()).await {
+            Ok(body) => body.to_vec(),
+            Err(err) => return error(StatusCode::BAD_REQUEST, &err.to_string()),
+        };
+        println!("{method} /{path}");
+
+        let segments: Vec<&str> = path.split('/').collect();
+
+        // Stand-ins for the presigned URLs of Bonsai, which take no API key.
+        match (&method, segments.as_slice()) {
+            (&Method::PUT, ["upload", "images", id]) => return self.put_image(id, body),
+            (&Method::PUT, ["upload", "inputs", id]) => return self.put_input(id, body),
+            (&Method::GET, ["receipts", id]) => return self.receipt(id),
+            _ => {}
+        }
+        if !authorized {
+            return error(StatusCode::UNAUTHORIZED, "invalid API key");
+        }
+        match (&method, segments.as_slice()) {
+            (&Method::GET, ["images", "upload", id]) => match parse_image_id(id) {
+                Ok(id) if self.images.lock().unwrap().contains_key(&id) => {
+                    empty(StatusCode::NO_CONTENT)
+                }
+                Ok(id) => json(
+                    StatusCode::OK,
+                    &ImgUploadRes {
+                        url: format!("{base}/upload/images/{}", hex::encode(id)),
+                    },
+                ),
+                Err(response) => response,
+            },
+            (&Method::GET, ["inputs", "upload"]) => {
+                let uuid = self.new_id("input");
+                self.inputs.lock().unwrap().insert(uuid.clone(), None);
+                json(
+                    StatusCode::OK,
+                    &UploadRes {
+                        url: format!("{base}/upload/inputs/{uuid}"),
+                        uuid,
+                    },
+                )
+            }
+            (&Method::POST, ["sessions", "create"]) => self.create_session(&body),
+            (&Method::GET, ["sessions", "status", id]) => {
+                let sessions = self.sessions.lock().unwrap();
+                let (status, receipt_url, error_msg) =
+                    match sessions.get(&SessionId(id.to_string())) {
+                        Some(Session::Running) => (STATUS_RUNNING, None, None),
+                        Some(Session::Succeeded(_)) => (
+                            STATUS_SUCCEEDED,
+                            Some(format!("{base}/receipts/{id}")),
+                            None,
+                        ),
+                        Some(Session::Failed(err)) => (STATUS_FAILED, None, Some(err.clone())),
+                        None => return error(StatusCode::NOT_FOUND, "no such session"),
+                    };
+                json(
+                    StatusCode::OK,
+                    &SessionStatusRes {
+                        status: status.into(),
+                        receipt_url,
+                        error_msg,
+                    },
+                )
+            }
+            _ => error(StatusCode::NOT_FOUND, "no such endpoint"),
+        }
+    }
+
+    fn authorized(&self, req: &Request<Body>) -> bool {
+        let api_key = match &self.api_key {
+            Some(api_key) => api_key,
+            None => return true,
+        };
+        let header = req
+            .headers()
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok());
+        header == Some(api_key.as_str())
+    }
+
+    fn new_id(&self, kind: &str) -> String {
+        format!("{kind}-{}", self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Store the ELF of image `id`, which must be a guest of the manifest.
+    fn put_image(&self, id: &str, body: Vec<u8>) -> Response<Body> {
+        let id = match parse_image_id(id) {
+            Ok(id) => id,
+            Err(response) => return response,
+        };
+        let entry = match self
+            .manifest
+            .guests
+            .iter()
+            .find(|entry| entry.image_id == id)
+        {
+            Some(entry) => entry,
+            None => {
+                return error(
+                    StatusCode::BAD_REQUEST,
+                    "image is not a guest of the manifest",
+                )
+            }
+        };
+        if H256::from_slice(&Sha256::digest(&body)) != entry.elf_sha256 {
+            return error(StatusCode::BAD_REQUEST, "ELF does not match the manifest");
+        }
+        self.images.lock().unwrap().insert(id, body);
+        empty(StatusCode::OK)
+    }
+
+    fn put_input(&self, id: &str, body: Vec<u8>) -> Response<Body> {
+        match self.inputs.lock().unwrap().get_mut(id) {
+            Some(input) => {
+                *input = Some(body);
+                empty(StatusCode::OK)
+            }
+            None => error(StatusCode::NOT_FOUND, "no such input"),
+        }
+    }
+
+    fn receipt(&self, id: &str) -> Response<Body> {
+        match self
+            .sessions
+            .lock()
+            .unwrap()
+            .get(&SessionId(id.to_string()))
+        {
+            Some(Session::Succeeded(receipt)) => {
+                Response::new(Body::from(bincode::serialize(receipt).unwrap()))
+            }
+            Some(_) => error(StatusCode::CONFLICT, "session has no receipt"),
+            None => error(StatusCode::NOT_FOUND, "no such session"),
+        }
+    }
+
+    fn create_session(self: Arc<Self>, body: &[u8]) -> Response<Body> {
+        let request: ProofReq = match serde_json::from_slice(body) {
+            Ok(request) => request,
+            Err(err) => return error(StatusCode::BAD_REQUEST, &err.to_string()),
+        };
+        let image_id = match parse_image_id(&request.img) {
+            Ok(id) => id,
+            Err(response) => return response,
+        };
+        let elf = match self.images.lock().unwrap().get(&image_id) {
+            Some(elf) => elf.clone(),
+            None => return error(StatusCode::NOT_FOUND, "no such image"),
+        };
+        let input = match self.inputs.lock().unwrap().get(&request.input) {
+            Some(Some(input)) => input.clone(),
+            _ => return error(StatusCode::NOT_FOUND, "no such input"),
+        };
+
+        let id = SessionId(self.new_id("session"));
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(id.clone(), Session::Running);
+
+        let server = self.clone();
+        let session = id.clone();
+        tokio::task::spawn_blocking(move || {
+            let result = server
+                .prover
+                .run_elf(&elf, Digest::from(image_id.0), &input)
+                .map(|proof| proof.receipt)
+                .map_err(|err| err.to_string());
+            if let Err(err) = &result {
+                println!("Session {session} failed: {err}");
+            }
+            let state = match result {
+                Ok(receipt) => Session::Succeeded(receipt),
+                Err(err) => Session::Failed(err),
+            };
+            server.sessions.lock().unwrap().insert(session, state);
+        });
+        json(StatusCode::OK, &CreateSessRes { uuid: id.0 })
+    }
+}
+
+fn parse_image_id(id: &str) -> Result<H256, Response<Body>> {
+    id.parse()
+        .map_err(|_| error(StatusCode::BAD_REQUEST, "invalid image ID"))
+}
+
+fn empty(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+fn json<T: Serialize>(status: StatusCode, body: &T) -> Response<Body> {
+    let mut response = Response::new(Body::from(serde_json::to_vec(body).unwrap()));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(message.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+
+    let manifest = match &args.manifest {
+        Some(path) => GuestManifest::load(path)?,
+        None => GuestManifest::embedded(),
+    };
+    let server = Arc::new(LocalBonsai {
+        api_key: args.api_key,
+        manifest,
+        prover: ProvingClient::new().with_skip_seal(args.skip_seal),
+        images: Default::default(),
+        inputs: Default::default(),
+        sessions: Default::default(),
+        next_id: AtomicU64::new(1),
+    });
+
+    let service = make_service_fn(move |_| {
+        let server = server.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let server = server.clone();
+                async move { Ok::<_, Infallible>(server.handle(req).await) }
+            }))
+        }
+    });
+    let server = Server::try_bind(&args.listen)?.serve(service);
+    println!("Serving the Bonsai API on http://{}", server.local_addr());
+    if args.skip_seal {
+        println!("Skipping seals: receipts will not verify");
+    }
+    server.await?;
+    Ok(())
+}
@@ -0,0 +1,92 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use clap::{Parser, Subcommand};
+use ethers::types::H256;
+use hello_bonsai_methods::cache::ProofCache;
+use hello_bonsai_methods::GuestImage;
+use risc0_zkvm::sha::Digest;
+
+/// Inspect and clear the local proof cache.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Cache directory. Defaults to `hello-bonsai-proof-cache` in the system temporary directory.
+    #[clap(long, env = "PROOF_CACHE_DIR")]
+    dir: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List cached receipts, least recently used first.
+    List,
+    /// Remove cached receipts.
+    Purge {
+        /// Only remove receipts for this image ID, as hex.
+        #[clap(long, conflicts_with = "guest")]
+        image_id: Option<H256>,
+
+        /// Only remove receipts for this built-in guest, by name.
+        #[clap(long)]
+        guest: Option<String>,
+    },
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let cache = match args.dir {
+        Some(dir) => ProofCache::new(dir),
+        None => ProofCache::from_env(),
+    };
+
+    match args.command {
+        Command::List => {
+            let entries = cache.entries()?;
+            println!("{} receipts in {}", entries.len(), cache.dir().display());
+            for entry in entries {
+                let guest = GuestImage::from_image_id(entry.key.image_id.0)
+                    .map_or("unknown", GuestImage::name);
+                let age = SystemTime::now()
+                    .duration_since(entry.last_used)
+                    .unwrap_or_default();
+                println!("{:?} {:?}", entry.key.image_id, entry.key.input_hash);
+                println!(
+                    "    Guest: {guest}, size: {} bytes, last used {}s ago",
+                    entry.size,
+                    age.as_secs()
+                );
+            }
+        }
+        Command::Purge { image_id, guest } => {
+            let image_id = match guest {
+                Some(name) => {
+                    let guest = GuestImage::from_name(&name)
+                        .ok_or_else(|| format!("unknown guest: {name}"))?;
+                    Some(H256(Digest::from(guest.image_id()).into()))
+                }
+                None => image_id,
+            };
+            let removed = cache.purge(image_id)?;
+            println!("Removed {removed} receipts from {}", cache.dir().display());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,105 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use clap::Parser;
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::types::Address;
+use hello_bonsai_contracts::image::deployed_image_ids;
+use hello_bonsai_contracts::L3L1Escrow;
+use hello_bonsai_methods::deployment::{Deployment, DEFAULT_DEPLOYMENTS_DIR};
+use hello_bonsai_methods::manifest::GuestManifest;
+
+/// Check that a deployed L3L1Escrow trusts the image IDs of the local build of the guests.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// JSON RPC URL for an Ethereum node that will serve call requests.
+    /// Currently only HTTP(S) URLs are supported.
+    #[clap(short = 'e', long, env, value_hint = clap::ValueHint::Url)]
+    ethereum_node_url: String,
+
+    /// L3L1Escrow contract address. Defaults to the escrow deployed to `--network`.
+    #[clap(short = 'a', long, env, required_unless_present = "network")]
+    escrow_contract_address: Option<Address>,
+
+    /// Network to check the deployment of, as recorded by `deploy`.
+    #[clap(short = 'n', long, env)]
+    network: Option<String>,
+
+    /// Directory of the deployment records.
+    #[clap(long, env, default_value = DEFAULT_DEPLOYMENTS_DIR)]
+    deployments_dir: PathBuf,
+
+    /// Guest manifest to check against, e.g. one written by `deploy --write-manifest`. Defaults to
+    /// the manifest of the guests built into this tool.
+    #[clap(long)]
+    manifest: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+
+    let manifest = match &args.manifest {
+        Some(path) => GuestManifest::load(path)?,
+        None => GuestManifest::embedded(),
+    };
+    println!(
+        "Checking against guests built with RISC Zero {} ({} profile)",
+        manifest.risc0_version, manifest.profile
+    );
+
+    let deployment = match &args.network {
+        Some(network) => Some(Deployment::load(&args.deployments_dir, network)?),
+        None => None,
+    };
+    let escrow_address = args
+        .escrow_contract_address
+        .or(deployment.as_ref().map(|deployment| deployment.escrow))
+        .ok_or("--escrow-contract-address or --network is required")?;
+
+    let provider = Arc::new(Provider::<Http>::try_from(&args.ethereum_node_url)?);
+    if let Some(deployment) = &deployment {
+        deployment.check_chain(provider.get_chainid().await?.as_u64())?;
+    }
+    let escrow = L3L1Escrow::new(escrow_address, provider);
+    let deployed = deployed_image_ids(&escrow).await?;
+    for (guest, image_id) in &deployed {
+        println!("    {guest:<16} {image_id:?}");
+    }
+
+    let mismatches = manifest.mismatches(deployed);
+    for mismatch in &mismatches {
+        match mismatch.expected {
+            Some(expected) => println!(
+                "Mismatch for {}: local build has image ID {:?}",
+                mismatch.name, expected
+            ),
+            None => println!("Mismatch for {}: not in the local build", mismatch.name),
+        }
+    }
+    if !mismatches.is_empty() {
+        return Err(format!(
+            "{} of the deployed image IDs do not match the local build",
+            mismatches.len()
+        )
+        .into());
+    }
+    println!("All deployed image IDs match the local build");
+    Ok(())
+}
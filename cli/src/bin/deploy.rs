@@ -13,19 +13,23 @@
 // limitations under the License.
 
 use std::error::Error;
-use std::ops::Deref;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use bonsai_client::BonsaiClient;
 use clap::Parser;
 use ethers::middleware::SignerMiddleware;
 use ethers::providers::{Http, Middleware, Provider};
 use ethers::signers::{LocalWallet, Signer};
-use ethers::types::{Address, H256};
-use hello_bonsai_contracts::HelloBonsai;
-use hello_bonsai_methods::{EVM_ELF, EVM_ID};
-use reqwest::{Client, Url};
-use risc0_zkvm::sha::{self, Digest, Sha256};
+use ethers::types::Address;
+use hello_bonsai_contracts::deploy::{deploy_escrow_with_receipt, EscrowImageIds, L3Chain};
+use hello_bonsai_methods::deployment::{
+    Deployment, DEFAULT_DEPLOYMENTS_DIR, DEPLOYMENT_FILE_VERSION,
+};
+use hello_bonsai_methods::manifest::GuestManifest;
+use hello_bonsai_methods::GuestImage;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -39,9 +43,19 @@ struct Args {
     #[clap(short = 'b', long, env, value_hint = clap::ValueHint::Url)]
     bonsai_url: String,
 
-    /// Ethereum contract address for the Bonsai proxy.
+    /// Ethereum contract address for the Bonsai proxy. Defaults to the proxy of the existing
+    /// deployment to the network, when redeploying.
     #[clap(short = 'p', long, env)]
-    bonsai_proxy_contract_address: Address,
+    bonsai_proxy_contract_address: Option<Address>,
+
+    /// Chain ID of the L3 payments are made on.
+    #[clap(long, env)]
+    l3_chain_id: u64,
+
+    /// Address of the `IL3BlockHashOracle` trusted for the hashes of L3 blocks, such as the output
+    /// oracle of the L3.
+    #[clap(long, env)]
+    l3_block_hash_oracle: Address,
 
     /// API Key for Bonsai to authorize requests.
     #[clap(env, long)]
@@ -52,31 +66,38 @@ struct Args {
     // https://docs.rs/ethers/latest/ethers/signers/index.html
     #[clap(env, long)]
     ethereum_private_key: String,
-}
 
-async fn upload_elf(
-    bonsai_url: &Url,
-    bonsai_api_key: &str,
-    elf: &'static [u8],
-) -> Result<String, Box<dyn Error>> {
-    let elf_hash = hex::encode(sha::Impl::hash_bytes(elf).deref());
-    Client::new()
-        .put(bonsai_url.join(&format!("/v1/elf/{elf_hash}"))?)
-        .bearer_auth(bonsai_api_key)
-        .header("Content-Type", "application/octet-stream")
-        .header("Expect", "100-continue")
-        .body(elf)
-        .send()
-        .await?
-        .error_for_status()?;
-
-    Ok(elf_hash)
+    /// Name of the network to record the deployment under, e.g. `sepolia`. Replaces the existing
+    /// deployment to the network, if any.
+    #[clap(short = 'n', long, env)]
+    network: String,
+
+    /// Directory of the deployment records.
+    #[clap(long, env, default_value = DEFAULT_DEPLOYMENTS_DIR)]
+    deployments_dir: PathBuf,
+
+    /// Also write the manifest of the deployed guests to this file, for tools outside this
+    /// workspace.
+    #[clap(long)]
+    write_manifest: Option<PathBuf>,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
 
+    let previous = match Deployment::path(&args.deployments_dir, &args.network)?.exists() {
+        true => Some(Deployment::load(&args.deployments_dir, &args.network)?),
+        false => None,
+    };
+    let proxy = args
+        .bonsai_proxy_contract_address
+        .or(previous.as_ref().map(|deployment| deployment.proxy))
+        .ok_or(format!(
+            "no Bonsai proxy address given, and no deployment to {} to take it from",
+            args.network
+        ))?;
+
     // Create a wallet and connection to the Ethereum node.
     let wallet = LocalWallet::from_str(args.ethereum_private_key.trim_start_matches("0x"))?;
     let provider = Provider::<Http>::try_from(&args.ethereum_node_url)?;
@@ -84,36 +105,86 @@ async fn main() -> Result<(), Box<dyn Error>> {
     println!("Loaded private key for Ethereum wallet");
     println!("    Wallet address: {:?}", wallet.address());
 
+    // A proxy taken from the existing deployment is only valid on the same chain.
+    if let (None, Some(previous)) = (args.bonsai_proxy_contract_address, &previous) {
+        previous.check_chain(chain_id.as_u64())?;
+    }
+
     let client = Arc::new(SignerMiddleware::new(
         provider,
         wallet.with_chain_id(chain_id.as_u64()),
     ));
 
-    // Upload the guest binary to Bonsai.
-    println!("Uploading guest binary to Bonsai...");
-    let elf_hash = upload_elf(
-        &Url::parse(&args.bonsai_url)?,
-        &args.bonsai_api_key,
-        EVM_ELF,
-    )
-    .await?;
-    println!("Uploaded guest binary");
-    println!("    SHA-256:  {}", elf_hash);
-    println!("    Image ID: {}", hex::encode(Digest::from(EVM_ID)));
-
-    // Deploy the HelloBonsai contract.
-    println!("Deploying guest binary to Bonsai...");
-    let hello_bonsai = HelloBonsai::deploy(
-        client.clone(),
-        (
-            args.bonsai_proxy_contract_address,
-            H256(Digest::from(EVM_ID).into()),
-        ),
-    )?
-    .send()
-    .await?;
-    println!("Deployed HelloBonsai contract at");
-    println!("    HelloBonsai address: {:?}", hello_bonsai.address());
+    // Upload the guest binaries to Bonsai, one per proof type.
+    let manifest = GuestManifest::embedded();
+    println!(
+        "Deploying guests built with RISC Zero {} ({} profile)",
+        manifest.risc0_version, manifest.profile
+    );
+    let bonsai = BonsaiClient::new(&args.bonsai_url, &args.bonsai_api_key)?;
+    for guest in GuestImage::ALL {
+        let entry = manifest
+            .get(guest.name())
+            .ok_or_else(|| format!("{} guest is missing from the manifest", guest.name()))?;
+        println!("Uploading {} guest binary to Bonsai...", guest.name());
+        match bonsai.upload_img(entry.image_id, guest.elf()).await? {
+            true => println!("Bonsai already has the guest binary"),
+            false => println!("Uploaded guest binary"),
+        }
+        println!("    SHA-256:  {:?}", entry.elf_sha256);
+        println!("    Image ID: {:?}", entry.image_id);
+    }
+
+    // Deploy the L3L1Escrow contract, trusting the image IDs of the uploaded guests.
+    let image_ids = EscrowImageIds::from_guests(|name| {
+        manifest
+            .get(name)
+            .map(|entry| entry.image_id)
+            .ok_or(format!("{name} guest is missing from the manifest"))
+    })?;
+    println!("Deploying L3L1Escrow contract...");
+    let l3 = L3Chain {
+        chain_id: args.l3_chain_id,
+        block_hashes: args.l3_block_hash_oracle,
+    };
+    let (escrow, receipt) =
+        deploy_escrow_with_receipt(client.clone(), proxy, l3, &image_ids).await?;
+    println!("Deployed L3L1Escrow contract at");
+    println!("    L3L1Escrow address: {:?}", escrow.address());
+
+    // Record the deployment, so other commands can find it by network name.
+    let deployment = Deployment {
+        version: DEPLOYMENT_FILE_VERSION,
+        network: args.network.clone(),
+        chain_id: chain_id.as_u64(),
+        escrow: escrow.address(),
+        proxy,
+        l3_chain_id: l3.chain_id,
+        l3_block_hash_oracle: l3.block_hashes,
+        guests: manifest.guests.clone(),
+        tx_hash: receipt.transaction_hash,
+        block_number: receipt
+            .block_number
+            .ok_or("deployment receipt has no block number")?
+            .as_u64(),
+        deployer: client.address(),
+        deployed_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    };
+    let path = deployment.save(&args.deployments_dir)?;
+    if let Some(previous) = previous {
+        println!(
+            "Replaced deployment to {} of L3L1Escrow at {:?}",
+            args.network, previous.escrow
+        );
+    }
+    println!("Saved deployment to {}", path.display());
+    if let Some(path) = &args.write_manifest {
+        manifest.save(path)?;
+        println!("Saved guest manifest to {}", path.display());
+    }
 
     Ok(())
 }
@@ -0,0 +1,476 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use ethers::contract::parse_log;
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{Address, H256, U256};
+use hello_bonsai_contracts::{
+    AssetKind, CrosschainPaymentReceivedFilter, DepositCreatedFilter, DepositRefundedFilter,
+    L3L1Escrow, L3Payment, PaymentRejectedFilter,
+};
+use hello_bonsai_methods::deployment::{Deployment, DEFAULT_DEPLOYMENTS_DIR};
+use hello_bonsai_methods::witness::{fetch_payment_witness, witness_input};
+use serde::Serialize;
+
+/// Asset the deposit asks to be paid in on L3.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Kind {
+    Native,
+    Erc20,
+    Erc721,
+    Erc1155,
+}
+
+impl From<Kind> for AssetKind {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::Native => AssetKind::Native,
+            Kind::Erc20 => AssetKind::Erc20,
+            Kind::Erc721 => AssetKind::Erc721,
+            Kind::Erc1155 => AssetKind::Erc1155,
+        }
+    }
+}
+
+/// Make, settle and inspect deposits of an L3L1Escrow contract.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// JSON RPC URL for an Ethereum node that will serve call and transaction requests.
+    /// Currently only HTTP(S) URLs are supported.
+    #[clap(short = 'e', long, env, value_hint = clap::ValueHint::Url)]
+    ethereum_node_url: String,
+
+    /// L3L1Escrow contract address. Defaults to the escrow deployed to `--network`.
+    #[clap(short = 'a', long, env, required_unless_present = "network")]
+    escrow_contract_address: Option<Address>,
+
+    /// Network to use the deployment of, as recorded by `deploy`.
+    #[clap(short = 'n', long, env)]
+    network: Option<String>,
+
+    /// Directory of the deployment records.
+    #[clap(long, env, default_value = DEFAULT_DEPLOYMENTS_DIR)]
+    deployments_dir: PathBuf,
+
+    /// L1 block to search for deposits from. Defaults to the deployment block of the escrow
+    /// deployed to `--network`, and is required by `status` and `list` without one.
+    #[clap(long, env)]
+    from_block: Option<u64>,
+
+    /// Ethereum private key to use for sending transactions. Only needed to send transactions.
+    // NOTE: Provided as an example and for testing. Integrate your preferred key management.
+    // https://docs.rs/ethers/latest/ethers/signers/index.html
+    #[clap(env, long)]
+    ethereum_private_key: Option<String>,
+
+    /// Print the result as JSON instead of text.
+    #[clap(long, global = true)]
+    json: bool,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Deposit wei for a creditor, released once they pay the depositor on L3.
+    Deposit {
+        /// Creditor who receives the deposit once they pay on L3.
+        #[clap(long)]
+        creditor: Address,
+        /// Wei to deposit.
+        #[clap(long, value_parser = parse_u256)]
+        amount: U256,
+        /// Asset the creditor must pay in on L3.
+        #[clap(long, value_enum, default_value = "native")]
+        kind: Kind,
+        /// Token contract or NFT collection on L3, required unless paid in the native currency.
+        #[clap(long)]
+        token: Option<Address>,
+        /// Token ID for ERC-721 and ERC-1155 payments.
+        #[clap(long, default_value = "0", value_parser = parse_u256)]
+        token_id: U256,
+        /// Value or token amount the creditor must pay on L3, one for ERC-721 tokens.
+        #[clap(long, value_parser = parse_u256)]
+        l3_amount: U256,
+    },
+    /// Prove that an L3 transaction settles a deposit and wait for the Bonsai callback.
+    Claim {
+        /// Hash of the L3 payment.
+        l3_tx_hash: H256,
+        /// ID of the deposit the payment settles.
+        #[clap(long, value_parser = parse_u256)]
+        escrow_id: U256,
+        /// JSON RPC URL for the L3 node the payment was sent on, to build the witness from.
+        #[clap(long, env, value_hint = clap::ValueHint::Url)]
+        l3_node_url: String,
+        /// Seconds to wait for the callback.
+        #[clap(long, default_value = "600")]
+        timeout: u64,
+    },
+    /// Show the deposits made by a depositor.
+    Status { depositor: Address },
+    /// Return an unsettled deposit to its depositor once no claim can settle it.
+    Refund {
+        #[clap(long, value_parser = parse_u256)]
+        escrow_id: U256,
+    },
+    /// List the open deposits.
+    List,
+}
+
+// `U256::from_str` reads hex, but amounts and IDs are given in decimal.
+fn parse_u256(value: &str) -> Result<U256, String> {
+    U256::from_dec_str(value).map_err(|err| err.to_string())
+}
+
+type Escrow = L3L1Escrow<SignerMiddleware<Provider<Http>, LocalWallet>>;
+
+/// A deposit as recorded by `DepositCreated`, with whether it is still open.
+#[derive(Debug, Serialize)]
+struct DepositInfo {
+    escrow_id: U256,
+    depositor: Address,
+    creditor: Address,
+    amount: U256,
+    kind: String,
+    token: Address,
+    token_id: U256,
+    l3_amount: U256,
+    /// Whether the deposit is neither settled nor refunded.
+    open: bool,
+    /// Window for the timestamp of the L3 payment, if the deposit is open.
+    earliest_time: Option<U256>,
+    latest_time: Option<U256>,
+}
+
+impl DepositInfo {
+    async fn load(escrow: &Escrow, event: DepositCreatedFilter) -> Result<Self, Box<dyn Error>> {
+        let (_, _, amount, _, earliest_time, latest_time) =
+            escrow.deposits(event.escrow_id).call().await?;
+        let open = !amount.is_zero();
+        let kind = match AssetKind::from_u8(event.l3.kind) {
+            Some(kind) => format!("{kind:?}"),
+            None => format!("unknown ({})", event.l3.kind),
+        };
+        Ok(Self {
+            escrow_id: event.escrow_id,
+            depositor: event.depositor,
+            creditor: event.creditor,
+            amount: event.amount,
+            kind,
+            token: event.l3.token,
+            token_id: event.l3.token_id,
+            l3_amount: event.l3.amount,
+            open,
+            earliest_time: open.then_some(earliest_time),
+            latest_time: open.then_some(latest_time),
+        })
+    }
+
+    fn print(&self) {
+        let state = match self.open {
+            true => "open",
+            false => "closed",
+        };
+        println!("Deposit {} ({state})", self.escrow_id);
+        println!("    Depositor: {:?}", self.depositor);
+        println!("    Creditor:  {:?}", self.creditor);
+        println!("    Amount:    {} wei", self.amount);
+        println!(
+            "    L3:        {} {} of {:?}",
+            self.l3_amount, self.kind, self.token
+        );
+        if let (Some(earliest), Some(latest)) = (self.earliest_time, self.latest_time) {
+            println!("    Window:    {earliest} to {latest}");
+        }
+    }
+}
+
+/// Outcome of a claim, from the event the callback emitted.
+#[derive(Debug, Serialize)]
+#[serde(tag = "result", rename_all = "lowercase")]
+enum ClaimResult {
+    Settled {
+        escrow_id: U256,
+        creditor: Address,
+        amount: U256,
+    },
+    Rejected {
+        escrow_id: U256,
+        status: u8,
+        reason: String,
+    },
+}
+
+/// A refund, from `DepositRefunded`.
+#[derive(Debug, Serialize)]
+struct RefundInfo {
+    escrow_id: U256,
+    depositor: Address,
+    amount: U256,
+}
+
+fn output<T: Serialize>(json: bool, value: &T, print: impl FnOnce(&T)) {
+    match json {
+        true => println!("{}", serde_json::to_string_pretty(value).unwrap()),
+        false => print(value),
+    }
+}
+
+/// Blocks to query logs over at once, as nodes limit the range of `eth_getLogs`.
+const LOG_PAGE_SIZE: u64 = 10_000;
+
+/// Deposits made since block `from_block`, by `depositor` if given.
+async fn deposits(
+    escrow: &Escrow,
+    from_block: u64,
+    depositor: Option<Address>,
+) -> Result<Vec<DepositInfo>, Box<dyn Error>> {
+    let latest = escrow.client().get_block_number().await?.as_u64();
+    let mut deposits = Vec::new();
+    let mut start = from_block;
+    while start <= latest {
+        let end = latest.min(start + LOG_PAGE_SIZE - 1);
+        let mut filter = escrow
+            .deposit_created_filter()
+            .from_block(start)
+            .to_block(end);
+        if let Some(depositor) = depositor {
+            filter = filter.topic2(H256::from(depositor));
+        }
+        for event in filter.query().await? {
+            deposits.push(DepositInfo::load(escrow, event).await?);
+        }
+        start = end + 1;
+    }
+    Ok(deposits)
+}
+
+/// Poll for the event settling `escrow_id`, or rejecting the claim on it for the L3 transaction
+/// `tx_hash`, from `from_block` on. Rejections of other claims on the same deposit are ignored, but
+/// any settlement is final.
+async fn wait_for_callback(
+    escrow: &Escrow,
+    escrow_id: U256,
+    tx_hash: H256,
+    from_block: u64,
+    timeout: Duration,
+) -> Result<ClaimResult, Box<dyn Error>> {
+    let start = Instant::now();
+    loop {
+        let settled: Vec<CrosschainPaymentReceivedFilter> = escrow
+            .crosschain_payment_received_filter()
+            .topic1(H256::from_uint(&escrow_id))
+            .from_block(from_block)
+            .query()
+            .await?;
+        if let Some(event) = settled.into_iter().next() {
+            return Ok(ClaimResult::Settled {
+                escrow_id,
+                creditor: event.recipient,
+                amount: event.amount,
+            });
+        }
+        let rejected: Vec<PaymentRejectedFilter> = escrow
+            .payment_rejected_filter()
+            .topic1(H256::from_uint(&escrow_id))
+            .topic2(tx_hash)
+            .from_block(from_block)
+            .query()
+            .await?;
+        if let Some(event) = rejected.into_iter().next() {
+            return Ok(ClaimResult::Rejected {
+                escrow_id,
+                status: event.status,
+                reason: event.reason,
+            });
+        }
+        if start.elapsed() > timeout {
+            return Err(format!("no callback for deposit {escrow_id} after {timeout:?}").into());
+        }
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+
+    let deployment = match &args.network {
+        Some(network) => Some(Deployment::load(&args.deployments_dir, network)?),
+        None => None,
+    };
+    let escrow_address = args
+        .escrow_contract_address
+        .or(deployment.as_ref().map(|deployment| deployment.escrow))
+        .ok_or("--escrow-contract-address or --network is required")?;
+    // Deposits can only have been made after the escrow was deployed. Without a record of the
+    // deployment, the caller must say where to start rather than scanning from genesis.
+    let from_block = args
+        .from_block
+        .or(deployment
+            .as_ref()
+            .map(|deployment| deployment.block_number))
+        .ok_or("--from-block or --network is required to search for deposits");
+
+    let provider = Provider::<Http>::try_from(&args.ethereum_node_url)?;
+    let chain_id = provider.get_chainid().await?;
+    if let Some(deployment) = &deployment {
+        deployment.check_chain(chain_id.as_u64())?;
+    }
+    // Read-only commands run without a key, with a random wallet that never signs.
+    let wallet = match &args.ethereum_private_key {
+        Some(key) => LocalWallet::from_str(key.trim_start_matches("0x"))?,
+        None => LocalWallet::new(&mut ethers::core::rand::thread_rng()),
+    };
+    let signing = args.ethereum_private_key.is_some();
+    let client = Arc::new(SignerMiddleware::new(
+        provider,
+        wallet.with_chain_id(chain_id.as_u64()),
+    ));
+    let escrow = L3L1Escrow::new(escrow_address, client.clone());
+    let require_key = || match signing {
+        true => Ok(()),
+        false => Err("--ethereum-private-key is required to send transactions"),
+    };
+
+    match args.command {
+        Command::Deposit {
+            creditor,
+            amount,
+            kind,
+            token,
+            token_id,
+            l3_amount,
+        } => {
+            require_key()?;
+            let l3 = L3Payment {
+                kind: AssetKind::from(kind) as u8,
+                token: token.unwrap_or_default(),
+                token_id,
+                amount: l3_amount,
+            };
+            let receipt = escrow
+                .deposit(creditor, amount, l3)
+                .value(amount)
+                .send()
+                .await?
+                .await?
+                .ok_or("no receipt from transaction")?;
+            let event = receipt
+                .logs
+                .into_iter()
+                .find_map(|log| parse_log::<DepositCreatedFilter>(log).ok())
+                .ok_or("deposit emitted no DepositCreated event")?;
+            let deposit = DepositInfo::load(&escrow, event).await?;
+            output(args.json, &deposit, DepositInfo::print);
+        }
+        Command::Claim {
+            l3_tx_hash,
+            escrow_id,
+            l3_node_url,
+            timeout,
+        } => {
+            require_key()?;
+            let l3 = Provider::<Http>::try_from(&l3_node_url)?;
+            eprintln!("Building witness for {l3_tx_hash:?}...");
+            let witness = witness_input(&fetch_payment_witness(&l3, l3_tx_hash).await?);
+            eprintln!("Sending L3L1Escrow.checkPaymentStatus...");
+            let receipt = escrow
+                .check_payment_status(escrow_id, l3_tx_hash.into(), witness.into())
+                .send()
+                .await?
+                .await?
+                .ok_or("no receipt from transaction")?;
+            let block = receipt.block_number.ok_or("receipt has no block")?;
+            eprintln!("Waiting for the callback from Bonsai...");
+            let result = wait_for_callback(
+                &escrow,
+                escrow_id,
+                l3_tx_hash,
+                block.as_u64(),
+                Duration::from_secs(timeout),
+            )
+            .await?;
+            output(args.json, &result, |result| match result {
+                ClaimResult::Settled {
+                    escrow_id,
+                    creditor,
+                    amount,
+                } => println!("Deposit {escrow_id} settled: {amount} wei sent to {creditor:?}"),
+                ClaimResult::Rejected {
+                    escrow_id,
+                    status,
+                    reason,
+                } => println!("Claim on deposit {escrow_id} rejected: {reason} (status {status})"),
+            });
+        }
+        Command::Status { depositor } => {
+            let deposits = deposits(&escrow, from_block?, Some(depositor)).await?;
+            output(args.json, &deposits, |deposits| {
+                if deposits.is_empty() {
+                    println!("No deposits by {depositor:?}");
+                }
+                deposits.iter().for_each(DepositInfo::print);
+            });
+        }
+        Command::Refund { escrow_id } => {
+            require_key()?;
+            let receipt = escrow
+                .refund(escrow_id)
+                .send()
+                .await?
+                .await?
+                .ok_or("no receipt from transaction")?;
+            let event = receipt
+                .logs
+                .into_iter()
+                .find_map(|log| parse_log::<DepositRefundedFilter>(log).ok())
+                .ok_or("refund emitted no DepositRefunded event")?;
+            let refund = RefundInfo {
+                escrow_id: event.escrow_id,
+                depositor: event.depositor,
+                amount: event.amount,
+            };
+            output(args.json, &refund, |refund| {
+                println!(
+                    "Refunded {} wei of deposit {} to {:?}",
+                    refund.amount, refund.escrow_id, refund.depositor
+                )
+            });
+        }
+        Command::List => {
+            let mut deposits = deposits(&escrow, from_block?, None).await?;
+            deposits.retain(|deposit| deposit.open);
+            output(args.json, &deposits, |deposits| {
+                if deposits.is_empty() {
+                    println!("No open deposits");
+                }
+                deposits.iter().for_each(DepositInfo::print);
+            });
+        }
+    }
+    Ok(())
+}
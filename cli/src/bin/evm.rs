@@ -0,0 +1,432 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Host for the EVM replay guest.
+//!
+//! Each subcommand exits with one of the codes of [Exit], so scripts can tell a reverted
+//! transaction from an invalid receipt or a failing RPC node without parsing the output. With
+//! `--json`, the execution result is printed to stdout as a [ResultJson] and everything else goes to
+//! stderr.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand};
+use ethers::types::H256;
+use evm_core::ether_trace::{Http, Provider};
+use evm_core::EvmResult;
+use hello_bonsai_methods::cache::ProofCache;
+use hello_bonsai_methods::evm::{preflight, EvmWitness, Outcome, Preflight, ResultJson};
+use hello_bonsai_methods::jobs::{run_jobs, Job, JobOptions};
+use hello_bonsai_methods::manifest::GuestManifest;
+use hello_bonsai_methods::prover::ProvingClient;
+use hello_bonsai_methods::receipt::ReceiptFile;
+use hello_bonsai_methods::GuestImage;
+use log::{info, warn};
+use risc0_zkvm::serde::from_slice;
+use risc0_zkvm::sha::Digest;
+use serde::Serialize;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+    /// Print the execution result as JSON on stdout, with accounts and storage slots in canonical
+    /// order, and everything else on stderr.
+    #[clap(long, global = true)]
+    json: bool,
+}
+
+/// Set by `--json`.
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Print human-readable output: on stdout, or on stderr with `--json` so stdout holds only JSON.
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if JSON.load(Ordering::Relaxed) {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// Print `result` on stdout with `--json`.
+fn print_json(result: ResultJson) {
+    if JSON.load(Ordering::Relaxed) {
+        println!("{}", result.to_json());
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Execute a transaction against the L3 node and print how it ended.
+    Trace(TxArgs),
+    /// Execute a transaction and save the witness the guest reads.
+    Witness {
+        #[clap(flatten)]
+        tx: TxArgs,
+        /// Path to save the witness to.
+        #[clap(short, long)]
+        out: PathBuf,
+    },
+    /// Prove a transaction, from the L3 node or a saved witness.
+    Prove(ProveArgs),
+    /// Prove every transaction or witness in a job list, one receipt per job.
+    Jobs(JobsArgs),
+    /// Check a saved receipt of the EVM replay guest.
+    Verify {
+        /// Receipt file, as saved by `prove --receipt`.
+        receipt: PathBuf,
+        /// Image ID to verify against, as hex. Defaults to the EVM replay guest of this build.
+        #[clap(long)]
+        image_id: Option<H256>,
+    },
+    /// Print a summary of a saved witness or receipt.
+    Inspect {
+        /// Witness or receipt file.
+        path: PathBuf,
+    },
+}
+
+#[derive(Args, Debug)]
+struct TxArgs {
+    #[clap(short, long)]
+    tx_hash: H256,
+    #[clap(short, long)]
+    rpc_url: String,
+}
+
+#[derive(Args, Debug)]
+struct ProveArgs {
+    #[clap(short, long, requires = "rpc_url", required_unless_present = "witness")]
+    tx_hash: Option<H256>,
+    #[clap(short, long)]
+    rpc_url: Option<String>,
+    /// Prove from a witness saved with the `witness` command instead of querying the L3 node.
+    #[clap(short, long, conflicts_with_all = ["tx_hash", "rpc_url"])]
+    witness: Option<PathBuf>,
+    /// Write the run report as JSON to this path in addition to logging it.
+    #[clap(long)]
+    report: Option<PathBuf>,
+    /// Save the receipt to this path, for offline verification with the `verify` command.
+    #[clap(long)]
+    receipt: Option<PathBuf>,
+    /// Always run the zkVM, instead of reusing a receipt from the proof cache.
+    #[clap(long)]
+    no_cache: bool,
+}
+
+#[derive(Args, Debug)]
+struct JobsArgs {
+    /// Job list: one transaction hash or witness path per line.
+    jobs: PathBuf,
+    /// L3 node to run transactions in preflight against. Not needed if every job is a witness.
+    #[clap(short, long)]
+    rpc_url: Option<String>,
+    /// Directory to write a receipt per job and `summary.json` to.
+    #[clap(short, long)]
+    out_dir: PathBuf,
+    /// Maximum number of preflights querying the L3 node at once.
+    #[clap(long, default_value_t = 4)]
+    rpc_concurrency: usize,
+    /// Maximum number of jobs proving at once. Defaults to the number of CPU cores.
+    #[clap(long)]
+    workers: Option<usize>,
+    /// Always run the zkVM, instead of reusing receipts from the proof cache.
+    #[clap(long)]
+    no_cache: bool,
+}
+
+/// Exit codes of the subcommands. Clap exits with 2 on invalid arguments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Exit {
+    /// The transaction succeeded, or the receipt or witness is valid.
+    Success = 0,
+    /// The command failed, e.g. because the L3 node could not be reached.
+    Error = 1,
+    /// The transaction reverted.
+    Reverted = 3,
+    /// The transaction halted abnormally, e.g. out of gas.
+    Halted = 4,
+    /// The receipt does not verify against the image ID.
+    InvalidReceipt = 5,
+}
+
+impl From<Outcome> for Exit {
+    fn from(outcome: Outcome) -> Self {
+        match outcome {
+            Outcome::Success => Exit::Success,
+            Outcome::Revert => Exit::Reverted,
+            Outcome::Halt => Exit::Halted,
+        }
+    }
+}
+
+/// Resource usage of a single guest run, used to budget Bonsai usage and spot regressions.
+#[derive(Debug, Serialize)]
+struct RunReport {
+    tx_hash: H256,
+    tx_type: Option<u64>,
+    /// Gas used in preflight, or `None` when proving from a saved witness.
+    gas_used: Option<u64>,
+    /// Total number of zkVM cycles executed by the guest.
+    cycles: usize,
+    /// Number of segments proven. The 0.13 prover proves each run as a single segment.
+    segments: usize,
+    witness_bytes: usize,
+    journal_bytes: usize,
+    preflight_time: Option<Duration>,
+    /// Whether the receipt came from the proof cache, in which case `prove_time` is zero.
+    cached: bool,
+    prove_time: Duration,
+    verify_time: Duration,
+}
+
+impl RunReport {
+    fn log(&self) {
+        info!("cycles: {}", self.cycles);
+        info!("segments: {}", self.segments);
+        info!("witness bytes: {}", self.witness_bytes);
+        info!("journal bytes: {}", self.journal_bytes);
+        info!("preflight time: {:?}", self.preflight_time);
+        info!("cached: {}", self.cached);
+        info!("prove time: {:?}", self.prove_time);
+        info!("verify time: {:?}", self.verify_time);
+    }
+}
+
+async fn run_preflight(tx: &TxArgs) -> Result<Preflight, Box<dyn Error>> {
+    let client = Arc::new(Provider::<Http>::try_from(tx.rpc_url.as_str())?);
+    info!("Running TX: {:?}", tx.tx_hash);
+    let preflight = preflight(client, tx.tx_hash).await?;
+    say!(
+        "Transaction {} in preflight: {:?}",
+        preflight.outcome(),
+        preflight.result.exit_reason
+    );
+    say!("    Block:    {}", preflight.witness.block_number);
+    say!("    Gas used: {}", preflight.result.gas_used);
+    say!("    Logs:     {}", preflight.result.logs.len());
+    Ok(preflight)
+}
+
+fn print_witness(witness: &EvmWitness) {
+    let reads = witness.db.reads();
+    say!("Witness for transaction {:?}", witness.tx_hash);
+    say!("    Block:        {}", witness.block_number);
+    say!("    Type:         {:?}", witness.tx_type);
+    say!("    Accounts:     {}", reads.accounts);
+    say!("    Storage:      {}", reads.storage);
+    say!("    Block hashes: {}", reads.block_hashes);
+}
+
+fn print_result(tx_hash: Option<H256>, result: &EvmResult) -> Exit {
+    let outcome = Outcome::from_exit_reason(result.exit_reason);
+    say!(
+        "Transaction {outcome} in the guest: {:?}",
+        result.exit_reason
+    );
+    say!("    Gas used:      {}", result.gas_used);
+    say!("    Logs:          {}", result.logs.len());
+    say!("    State updates: {}", result.state.len());
+    print_json(ResultJson::from_result(tx_hash, result));
+    outcome.into()
+}
+
+async fn prove(args: ProveArgs) -> Result<Exit, Box<dyn Error>> {
+    let (witness, preflight, expected_journal) = match (&args.witness, args.tx_hash, args.rpc_url) {
+        (Some(path), _, _) => (EvmWitness::load(path)?, None, None),
+        (None, Some(tx_hash), Some(rpc_url)) => {
+            let preflight = run_preflight(&TxArgs { tx_hash, rpc_url }).await?;
+            let gas_used = preflight.result.gas_used;
+            let expected_journal = preflight.expected_journal()?;
+            (
+                preflight.witness,
+                Some((gas_used, preflight.time)),
+                Some(expected_journal),
+            )
+        }
+        _ => return Err("pass either --tx-hash and --rpc-url, or --witness".into()),
+    };
+
+    info!("Running zkvm...");
+    let mut client = ProvingClient::new();
+    if !args.no_cache {
+        client = client.with_cache(ProofCache::from_env());
+    }
+    let proof = client.prove_evm(&witness.env, &witness.db)?;
+    // The journal is canonical, so the guest must commit exactly what preflight computed.
+    if let Some(expected) = expected_journal {
+        if expected != proof.receipt.journal {
+            warn!("Journal differs from the result of preflight");
+        }
+    }
+
+    let report = RunReport {
+        tx_hash: witness.tx_hash,
+        tx_type: witness.tx_type,
+        gas_used: preflight.map(|(gas_used, _)| gas_used),
+        cycles: proof.cycles,
+        segments: 1,
+        witness_bytes: proof.input_bytes,
+        journal_bytes: proof.receipt.journal.len(),
+        preflight_time: preflight.map(|(_, time)| time),
+        cached: proof.cached,
+        prove_time: proof.prove_time,
+        verify_time: proof.verify_time.unwrap_or_default(),
+    };
+
+    if let Some(path) = &args.receipt {
+        ReceiptFile::from_proof(&proof).save(path)?;
+        info!("Saved receipt to {}", path.display());
+    }
+
+    report.log();
+    if let Some(path) = &args.report {
+        std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+        info!("Wrote run report to {}", path.display());
+    }
+    Ok(print_result(Some(witness.tx_hash), &proof.journal))
+}
+
+async fn jobs(args: JobsArgs) -> Result<Exit, Box<dyn Error>> {
+    let jobs = Job::load_list(&args.jobs)?;
+    let rpc = match &args.rpc_url {
+        Some(rpc_url) => Some(Arc::new(Provider::<Http>::try_from(rpc_url.as_str())?)),
+        None => None,
+    };
+    let mut options = JobOptions::new(&args.out_dir);
+    options.rpc_concurrency = args.rpc_concurrency;
+    if let Some(workers) = args.workers {
+        options.workers = workers;
+    }
+    let mut client = ProvingClient::new();
+    if !args.no_cache {
+        client = client.with_cache(ProofCache::from_env());
+    }
+
+    say!(
+        "Proving {} jobs on {} workers...",
+        jobs.len(),
+        options.workers
+    );
+    let summary = run_jobs(jobs, rpc, client, &options).await?;
+    for report in &summary.jobs {
+        match (&report.outcome, &report.error) {
+            (Some(outcome), _) => say!("    {:<66} {outcome}", report.name),
+            (_, Some(err)) => say!("    {:<66} failed: {err}", report.name),
+            _ => {}
+        }
+    }
+    say!(
+        "Proved {} of {} jobs in {:?}, summary in {}",
+        summary.proved,
+        summary.jobs.len(),
+        summary.total_time,
+        args.out_dir.join("summary.json").display()
+    );
+    match summary.failed {
+        0 => Ok(Exit::Success),
+        _ => Ok(Exit::Error),
+    }
+}
+
+fn verify(path: &Path, image_id: Option<H256>) -> Result<Exit, Box<dyn Error>> {
+    let file = ReceiptFile::load(path)?;
+    let image_id = match image_id {
+        Some(image_id) => image_id,
+        None => GuestManifest::embedded()
+            .image_id(GuestImage::EvmReplay)
+            .ok_or("EVM replay guest is missing from the manifest")?,
+    };
+    say!("Verifying receipt against image ID {image_id:?}...");
+    if let Err(err) = file.verify(Digest::from(image_id.0)) {
+        say!("Receipt is invalid: {err}");
+        return Ok(Exit::InvalidReceipt);
+    }
+    say!("Receipt is valid");
+    let result: EvmResult = from_slice(&file.journal[..])?;
+    print_result(None, &result);
+    Ok(Exit::Success)
+}
+
+fn inspect(path: &Path) -> Result<Exit, Box<dyn Error>> {
+    // Receipts are JSON, witnesses are words of `risc0_zkvm::serde`.
+    if let Ok(file) = ReceiptFile::load(path) {
+        say!("Receipt for image ID {:?}", file.image_id);
+        if let Some(guest) = &file.metadata.guest {
+            say!("    Guest:      {guest}");
+        }
+        say!("    Journal:    {} bytes", file.journal.len());
+        say!("    Seal:       {} bytes", file.seal.len());
+        say!("    Cycles:     {}", file.metadata.cycles);
+        say!("    Prove time: {} ms", file.metadata.prove_time_ms);
+        if file.guest() == Some(GuestImage::EvmReplay) {
+            let result: EvmResult = from_slice(&file.journal[..])?;
+            print_result(None, &result);
+        }
+        return Ok(Exit::Success);
+    }
+    let witness = EvmWitness::load(path).map_err(|err| {
+        format!(
+            "{} is neither a receipt nor a witness: {err}",
+            path.display()
+        )
+    })?;
+    print_witness(&witness);
+    Ok(Exit::Success)
+}
+
+async fn run(cli: Cli) -> Result<Exit, Box<dyn Error>> {
+    match cli.command {
+        Command::Trace(tx) => {
+            let preflight = run_preflight(&tx).await?;
+            print_json(ResultJson::from_preflight(&preflight));
+            Ok(preflight.outcome().into())
+        }
+        Command::Witness { tx, out } => {
+            let preflight = run_preflight(&tx).await?;
+            preflight.witness.save(&out)?;
+            print_witness(&preflight.witness);
+            say!("Saved witness to {}", out.display());
+            print_json(ResultJson::from_preflight(&preflight));
+            Ok(preflight.outcome().into())
+        }
+        Command::Prove(args) => prove(args).await,
+        Command::Jobs(args) => jobs(args).await,
+        Command::Verify { receipt, image_id } => verify(&receipt, image_id),
+        Command::Inspect { path } => inspect(&path),
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    env_logger::init();
+    let cli = Cli::parse();
+    JSON.store(cli.json, Ordering::Relaxed);
+    let exit = match run(cli).await {
+        Ok(exit) => exit,
+        Err(err) => {
+            eprintln!("error: {err}");
+            Exit::Error
+        }
+    };
+    ExitCode::from(exit as u8)
+}
@@ -0,0 +1,198 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use clap::{Parser, ValueEnum};
+use escrow_core::escrow_reference;
+use escrow_core::reference::tag_calldata;
+use ethers::abi::{self, Token};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{Address, NameOrAddress, TransactionRequest, H256, U256};
+use ethers::utils::id;
+use hello_bonsai_methods::deployment::{Deployment, DEFAULT_DEPLOYMENTS_DIR};
+
+/// Asset the deposit asks to be paid in on L3.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Kind {
+    Native,
+    Erc20,
+    Erc721,
+    Erc1155,
+}
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// JSON RPC URL for the L3 node the payment is sent to.
+    /// Currently only HTTP(S) URLs are supported.
+    #[clap(short = 'e', long, env, value_hint = clap::ValueHint::Url)]
+    ethereum_node_url: String,
+
+    /// L3L1Escrow contract address on L1. Defaults to the escrow deployed to `--network`.
+    #[clap(short = 'a', long, env, required_unless_present = "network")]
+    escrow_contract_address: Option<Address>,
+
+    /// L1 network the escrow is deployed to, as recorded by `deploy`.
+    #[clap(short = 'n', long, env)]
+    network: Option<String>,
+
+    /// Directory of the deployment records.
+    #[clap(long, env, default_value = DEFAULT_DEPLOYMENTS_DIR)]
+    deployments_dir: PathBuf,
+
+    /// ID of the deposit the payment settles.
+    #[clap(long, value_parser = parse_u256)]
+    escrow_id: U256,
+
+    /// Depositor who receives the payment on L3.
+    #[clap(long)]
+    depositor: Address,
+
+    /// Asset to pay in.
+    #[clap(long, value_enum, default_value = "native")]
+    kind: Kind,
+
+    /// Token contract or NFT collection on L3, required unless paying in the native currency.
+    #[clap(long)]
+    token: Option<Address>,
+
+    /// Token ID for ERC-721 and ERC-1155 payments.
+    #[clap(long, default_value = "0", value_parser = parse_u256)]
+    token_id: U256,
+
+    /// Value or token amount to pay, one for ERC-721 tokens.
+    #[clap(long, value_parser = parse_u256)]
+    amount: U256,
+
+    /// Print the tagged transaction instead of sending it.
+    #[clap(long)]
+    dry_run: bool,
+
+    /// Ethereum private key of the creditor, used for sending the payment.
+    // NOTE: Provided as an example and for testing. Integrate your preferred key management.
+    // https://docs.rs/ethers/latest/ethers/signers/index.html
+    #[clap(env, long)]
+    ethereum_private_key: String,
+}
+
+// `U256::from_str` reads hex, but amounts and IDs are given in decimal.
+fn parse_u256(value: &str) -> Result<U256, String> {
+    U256::from_dec_str(value).map_err(|err| err.to_string())
+}
+
+/// Build the L3 payment, ending its calldata with the escrow reference so it can only settle the
+/// given deposit.
+fn payment(args: &Args, payer: Address, reference: H256) -> Result<TransactionRequest, String> {
+    let token = || {
+        args.token
+            .ok_or_else(|| format!("--token is required for {:?}", args.kind))
+    };
+    let tx = match args.kind {
+        Kind::Native => TransactionRequest::new()
+            .to(args.depositor)
+            .value(args.amount)
+            .data(reference.as_bytes().to_vec()),
+        Kind::Erc20 => {
+            let transfer = call(
+                "transfer(address,uint256)",
+                &[Token::Address(args.depositor), Token::Uint(args.amount)],
+            );
+            // ERC-20 tokens ignore calldata after the arguments of `transfer`.
+            TransactionRequest::new()
+                .to(token()?)
+                .data(tag_calldata(&transfer, reference))
+        }
+        Kind::Erc721 => TransactionRequest::new().to(token()?).data(call(
+            "safeTransferFrom(address,address,uint256,bytes)",
+            &[
+                Token::Address(payer),
+                Token::Address(args.depositor),
+                Token::Uint(args.token_id),
+                Token::Bytes(reference.as_bytes().to_vec()),
+            ],
+        )),
+        Kind::Erc1155 => TransactionRequest::new().to(token()?).data(call(
+            "safeTransferFrom(address,address,uint256,uint256,bytes)",
+            &[
+                Token::Address(payer),
+                Token::Address(args.depositor),
+                Token::Uint(args.token_id),
+                Token::Uint(args.amount),
+                Token::Bytes(reference.as_bytes().to_vec()),
+            ],
+        )),
+    };
+    Ok(tx.from(payer))
+}
+
+fn call(signature: &str, args: &[Token]) -> Vec<u8> {
+    let mut calldata = id(signature).to_vec();
+    calldata.extend(abi::encode(args));
+    calldata
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+
+    // The node is on L3, so the chain of the L1 deployment cannot be checked against it.
+    let escrow_address = match (args.escrow_contract_address, &args.network) {
+        (Some(address), _) => address,
+        (None, Some(network)) => Deployment::load(&args.deployments_dir, network)?.escrow,
+        (None, None) => return Err("--escrow-contract-address or --network is required".into()),
+    };
+
+    // Create a wallet and connection to the L3 node.
+    let wallet = LocalWallet::from_str(args.ethereum_private_key.trim_start_matches("0x"))?;
+    let provider = Provider::<Http>::try_from(&args.ethereum_node_url)?;
+    let chain_id = provider.get_chainid().await?;
+    println!("Loaded private key for Ethereum wallet");
+    println!("    Wallet address: {:?}", wallet.address());
+
+    let reference = escrow_reference(escrow_address, args.escrow_id);
+    let tx = payment(&args, wallet.address(), reference)?;
+    println!("Tagged payment for deposit {}", args.escrow_id);
+    println!("    Reference: {:?}", reference);
+    let to = tx.to.as_ref().and_then(NameOrAddress::as_address);
+    println!("    To:        {:?}", to.ok_or("payment has no recipient")?);
+    println!("    Value:     {}", tx.value.unwrap_or_default());
+    println!("    Calldata:  {}", tx.data.clone().unwrap_or_default());
+    if args.dry_run {
+        return Ok(());
+    }
+
+    let client = Arc::new(SignerMiddleware::new(
+        provider,
+        wallet.with_chain_id(chain_id.as_u64()),
+    ));
+
+    println!("Sending payment on L3...");
+    let receipt = client
+        .send_transaction(tx, None)
+        .await?
+        .confirmations(1)
+        .await?
+        .ok_or("no receipt from transaction")?;
+    println!("Transaction processed");
+    println!("    Hash: {:?}", receipt.transaction_hash);
+    println!("Pass this hash to L3L1Escrow.checkPaymentStatus to settle the deposit");
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use bonsai_client::BonsaiClient;
+use clap::Parser;
+use ethers::types::H256;
+use hello_bonsai_methods::prover::words_to_bytes;
+use hello_bonsai_methods::receipt::{ReceiptFile, ReceiptMetadata, RECEIPT_FILE_VERSION};
+use hello_bonsai_methods::GuestImage;
+use risc0_zkvm::sha::Digest;
+
+/// Prove a guest through the Bonsai REST API directly, without going through the Bonsai proxy
+/// contract, and save the receipt.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// URL for the Bonsai service.
+    #[clap(short = 'b', long, env, value_hint = clap::ValueHint::Url)]
+    bonsai_url: String,
+
+    /// API Key for Bonsai to authorize requests.
+    #[clap(env, long)]
+    bonsai_api_key: String,
+
+    /// Guest to prove, by name, e.g. `native_transfer`.
+    #[clap(short, long)]
+    guest: String,
+
+    /// File with the raw input of the guest.
+    #[clap(short, long)]
+    input: PathBuf,
+
+    /// Path to save the receipt to, for offline verification with `verify`.
+    #[clap(short, long)]
+    receipt: PathBuf,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+
+    let guest =
+        GuestImage::from_name(&args.guest).ok_or(format!("unknown guest {}", args.guest))?;
+    let input = fs::read(&args.input)?;
+    let bonsai = BonsaiClient::new(&args.bonsai_url, &args.bonsai_api_key)?;
+
+    println!("Proving {} guest on Bonsai...", guest.name());
+    let image_id = H256(Digest::from(guest.image_id()).into());
+    let start = Instant::now();
+    let receipt = bonsai.prove(image_id, guest.elf(), &input).await?;
+    let prove_time = start.elapsed();
+    println!("Proved in {prove_time:?}");
+
+    let file = ReceiptFile {
+        version: RECEIPT_FILE_VERSION,
+        image_id,
+        journal: receipt.journal.into(),
+        seal: words_to_bytes(&receipt.seal).into(),
+        metadata: ReceiptMetadata {
+            guest: Some(guest.name().into()),
+            claim: None,
+            cycles: 0,
+            prove_time_ms: prove_time.as_millis() as u64,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        },
+    };
+    match file.seal.is_empty() {
+        true => println!("Receipt has no seal, skipping verification"),
+        false => {
+            file.verify(Digest::from(guest.image_id()))?;
+            println!("Receipt is valid");
+        }
+    }
+    file.save(&args.receipt)?;
+    println!("Saved receipt to {}", args.receipt.display());
+    Ok(())
+}
@@ -0,0 +1,144 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Parser;
+use escrow_core::{AggregateJournal, BatchJournal, EscrowJournal};
+use ethers::types::H256;
+use hello_bonsai_contracts::journal::check_claim;
+use hello_bonsai_methods::deployment::{Deployment, DEFAULT_DEPLOYMENTS_DIR};
+use hello_bonsai_methods::manifest::GuestManifest;
+use hello_bonsai_methods::receipt::ReceiptFile;
+use hello_bonsai_methods::GuestImage;
+use risc0_zkvm::sha::Digest;
+
+/// Check a saved receipt offline and print the journal it proves.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Receipt file, as saved with `ReceiptFile::save`.
+    receipt: PathBuf,
+
+    /// Image ID to verify against, as hex.
+    #[clap(long, conflicts_with = "guest")]
+    image_id: Option<H256>,
+
+    /// Guest to verify against, by name, e.g. `native_transfer`. Defaults to the guest in the
+    /// manifest with the image ID recorded in the receipt.
+    #[clap(long)]
+    guest: Option<String>,
+
+    /// Guest manifest to look up guests in, e.g. one written by `deploy --write-manifest` for
+    /// another build. Defaults to the manifest of the guests built into this tool.
+    #[clap(long, conflicts_with = "network")]
+    manifest: Option<PathBuf>,
+
+    /// Network to look up guests in the deployment of, as recorded by `deploy`, so only receipts
+    /// of the guests the escrow deployed there trusts are valid.
+    #[clap(short = 'n', long, env)]
+    network: Option<String>,
+
+    /// Directory of the deployment records.
+    #[clap(long, env, default_value = DEFAULT_DEPLOYMENTS_DIR)]
+    deployments_dir: PathBuf,
+}
+
+fn print_journal(guest: Option<GuestImage>, file: &ReceiptFile) -> Result<(), Box<dyn Error>> {
+    match guest {
+        Some(GuestImage::NativeTransfer | GuestImage::Erc20Transfer | GuestImage::NftTransfer) => {
+            let journal = EscrowJournal::abi_decode(&file.journal)?;
+            println!("{journal:#?}");
+            // The claim in the metadata is not proven, but shows whether the journal settles it.
+            if let Some(claim) = file.claim().transpose()? {
+                match check_claim(&journal, &claim) {
+                    Ok(()) => println!("Journal settles deposit {}", claim.escrow_id),
+                    Err(err) => {
+                        println!("Journal does not settle deposit {}: {err}", claim.escrow_id)
+                    }
+                }
+            }
+        }
+        Some(GuestImage::Batch) => println!("{:#?}", BatchJournal::abi_decode(&file.journal)?),
+        Some(GuestImage::Aggregate) => {
+            println!("{:#?}", AggregateJournal::abi_decode(&file.journal)?)
+        }
+        Some(GuestImage::EvmReplay | GuestImage::ViewCall) | None => {
+            println!("Journal: {}", file.journal)
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+
+    let file = ReceiptFile::load(&args.receipt)?;
+    println!("Loaded receipt from {}", args.receipt.display());
+
+    let (guests, source) = match (&args.network, &args.manifest) {
+        (Some(network), _) => (
+            Deployment::load(&args.deployments_dir, network)?.guests,
+            format!("the deployment to {network}"),
+        ),
+        (None, Some(path)) => (GuestManifest::load(path)?.guests, "the manifest".into()),
+        (None, None) => (GuestManifest::embedded().guests, "the manifest".into()),
+    };
+
+    // The image ID recorded in the file is not proven, so only trust it if it is in the manifest
+    // or deployment.
+    let (image_id, guest) = match (args.image_id, &args.guest) {
+        (Some(image_id), _) => match guests.iter().find(|entry| entry.image_id == image_id) {
+            Some(entry) => (image_id, GuestImage::from_name(&entry.name)),
+            // A deployment only trusts the image IDs it was deployed with.
+            None if args.network.is_some() => {
+                return Err(format!("image ID {image_id:?} is not in {source}").into())
+            }
+            None => (image_id, GuestImage::from_image_id(image_id.0)),
+        },
+        (None, name) => {
+            let entry = match name {
+                Some(name) => guests
+                    .iter()
+                    .find(|entry| entry.name == *name)
+                    .ok_or_else(|| format!("{name} guest is not in {source}"))?,
+                None => guests
+                    .iter()
+                    .find(|entry| entry.image_id == file.image_id)
+                    .ok_or_else(|| {
+                        format!(
+                            "receipt is not for a guest in {source}; pass the image ID to verify \
+                             against with --image-id"
+                        )
+                    })?,
+            };
+            (entry.image_id, GuestImage::from_name(&entry.name))
+        }
+    };
+    let image_id = Digest::from(image_id.0);
+    if Digest::from(file.image_id.0) != image_id {
+        println!("Warning: receipt records image ID {:?}", file.image_id);
+    }
+
+    println!("Verifying receipt...");
+    println!("    Image ID: {:?}", H256(image_id.into()));
+    if let Some(guest) = guest {
+        println!("    Guest:    {}", guest.name());
+    }
+    file.verify(image_id)?;
+    println!("Receipt is valid");
+
+    print_journal(guest, &file)
+}
@@ -0,0 +1,120 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! [BonsaiClient] end to end against `bonsai_local`.
+
+use std::error::Error;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+use bonsai_client::{Backoff, BonsaiClient};
+use escrow_core::input::frame_input;
+use escrow_core::{ClaimStatus, EscrowClaim, EscrowJournal};
+use ethers::types::H256;
+use hello_bonsai_methods::GuestImage;
+use risc0_zkvm::sha::Digest;
+
+const API_KEY: &str = "test-key";
+
+/// `bonsai_local` child process, killed on drop.
+struct LocalBonsai {
+    child: Child,
+    url: String,
+}
+
+impl LocalBonsai {
+    /// Start `bonsai_local` on a free port, skipping seals.
+    fn start() -> Result<Self, Box<dyn Error>> {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_bonsai_local"))
+            .args([
+                "--listen",
+                "127.0.0.1:0",
+                "--skip-seal",
+                "--api-key",
+                API_KEY,
+            ])
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let mut stdout = BufReader::new(child.stdout.take().ok_or("no stdout")?);
+
+        // The first line names the address the server is bound to.
+        let mut line = String::new();
+        stdout.read_line(&mut line)?;
+        let url = line
+            .trim()
+            .strip_prefix("Serving the Bonsai API on ")
+            .ok_or(format!("unexpected output {line:?}"))?
+            .to_string();
+        // Keep draining the request log so the server never blocks on a full pipe.
+        std::thread::spawn(move || stdout.read_to_end(&mut Vec::new()));
+        Ok(Self { child, url })
+    }
+}
+
+impl Drop for LocalBonsai {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[tokio::test]
+async fn proves_a_guest_through_the_client() -> Result<(), Box<dyn Error>> {
+    let server = LocalBonsai::start()?;
+    let client = BonsaiClient::new(&server.url, API_KEY)?.with_backoff(Backoff {
+        initial: Duration::from_millis(100),
+        max: Duration::from_secs(1),
+        factor: 2.0,
+        timeout: Some(Duration::from_secs(600)),
+    });
+
+    // A claim without a witness proves as rejected, which is enough to exercise the service.
+    let guest = GuestImage::NativeTransfer;
+    let image_id = H256(Digest::from(guest.image_id()).into());
+    let claim = EscrowClaim::default();
+    let input = frame_input(&claim.abi_encode());
+
+    assert!(!client.upload_img(image_id, guest.elf()).await?);
+    assert!(client.upload_img(image_id, guest.elf()).await?);
+    let input_id = client.upload_input(&input).await?;
+    let session = client.create_session(image_id, &input_id).await?;
+    let receipt_url = client.wait(&session).await?;
+    let receipt = client.receipt(&receipt_url).await?;
+
+    assert!(receipt.seal.is_empty());
+    assert_eq!(
+        EscrowJournal::abi_decode(&receipt.journal)?,
+        EscrowJournal::rejected(&claim, ClaimStatus::DecodeError)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn rejects_unknown_images_and_keys() -> Result<(), Box<dyn Error>> {
+    let server = LocalBonsai::start()?;
+    let client = BonsaiClient::new(&server.url, API_KEY)?;
+    let image_id = H256(Digest::from(GuestImage::NativeTransfer.image_id()).into());
+
+    assert!(client
+        .upload_img(H256::zero(), b"not a guest")
+        .await
+        .is_err());
+    assert!(client.upload_img(image_id, b"not the guest").await.is_err());
+    assert!(BonsaiClient::new(&server.url, "wrong-key")?
+        .upload_input(b"input")
+        .await
+        .is_err());
+    Ok(())
+}
@@ -20,6 +20,7 @@ import {IBonsaiApp} from "./IBonsaiApp.sol";
 pragma solidity ^0.8.16;
 
 /// @notice A base contract for writing a Bonsai app with one callback and one associated guest.
+/// @dev Apps with several guests override `is_trusted_image` and submit with an explicit image ID.
 abstract contract BonsaiApp is IBonsaiApp {
     // Address of the Bonsai proxy contract.
     IBonsaiProxy public immutable bonsai_proxy;
@@ -34,12 +35,29 @@ abstract contract BonsaiApp is IBonsaiApp {
 
     function bonsai_callback(bytes memory journal) internal virtual;
 
+    /// @notice Callback logic for apps whose guests commit different journal layouts.
+    /// @dev Called with the verified image ID. Forwards to `bonsai_callback(journal)` by default.
+    function bonsai_callback(bytes32, bytes memory journal) internal virtual {
+        bonsai_callback(journal);
+    }
+
+    /// @notice Whether journals from the given guest image are accepted by the callback.
+    function is_trusted_image(bytes32 _image_id) internal view virtual returns (bool) {
+        return _image_id == image_id;
+    }
+
     /// @notice Submit a request to Bonsai to run the associated image with the given input.
     /// @dev This function sends the request to Bonsai through the on-chain proxy.
     ///      The request will trigger Bonsai to run the specified RISC Zero guest program with
     ///      the given input and asynchronously return the verified results via the callback below.
     function submit_bonsai_request(bytes memory input) internal {
-        bonsai_proxy.submit_request(image_id, input, address(this));
+        submit_bonsai_request(image_id, input);
+    }
+
+    /// @notice Submit a request to Bonsai to run the given image with the given input.
+    /// @dev The image must be trusted by `is_trusted_image` for the result to be accepted.
+    function submit_bonsai_request(bytes32 _image_id, bytes memory input) internal {
+        bonsai_proxy.submit_request(_image_id, input, address(this));
     }
 
     /// @notice Callback function to be called by the Bonsai proxy when the result is ready.
@@ -53,9 +71,9 @@ abstract contract BonsaiApp is IBonsaiApp {
             msg.sender == address(bonsai_proxy),
             "calls must come from Bonsai"
         );
-        require(_image_id == image_id, "call must be from expected guest");
+        require(is_trusted_image(_image_id), "call must be from expected guest");
 
         // Now pass the jounral to the user-defined bonsai_callback function.
-        bonsai_callback(journal);
+        bonsai_callback(_image_id, journal);
     }
 }
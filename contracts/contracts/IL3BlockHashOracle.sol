@@ -0,0 +1,23 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+pragma solidity ^0.8.16;
+
+/// @notice Source of L3 block hashes trusted on L1, such as the output oracle of the L3 rollup.
+interface IL3BlockHashOracle {
+    /// @notice Hash of the L3 block with the given number, or zero if it is not known (yet).
+    function blockHash(uint256 blockNumber) external view returns (bytes32);
+}
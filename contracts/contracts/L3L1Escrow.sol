@@ -18,6 +18,7 @@ pragma solidity ^0.8.16;
 
 import {IBonsaiProxy} from "./IBonsaiProxy.sol";
 import {BonsaiApp} from "./BonsaiApp.sol";
+import {IL3BlockHashOracle} from "./IL3BlockHashOracle.sol";
 
 // @dev One-way bridge for ValidityRollups (easily to deploy other way round)
 // 1. user A deposits funds on chainA and provides address of creditor on chainB
@@ -25,70 +26,406 @@ import {BonsaiApp} from "./BonsaiApp.sol";
 // 3. Escrow checks if user B has sent funds to user A on chainB
 // 4. On success release funds from Escrow on chainA to user B
 contract L3L1Escrow is BonsaiApp {
+    // Version of the claim layout read by the guest. Must match `CLAIM_VERSION` in escrow-core.
+    uint8 public constant CLAIM_VERSION = 5;
+    // Version of the journal layout committed by the guest. Must match `JOURNAL_VERSION` in escrow-core.
+    uint8 public constant JOURNAL_VERSION = 6;
+    // Version of the batch journal layout. Must match `BATCH_VERSION` in escrow-core.
+    uint8 public constant BATCH_VERSION = 1;
+    // Largest number of claims checked in one batch. Must match `MAX_BATCH_SIZE` in escrow-core.
+    uint256 public constant MAX_BATCH_SIZE = 64;
+    // Version of the aggregate journal layout. Must match `AGGREGATE_VERSION` in escrow-core.
+    uint8 public constant AGGREGATE_VERSION = 2;
+    // Largest number of L3 transactions summed for one deposit. Must match `MAX_AGGREGATE_TXS` in escrow-core.
+    uint256 public constant MAX_AGGREGATE_TXS = 16;
+    // Time the creditor has to pay on L3 after a deposit is made.
+    uint256 public constant PAYMENT_PERIOD = 7 days;
+    // Time after the payment window during which a payment made inside it can still be claimed.
+    uint256 public constant CLAIM_PERIOD = 1 days;
+    // Time Bonsai has to return the result of a claim made in the claim period, before the deposit
+    // can be refunded.
+    uint256 public constant PROVING_PERIOD = 1 days;
+
+    // Standard of the L3 payment. Must match `AssetKind` in escrow-core.
+    enum AssetKind { Native, Erc20, Erc721, Erc1155 }
+
+    // Outcome of a claim committed by the guest. Must match `ClaimStatus` in escrow-core.
+    enum ClaimStatus {
+        Accepted,
+        DecodeError,
+        TxNotFound,
+        TxReverted,
+        UnsupportedAsset,
+        TransferNotFound,
+        WrongPayer,
+        WrongRecipient,
+        WrongAmount,
+        StaleBlock,
+        MissingReference,
+        DuplicateTransaction,
+        WrongChain
+    }
+
+    // What the creditor must send the depositor on L3 to release the deposit.
+    struct L3Payment {
+        AssetKind kind;
+        // token contract or NFT collection, zero for the native currency
+        address token;
+        // token ID for ERC-721 and ERC-1155, zero otherwise
+        uint256 tokenId;
+        // value or token amount, one for ERC-721
+        uint256 amount;
+    }
+
     struct Deposit {
+        address depositor;
         // who should get it
         address creditor;
         uint256 amount;
+        L3Payment l3;
+        // window for the timestamp of the L3 block including the payment, inclusive
         uint256 earliestTime;
+        uint256 latestTime;
+    }
+    mapping(uint256 => Deposit) public deposits;
+    uint256 public nextEscrowId;
+    // Image IDs of the guests checking token and NFT payments. Native payments use `image_id`.
+    bytes32 public immutable erc20_image_id;
+    bytes32 public immutable nft_image_id;
+    // Image ID of the guest checking batches of claims of any kind.
+    bytes32 public immutable batch_image_id;
+    // Image ID of the guest checking payments split across several L3 transactions.
+    bytes32 public immutable aggregate_image_id;
+    // Chain ID of the L3 payments are made on.
+    uint256 public immutable l3ChainId;
+    // Hashes of L3 blocks trusted on L1. The guest only checks that the payment is included in the
+    // block header it was given, so the header itself must be anchored here.
+    IL3BlockHashOracle public immutable l3BlockHashes;
+
+    // Journal committed by the guest, decoded by `EscrowJournal::abi_decode` on the Rust side.
+    struct Journal {
+        uint8 version;
+        ClaimStatus status;
+        uint256 chainId;
+        bytes32 blockHash;
+        uint64 blockNumber;
+        uint64 blockTimestamp;
+        bytes32 txHash;
+        AssetKind kind;
+        address token;
+        uint256 tokenId;
+        address payer;
+        address payee;
+        uint256 amount;
+        address escrow;
+        uint256 escrowId;
+        // escrow reference found at the end of the L3 transaction calldata
+        bytes32 reference;
+    }
+
+    // L3 block a part of an aggregate payment was found in, decoded by `PartBlock` on the Rust side.
+    struct L3Block {
+        bytes32 hash;
+        uint64 number;
+        uint64 timestamp;
     }
-    mapping(address => Deposit) public deposits;
 
-    // Initialize the contract, binding it to a specified Bonsai proxy and RISC Zero guest image.
+    // Initialize the contract, binding it to a specified Bonsai proxy, the L3 chain with its source of
+    // block hashes, and the RISC Zero guest images checking native, ERC-20 and NFT payments, batches
+    // of them and payments made in parts.
     constructor(
         IBonsaiProxy _bonsai_proxy,
-        bytes32 _image_id
-    ) BonsaiApp(_bonsai_proxy, _image_id) {} // solhint-disable-line no-empty-blocks
+        uint256 _l3_chain_id,
+        IL3BlockHashOracle _l3_block_hashes,
+        bytes32 _native_image_id,
+        bytes32 _erc20_image_id,
+        bytes32 _nft_image_id,
+        bytes32 _batch_image_id,
+        bytes32 _aggregate_image_id
+    ) BonsaiApp(_bonsai_proxy, _native_image_id) {
+        // Transactions without replay protection report chain zero, which must never match.
+        require(_l3_chain_id != 0, "L3 chain ID invalid");
+        l3ChainId = _l3_chain_id;
+        l3BlockHashes = _l3_block_hashes;
+        erc20_image_id = _erc20_image_id;
+        nft_image_id = _nft_image_id;
+        batch_image_id = _batch_image_id;
+        aggregate_image_id = _aggregate_image_id;
+    }
 
-    event CrosschainPaymentReceived(address indexed sender, address indexed recipient, uint256 amount);
+    event DepositCreated(
+        uint256 indexed escrowId,
+        address indexed depositor,
+        address indexed creditor,
+        uint256 amount,
+        L3Payment l3
+    );
+    event CrosschainPaymentReceived(uint256 indexed escrowId, address indexed sender, address indexed recipient, uint256 amount);
+    event DepositRefunded(uint256 indexed escrowId, address indexed depositor, uint256 amount);
+    // Emitted for results that do not settle their deposit. The deposit stays open, and the rest of a
+    // batch is still settled. `status` is `Accepted` when the guest accepted a payment that does not
+    // match the deposit.
+    // `txHash` is the transaction hash of the claim, as in `Journal`, so a claimant can tell its
+    // result from those of other claims on the same deposit.
+    event PaymentRejected(uint256 indexed escrowId, bytes32 indexed txHash, ClaimStatus status, string reason);
 
-    /// @notice Sends a request to Bonsai to have have the nth Fibonacci number calculated.
+    /// @notice Sends a request to Bonsai to check that the creditor paid the depositor on L3.
     /// @dev This function sends the request to Bonsai through the on-chain proxy.
     ///      The request will trigger Bonsai to run the specified RISC Zero guest program with
     ///      the given input and asynchronously return the verified results via the callback below.
-    function checkPaymentStatus(bytes32 txHash) external {
-        require(deposits[msg.sender].amount > 0, "No deposit found");
-        require(deposits[msg.sender].earliestTime < block.timestamp, "Too early");
+    /// @param witness Serialized `PaymentWitness` proving the L3 transaction, appended to the claim.
+    function checkPaymentStatus(uint256 escrowId, bytes32 txHash, bytes calldata witness) external {
+        Deposit memory deposit = deposits[escrowId];
+        require(deposit.amount > 0, "No deposit found");
+        require(deposit.earliestTime < block.timestamp, "Too early");
+        require(block.timestamp <= deposit.latestTime + CLAIM_PERIOD, "Claim period over");
 
         // working example for eth: 0x671a3b40ecb7d51b209e68392df2d38c098aae03febd3a88be0f1fa77725bbd7
-        Deposit memory deposit = deposits[msg.sender];
-        submit_bonsai_request(serialize(abi.encode(txHash, msg.sender, deposit.creditor, deposit.amount)));
+        bytes memory claim = encodeClaim(escrowId, txHash, deposit);
+        submit_bonsai_request(imageIdFor(deposit.l3.kind), frameInput(bytes.concat(claim, witness)));
+    }
+
+    /// @notice Sends a single request to Bonsai to check the payments of several deposits.
+    /// @dev The batch guest checks claims of any asset kind, and witnesses may come from different
+    ///      L3 blocks. Results are settled one by one in the callback.
+    /// @param witnesses Serialized `PaymentWitness` of each payment, in the order of `escrowIds`.
+    function checkPaymentStatusBatch(
+        uint256[] calldata escrowIds,
+        bytes32[] calldata txHashes,
+        bytes calldata witnesses
+    ) external {
+        require(escrowIds.length > 0 && escrowIds.length <= MAX_BATCH_SIZE, "Batch size invalid");
+        require(escrowIds.length == txHashes.length, "Batch length mismatch");
+
+        // Claim count followed by the claims, decoded by `decode_claim_count` in the guest.
+        bytes memory claims = abi.encode(escrowIds.length);
+        for (uint256 i = 0; i < escrowIds.length; i++) {
+            Deposit memory deposit = deposits[escrowIds[i]];
+            require(deposit.amount > 0, "No deposit found");
+            require(deposit.earliestTime < block.timestamp, "Too early");
+            require(block.timestamp <= deposit.latestTime + CLAIM_PERIOD, "Claim period over");
+            claims = bytes.concat(claims, encodeClaim(escrowIds[i], txHashes[i], deposit));
+        }
+        submit_bonsai_request(batch_image_id, frameInput(bytes.concat(claims, witnesses)));
     }
 
-    function deposit(address creditor, uint256 amount) payable external {
+    /// @notice Reference the L3 payment for a deposit must end its calldata with, so that one
+    ///         payment cannot settle several deposits with the same terms.
+    /// @dev Computed by `escrow_reference` in escrow-core as well.
+    function escrowReference(uint256 escrowId) public view returns (bytes32) {
+        return keccak256(abi.encode(address(this), escrowId));
+    }
+
+    /// @notice Sends a request to Bonsai to check a payment the creditor split across several L3
+    ///         transactions, which must together pay the deposit's amount.
+    /// @dev The claim carries `keccak256(abi.encodePacked(txHashes))` as its transaction hash and is
+    ///      followed by the hashes themselves, decoded by `decode_tx_count` in the guest.
+    /// @param witnesses Serialized `PaymentWitness` of each transaction, in the order of `txHashes`.
+    function checkPaymentStatusAggregate(
+        uint256 escrowId,
+        bytes32[] calldata txHashes,
+        bytes calldata witnesses
+    ) external {
+        require(txHashes.length > 0 && txHashes.length <= MAX_AGGREGATE_TXS, "Transaction count invalid");
+        Deposit memory deposit = deposits[escrowId];
+        require(deposit.amount > 0, "No deposit found");
+        require(deposit.earliestTime < block.timestamp, "Too early");
+        require(block.timestamp <= deposit.latestTime + CLAIM_PERIOD, "Claim period over");
+
+        bytes memory claim = encodeClaim(escrowId, keccak256(abi.encodePacked(txHashes)), deposit);
+        bytes memory hashes = bytes.concat(abi.encode(txHashes.length), abi.encodePacked(txHashes));
+        submit_bonsai_request(aggregate_image_id, frameInput(bytes.concat(claim, hashes, witnesses)));
+    }
+
+    // Prefixes a guest input with its length, so the guest can read it in one go and decode it
+    // without panicking. Decoded by `decode_input_len` in the guest.
+    function frameInput(bytes memory input) private pure returns (bytes memory) {
+        require(input.length % 4 == 0, "Witness not word-aligned");
+        return bytes.concat(abi.encode(input.length), input);
+    }
+
+    // Guest image checking payments of the given kind.
+    function imageIdFor(AssetKind kind) private view returns (bytes32) {
+        if (kind == AssetKind.Native) {
+            return image_id;
+        }
+        return kind == AssetKind.Erc20 ? erc20_image_id : nft_image_id;
+    }
+
+    // Fixed-size layout decoded by `EscrowClaim::abi_decode` in the guest.
+    function encodeClaim(uint256 escrowId, bytes32 txHash, Deposit memory deposit) private view returns (bytes memory) {
+        return abi.encode(
+            CLAIM_VERSION,
+            address(this),
+            escrowId,
+            txHash,
+            deposit.l3.kind,
+            deposit.l3.token,
+            deposit.l3.tokenId,
+            deposit.depositor,
+            deposit.creditor,
+            deposit.l3.amount,
+            uint64(deposit.earliestTime),
+            uint64(deposit.latestTime)
+        );
+    }
+
+    /// @notice Deposits `amount` wei for the creditor, released once they send `l3` to the
+    ///         depositor on L3.
+    /// @dev For an NFT sale the buyer deposits the price and names the seller as creditor, who
+    ///      then transfers the token to the buyer on L3.
+    function deposit(
+        address creditor,
+        uint256 amount,
+        L3Payment calldata l3
+    ) payable external returns (uint256 escrowId) {
         require(msg.value == amount, "Amount invalid");
-        deposits[msg.sender] = Deposit(creditor, amount, block.number); // 1 ETH for 1 ETH
+        require(amount > 0, "Deposit amount invalid");
+        require(l3.amount > 0, "L3 amount invalid");
+        require((l3.kind == AssetKind.Native) == (l3.token == address(0)), "L3 token invalid");
+        require(l3.kind != AssetKind.Erc721 || l3.amount == 1, "ERC-721 amount must be one");
+        require(l3.kind == AssetKind.Erc721 || l3.kind == AssetKind.Erc1155 || l3.tokenId == 0, "L3 token ID invalid");
+        escrowId = nextEscrowId++;
+        deposits[escrowId] = Deposit(msg.sender, creditor, amount, l3, block.timestamp, block.timestamp + PAYMENT_PERIOD);
+        emit DepositCreated(escrowId, msg.sender, creditor, amount, l3);
+    }
+
+    /// @notice Returns an unsettled deposit to its depositor once its payment window and claim
+    ///         period have closed and Bonsai has had the proving period to return pending claims.
+    /// @dev A claim still pending after that can no longer settle the deposit, so creditors should
+    ///      claim before the claim period is over.
+    function refund(uint256 escrowId) external {
+        Deposit memory deposit = deposits[escrowId];
+        require(deposit.amount != 0, "No deposit found");
+        require(msg.sender == deposit.depositor, "Only the depositor can refund");
+        require(
+            block.timestamp > deposit.latestTime + CLAIM_PERIOD + PROVING_PERIOD,
+            "Claims still pending"
+        );
+        delete deposits[escrowId];
+        payable(deposit.depositor).transfer(deposit.amount);
+
+        emit DepositRefunded(escrowId, deposit.depositor, deposit.amount);
+    }
+
+    function is_trusted_image(bytes32 _image_id) internal view override returns (bool) {
+        return _image_id == image_id
+            || _image_id == erc20_image_id
+            || _image_id == nft_image_id
+            || _image_id == batch_image_id
+            || _image_id == aggregate_image_id;
     }
 
     /// @notice Callback function logic for processing verified journals from Bonsai.
-    function bonsai_callback(bytes memory journal) internal override {
-        (bool success, address depositor) = abi.decode(journal, (bool, address));
-        require (success, "Bonsai error");
-        Deposit memory deposit = deposits[msg.sender];
-        require(deposit.amount > 0, "No deposit found");
+    function bonsai_callback(bytes32 _image_id, bytes memory journal) internal override {
+        if (_image_id == aggregate_image_id) {
+            // Layout of `AggregateJournal::abi_encode` on the Rust side.
+            (uint8 aggregateVersion, Journal memory result, bytes32[] memory txHashes, L3Block[] memory blocks) =
+                abi.decode(journal, (uint8, Journal, bytes32[], L3Block[]));
+            require(aggregateVersion == AGGREGATE_VERSION, "Unsupported aggregate journal");
+            // Input that failed to decode may not have held the claimed hashes, and is recorded as
+            // rejected regardless.
+            if (result.status != ClaimStatus.DecodeError) {
+                require(result.txHash == keccak256(abi.encodePacked(txHashes)), "Wrong transactions");
+            }
+            // The result only names the latest block, so every other part must be anchored too.
+            if (bytes(rejection(result)).length == 0) {
+                string memory reason = partsRejection(result.escrowId, txHashes.length, blocks);
+                require(bytes(reason).length == 0, reason);
+            }
+            processResult(result);
+            return;
+        }
+        if (_image_id != batch_image_id) {
+            bonsai_callback(journal);
+            return;
+        }
+        // Layout of `BatchJournal::abi_encode` on the Rust side.
+        (uint8 version, Journal[] memory results) = abi.decode(journal, (uint8, Journal[]));
+        require(version == BATCH_VERSION, "Unsupported batch journal");
+        for (uint256 i = 0; i < results.length; i++) {
+            string memory reason = rejection(results[i]);
+            if (bytes(reason).length == 0) {
+                settle(results[i].escrowId);
+            } else {
+                emit PaymentRejected(results[i].escrowId, results[i].txHash, results[i].status, reason);
+            }
+        }
+    }
 
-        payable(deposit.creditor).transfer(deposit.amount);
+    function bonsai_callback(bytes memory journal) internal override {
+        processResult(abi.decode(journal, (Journal)));
+    }
 
-        emit CrosschainPaymentReceived(depositor, deposit.creditor, deposit.amount);
-        delete deposits[msg.sender];
+    // Settle the deposit of a single result, reverting if it does not match the deposit.
+    function processResult(Journal memory result) private {
+        require(result.version == JOURNAL_VERSION, "Unsupported journal");
+        require(result.escrow == address(this), "Wrong escrow");
+        // A claim rejected by the guest is recorded rather than reverted, so the proof is not lost.
+        if (result.status != ClaimStatus.Accepted) {
+            emit PaymentRejected(result.escrowId, result.txHash, result.status, "Rejected by guest");
+            return;
+        }
+        string memory reason = rejection(result);
+        require(bytes(reason).length == 0, reason);
+        settle(result.escrowId);
     }
 
-    function serialize(bytes memory input) public pure returns (bytes memory) {
-        uint256 length = input.length;
+    // Reason the journal does not settle its deposit, or an empty string if it does.
+    function rejection(Journal memory result) private view returns (string memory) {
+        if (result.version != JOURNAL_VERSION) return "Unsupported journal";
+        if (result.escrow != address(this)) return "Wrong escrow";
+        if (result.status != ClaimStatus.Accepted) return "Rejected by guest";
+        Deposit memory deposit = deposits[result.escrowId];
+        if (deposit.amount == 0) return "No deposit found";
+        // Each guest only accepts its own kinds of payment, so matching the kind also
+        // ties the journal to the image the deposit was checked with.
+        if (
+            result.kind != deposit.l3.kind
+                || result.token != deposit.l3.token
+                || result.tokenId != deposit.l3.tokenId
+                || result.payer != deposit.creditor
+                || result.payee != deposit.depositor
+                || result.amount != deposit.l3.amount
+        ) return "Payment does not match deposit";
+        if (result.reference != escrowReference(result.escrowId)) return "Payment does not reference deposit";
+        if (result.chainId != l3ChainId) return "Payment on another chain";
+        if (!isKnownL3Block(result.blockNumber, result.blockHash)) return "Unknown L3 block";
+        // The guest checked the block against the window of the claim, which was taken from the deposit.
+        if (result.blockTimestamp < deposit.earliestTime || result.blockTimestamp > deposit.latestTime) {
+            return "Payment outside time window";
+        }
+        return "";
+    }
 
-        bytes memory result = new bytes(0);
-        result = bytes.concat(bytes.concat(bytes4(reverse(uint32(length)))), input);
-        return result;
+    // Reason a part of an aggregate payment does not count, or an empty string if all of them do.
+    // The guest took the header of each part on trust, so each must be a known block in the window.
+    function partsRejection(uint256 escrowId, uint256 txCount, L3Block[] memory blocks)
+        private
+        view
+        returns (string memory)
+    {
+        if (blocks.length != txCount) return "Missing part blocks";
+        Deposit memory deposit = deposits[escrowId];
+        for (uint256 i = 0; i < blocks.length; i++) {
+            if (!isKnownL3Block(blocks[i].number, blocks[i].hash)) return "Unknown L3 block";
+            if (blocks[i].timestamp < deposit.earliestTime || blocks[i].timestamp > deposit.latestTime) {
+                return "Payment outside time window";
+            }
+        }
+        return "";
     }
 
-    // copied from https://ethereum.stackexchange.com/questions/83626/how-to-reverse-byte-order-in-uint256-or-bytes32
-    function reverse(uint32 input) internal pure returns (uint32 v) {
-        v = input;
+    // Whether the oracle vouches for the L3 block the guest checked the payment against.
+    function isKnownL3Block(uint256 blockNumber, bytes32 blockHash) private view returns (bool) {
+        return blockHash != bytes32(0) && l3BlockHashes.blockHash(blockNumber) == blockHash;
+    }
 
-        // swap bytes
-        v = ((v & 0xFF00FF00) >> 8) |
-        ((v & 0x00FF00FF) << 8);
+    function settle(uint256 escrowId) private {
+        Deposit memory deposit = deposits[escrowId];
+        delete deposits[escrowId];
+        payable(deposit.creditor).transfer(deposit.amount);
 
-        // swap 2-byte long pairs
-        v = (v >> 16) | (v << 16);
+        emit CrosschainPaymentReceived(escrowId, deposit.depositor, deposit.creditor, deposit.amount);
     }
 }
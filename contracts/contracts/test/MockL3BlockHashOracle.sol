@@ -0,0 +1,32 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+pragma solidity ^0.8.16;
+
+import {IL3BlockHashOracle} from "../IL3BlockHashOracle.sol";
+
+contract MockL3BlockHashOracle is IL3BlockHashOracle {
+    mapping(uint256 => bytes32) private hashes;
+
+    // Function called by tests to publish the hash of an L3 block.
+    function setBlockHash(uint256 blockNumber, bytes32 hash) external {
+        hashes[blockNumber] = hash;
+    }
+
+    function blockHash(uint256 blockNumber) external view returns (bytes32) {
+        return hashes[blockNumber];
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers to deploy `L3L1Escrow`, and the mock Bonsai proxy and L3 block hash oracle to test it
+//! against.
+
+use std::sync::Arc;
+
+use ethers::contract::ContractError;
+use ethers::providers::Middleware;
+use ethers::types::{Address, TransactionReceipt, H256, U256};
+
+use crate::{L3L1Escrow, MockBonsaiProxy, MockL3BlockHashOracle};
+
+/// L3 chain `L3L1Escrow` settles payments made on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct L3Chain {
+    /// Chain ID payments must be signed for. Must not be zero.
+    pub chain_id: u64,
+    /// `IL3BlockHashOracle` trusted for the hashes of L3 blocks.
+    pub block_hashes: Address,
+}
+
+/// Image IDs `L3L1Escrow` is deployed with, one per guest.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EscrowImageIds {
+    pub native: H256,
+    pub erc20: H256,
+    pub nft: H256,
+    pub batch: H256,
+    pub aggregate: H256,
+}
+
+impl EscrowImageIds {
+    /// Look up the image ID of each guest by name, e.g. `native_transfer`, in a guest manifest.
+    pub fn from_guests<E>(mut image_id: impl FnMut(&str) -> Result<H256, E>) -> Result<Self, E> {
+        Ok(Self {
+            native: image_id("native_transfer")?,
+            erc20: image_id("erc20_transfer")?,
+            nft: image_id("nft_transfer")?,
+            batch: image_id("batch")?,
+            aggregate: image_id("aggregate")?,
+        })
+    }
+}
+
+/// Deploy `L3L1Escrow`, submitting requests to the Bonsai proxy at `proxy` and settling payments
+/// made on `l3`.
+pub async fn deploy_escrow<M: Middleware>(
+    client: Arc<M>,
+    proxy: Address,
+    l3: L3Chain,
+    image_ids: &EscrowImageIds,
+) -> Result<L3L1Escrow<M>, ContractError<M>> {
+    let (escrow, _) = deploy_escrow_with_receipt(client, proxy, l3, image_ids).await?;
+    Ok(escrow)
+}
+
+/// Like [deploy_escrow], also returning the receipt of the deployment transaction, to record
+/// where the escrow was deployed.
+pub async fn deploy_escrow_with_receipt<M: Middleware>(
+    client: Arc<M>,
+    proxy: Address,
+    l3: L3Chain,
+    image_ids: &EscrowImageIds,
+) -> Result<(L3L1Escrow<M>, TransactionReceipt), ContractError<M>> {
+    L3L1Escrow::deploy(
+        client,
+        (
+            proxy,
+            U256::from(l3.chain_id),
+            l3.block_hashes,
+            image_ids.native.0,
+            image_ids.erc20.0,
+            image_ids.nft.0,
+            image_ids.batch.0,
+            image_ids.aggregate.0,
+        ),
+    )?
+    .send_with_receipt()
+    .await
+}
+
+/// Deploy a `MockBonsaiProxy`, for tests and local networks without a Bonsai proxy.
+pub async fn deploy_mock_proxy<M: Middleware>(
+    client: Arc<M>,
+) -> Result<MockBonsaiProxy<M>, ContractError<M>> {
+    MockBonsaiProxy::deploy(client, ())?.send().await
+}
+
+/// Deploy a `MockL3BlockHashOracle`, for tests and local networks without an L3 output oracle.
+pub async fn deploy_mock_block_hashes<M: Middleware>(
+    client: Arc<M>,
+) -> Result<MockL3BlockHashOracle<M>, ContractError<M>> {
+    MockL3BlockHashOracle::deploy(client, ())?.send().await
+}
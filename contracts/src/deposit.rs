@@ -0,0 +1,54 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Build escrow claims from the deposit records of a deployed `L3L1Escrow`.
+//!
+//! `L3L1Escrow.checkPaymentStatus` encodes the claim on-chain. Host tools running the guest
+//! themselves, e.g. to batch claims or test a payment before submitting it, use [deposit_claim]
+//! to get the same claim, including the payment window stored with the deposit.
+
+use ethers::contract::ContractError;
+use ethers::providers::Middleware;
+use ethers::types::{H256, U256};
+
+use crate::{AssetKind, EscrowClaim, L3L1Escrow};
+
+/// Claim that `tx_hash` settles deposit `escrow_id`, as `L3L1Escrow` would submit it.
+///
+/// Returns `None` if there is no open deposit with that ID.
+pub async fn deposit_claim<M: Middleware>(
+    escrow: &L3L1Escrow<M>,
+    escrow_id: U256,
+    tx_hash: H256,
+) -> Result<Option<EscrowClaim>, ContractError<M>> {
+    let (depositor, creditor, amount, l3, earliest_time, latest_time) =
+        escrow.deposits(escrow_id).call().await?;
+    if amount.is_zero() {
+        return Ok(None);
+    }
+    Ok(AssetKind::from_u8(l3.kind).map(|kind| EscrowClaim {
+        escrow: escrow.address(),
+        escrow_id,
+        tx_hash,
+        kind,
+        token: l3.token,
+        token_id: l3.token_id,
+        depositor,
+        creditor,
+        amount: l3.amount,
+        // The contract narrows the window to `uint64` when encoding the claim.
+        not_before: earliest_time.low_u64(),
+        not_after: latest_time.low_u64(),
+    }))
+}
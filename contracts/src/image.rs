@@ -0,0 +1,42 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Image IDs trusted by a deployed `L3L1Escrow`, to compare against a local build of the guests.
+
+use ethers::contract::ContractError;
+use ethers::providers::Middleware;
+use ethers::types::H256;
+
+use crate::L3L1Escrow;
+
+/// Getter on `L3L1Escrow` for the image ID of each guest, by guest name.
+const IMAGE_ID_GETTERS: [(&str, &str); 5] = [
+    ("native_transfer", "image_id"),
+    ("erc20_transfer", "erc20_image_id"),
+    ("nft_transfer", "nft_image_id"),
+    ("batch", "batch_image_id"),
+    ("aggregate", "aggregate_image_id"),
+];
+
+/// Image ID `escrow` submits requests with for each guest, keyed by guest name.
+pub async fn deployed_image_ids<M: Middleware>(
+    escrow: &L3L1Escrow<M>,
+) -> Result<Vec<(&'static str, H256)>, ContractError<M>> {
+    let mut image_ids = Vec::new();
+    for (guest, getter) in IMAGE_ID_GETTERS {
+        let image_id: [u8; 32] = escrow.method(getter, ())?.call().await?;
+        image_ids.push((guest, H256(image_id)));
+    }
+    Ok(image_ids)
+}
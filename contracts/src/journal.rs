@@ -0,0 +1,181 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Typed access to the journals committed by the escrow guest.
+//!
+//! Use [EscrowJournal::abi_decode] on the journal of a RISC Zero receipt, or on the journal passed
+//! to `L3L1Escrow.callback`, then [check_claim] to see whether it settles a given claim. Journals
+//! of the batch guest decode with [BatchJournal::abi_decode] into one result per claim, and those of
+//! the aggregate guest with [AggregateJournal::abi_decode] into one result for several payments.
+
+use std::error::Error;
+use std::fmt;
+
+pub use escrow_core::{
+    AggregateJournal, BatchJournal, ClaimStatus, DecodeError, EscrowJournal, PartBlock,
+    AGGREGATE_VERSION, BATCH_VERSION, JOURNAL_VERSION,
+};
+
+use crate::EscrowClaim;
+
+/// Reason a journal does not settle a claim.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JournalMismatch {
+    /// The guest did not accept the payment, for the given reason.
+    Rejected(ClaimStatus),
+    Escrow,
+    EscrowId,
+    TxHash,
+    Kind,
+    Token,
+    TokenId,
+    Payer,
+    Payee,
+    Amount,
+    Reference,
+    Window,
+}
+
+impl fmt::Display for JournalMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalMismatch::Rejected(status) => {
+                write!(f, "payment was rejected by the guest: {status}")
+            }
+            JournalMismatch::Escrow => write!(f, "journal is for another escrow contract"),
+            JournalMismatch::EscrowId => write!(f, "journal is for another deposit"),
+            JournalMismatch::TxHash => write!(f, "journal is for another transaction"),
+            JournalMismatch::Kind => write!(f, "payment was made in another kind of asset"),
+            JournalMismatch::Token => write!(f, "payment was made in another token"),
+            JournalMismatch::TokenId => write!(f, "payment was made with another token ID"),
+            JournalMismatch::Payer => write!(f, "payment was not sent by the creditor"),
+            JournalMismatch::Payee => write!(f, "payment was not sent to the depositor"),
+            JournalMismatch::Amount => write!(f, "payment amount does not match the deposit"),
+            JournalMismatch::Reference => write!(f, "payment does not reference the deposit"),
+            JournalMismatch::Window => {
+                write!(f, "payment is outside the time window of the deposit")
+            }
+        }
+    }
+}
+
+impl Error for JournalMismatch {}
+
+/// Check that `journal` settles `claim`, mirroring the checks in `L3L1Escrow.bonsai_callback`.
+pub fn check_claim(journal: &EscrowJournal, claim: &EscrowClaim) -> Result<(), JournalMismatch> {
+    let checks = [
+        (
+            journal.status.is_accepted(),
+            JournalMismatch::Rejected(journal.status),
+        ),
+        (journal.escrow == claim.escrow, JournalMismatch::Escrow),
+        (
+            journal.escrow_id == claim.escrow_id,
+            JournalMismatch::EscrowId,
+        ),
+        (journal.tx_hash == claim.tx_hash, JournalMismatch::TxHash),
+        (journal.kind == claim.kind, JournalMismatch::Kind),
+        (journal.token == claim.token, JournalMismatch::Token),
+        (journal.token_id == claim.token_id, JournalMismatch::TokenId),
+        (journal.payer == claim.creditor, JournalMismatch::Payer),
+        (journal.payee == claim.depositor, JournalMismatch::Payee),
+        (journal.amount == claim.amount, JournalMismatch::Amount),
+        (
+            journal.reference == claim.reference(),
+            JournalMismatch::Reference,
+        ),
+        (
+            claim.in_window(journal.block_timestamp),
+            JournalMismatch::Window,
+        ),
+    ];
+    match checks.into_iter().find(|(ok, _)| !ok) {
+        Some((_, mismatch)) => Err(mismatch),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::{Address, H256, U256};
+
+    use super::*;
+    use crate::AssetKind;
+
+    fn claim() -> EscrowClaim {
+        EscrowClaim {
+            escrow: Address::repeat_byte(0xee),
+            escrow_id: U256::from(7),
+            tx_hash: H256::repeat_byte(0xab),
+            kind: AssetKind::Erc721,
+            token: Address::repeat_byte(0x70),
+            token_id: U256::from(42),
+            depositor: Address::repeat_byte(0x11),
+            creditor: Address::repeat_byte(0x22),
+            amount: U256::one(),
+            not_before: 1_680_000_000,
+            not_after: 1_680_604_800,
+        }
+    }
+
+    fn journal() -> EscrowJournal {
+        EscrowJournal {
+            status: ClaimStatus::Accepted,
+            chain_id: 1001,
+            block_hash: H256::repeat_byte(0xbb),
+            block_number: 12,
+            block_timestamp: 1_680_000_060,
+            tx_hash: H256::repeat_byte(0xab),
+            kind: AssetKind::Erc721,
+            token: Address::repeat_byte(0x70),
+            token_id: U256::from(42),
+            payer: Address::repeat_byte(0x22),
+            payee: Address::repeat_byte(0x11),
+            amount: U256::one(),
+            escrow: Address::repeat_byte(0xee),
+            escrow_id: U256::from(7),
+            reference: claim().reference(),
+        }
+    }
+
+    #[test]
+    fn accepts_matching_journal() {
+        let encoded = journal().abi_encode();
+        let decoded = EscrowJournal::abi_decode(&encoded).unwrap();
+        assert_eq!(check_claim(&decoded, &claim()), Ok(()));
+    }
+
+    #[test]
+    fn reports_first_mismatch() {
+        let mut journal = journal();
+        journal.escrow_id = U256::from(8);
+        journal.amount = U256::from(2);
+        assert_eq!(
+            check_claim(&journal, &claim()),
+            Err(JournalMismatch::EscrowId)
+        );
+    }
+
+    #[test]
+    fn reports_guest_status() {
+        let mut journal = journal();
+        journal.status = ClaimStatus::WrongRecipient;
+        let err = check_claim(&journal, &claim()).unwrap_err();
+        assert_eq!(err, JournalMismatch::Rejected(ClaimStatus::WrongRecipient));
+        assert_eq!(
+            err.to_string(),
+            "payment was rejected by the guest: payment was not sent to the depositor"
+        );
+    }
+}
@@ -12,13 +12,68 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Rust interfaces for deploying and interfacing with HelloBonsai.
+//! Rust interfaces for deploying and interfacing with `L3L1Escrow` and the Bonsai contracts.
 //!
 //! Contract wrappers are automatically generated by [ethers] and provides useful function wrapping
 //! the `ethers::contract::Contract` type. See the [ethers] documentation for more information.
 //!
+//! The `L3L1Escrow` bindings, with its event and struct types, are at the root of the crate. The
+//! Bonsai contracts and the L3 block hash oracle each get a module, as they declare calls of the
+//! same name.
+//!
 //! [ethers]: https://docs.rs/ethers/latest/ethers/
 
+pub mod deploy;
+pub mod deposit;
+pub mod image;
+pub mod journal;
+
+pub use escrow_core::{AssetKind, EscrowClaim, CLAIM_VERSION};
 use ethers::prelude::abigen;
 
-abigen!(HelloBonsai, "artifacts/HelloBonsai.sol/HelloBonsai.json");
+pub use crate::app::IBonsaiApp;
+pub use crate::mock::{MockBonsaiProxy, MockL3BlockHashOracle};
+pub use crate::oracle::IL3BlockHashOracle;
+pub use crate::proxy::IBonsaiProxy;
+
+abigen!(L3L1Escrow, "artifacts/L3L1Escrow.sol/L3L1Escrow.json");
+
+/// Bindings for the interface of the Bonsai proxy, which applications submit requests to.
+pub mod proxy {
+    use ethers::prelude::abigen;
+
+    abigen!(IBonsaiProxy, "artifacts/IBonsaiProxy.sol/IBonsaiProxy.json");
+}
+
+/// Bindings for the interface applications implement to receive callbacks from the Bonsai proxy.
+pub mod app {
+    use ethers::prelude::abigen;
+
+    abigen!(IBonsaiApp, "artifacts/IBonsaiApp.sol/IBonsaiApp.json");
+}
+
+/// Bindings for the source of L3 block hashes `L3L1Escrow` anchors journals to.
+pub mod oracle {
+    use ethers::prelude::abigen;
+
+    abigen!(
+        IL3BlockHashOracle,
+        "artifacts/IL3BlockHashOracle.sol/IL3BlockHashOracle.json"
+    );
+}
+
+/// Bindings for the test contracts: the mock of the Bonsai proxy, which emits a `SubmitRequest`
+/// event for every request and lets tests send the callback, and an L3 block hash oracle tests
+/// publish hashes to.
+pub mod mock {
+    use ethers::prelude::abigen;
+
+    abigen!(
+        MockBonsaiProxy,
+        "artifacts/MockBonsaiProxy.sol/MockBonsaiProxy.json"
+    );
+    abigen!(
+        MockL3BlockHashOracle,
+        "artifacts/MockL3BlockHashOracle.sol/MockL3BlockHashOracle.json"
+    );
+}
@@ -0,0 +1,887 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod utils;
+
+use std::error::Error;
+
+use escrow_core::aggregate::{aggregate_tx_hash, encode_tx_hashes};
+use escrow_core::input::{decode_input_len, LEN_PREFIX_LEN};
+use ethers::prelude::*;
+use hello_bonsai_contracts::deploy::{
+    deploy_escrow, deploy_mock_block_hashes, deploy_mock_proxy, EscrowImageIds, L3Chain,
+};
+use hello_bonsai_contracts::deposit::deposit_claim;
+use hello_bonsai_contracts::image::deployed_image_ids;
+use hello_bonsai_contracts::journal::{
+    AggregateJournal, BatchJournal, ClaimStatus, EscrowJournal, PartBlock,
+};
+use hello_bonsai_contracts::{AssetKind, EscrowClaim, L3L1Escrow, L3Payment, MockBonsaiProxy};
+use hello_bonsai_methods::manifest::GuestManifest;
+use utils::{get_ganache_client, Client};
+
+type ClientMiddleware = SignerMiddleware<Provider<Ws>, LocalWallet>;
+
+const NATIVE_IMAGE_ID: [u8; 32] = [0u8; 32];
+const ERC20_IMAGE_ID: [u8; 32] = [1u8; 32];
+const NFT_IMAGE_ID: [u8; 32] = [2u8; 32];
+const BATCH_IMAGE_ID: [u8; 32] = [3u8; 32];
+const AGGREGATE_IMAGE_ID: [u8; 32] = [4u8; 32];
+
+const IMAGE_IDS: EscrowImageIds = EscrowImageIds {
+    native: H256(NATIVE_IMAGE_ID),
+    erc20: H256(ERC20_IMAGE_ID),
+    nft: H256(NFT_IMAGE_ID),
+    batch: H256(BATCH_IMAGE_ID),
+    aggregate: H256(AGGREGATE_IMAGE_ID),
+};
+
+const L3_CHAIN_ID: u64 = 1001;
+/// L3 block the settling journals point at, published to the mock block hash oracle.
+const L3_BLOCK_NUMBER: u64 = 1;
+const L3_BLOCK_HASH: H256 = H256([0xb1; 32]);
+
+/// Deploy the proxy without a worker, so requests can be inspected and callbacks sent by hand. The
+/// block hash oracle knows only [L3_BLOCK_HASH].
+async fn deploy(
+    client: &Client,
+) -> Result<
+    (
+        MockBonsaiProxy<ClientMiddleware>,
+        L3L1Escrow<ClientMiddleware>,
+    ),
+    Box<dyn Error>,
+> {
+    let proxy = deploy_mock_proxy(client.clone()).await?;
+    let block_hashes = deploy_mock_block_hashes(client.clone()).await?;
+    block_hashes
+        .set_block_hash(L3_BLOCK_NUMBER.into(), L3_BLOCK_HASH.0)
+        .send()
+        .await?
+        .await?;
+    let l3 = L3Chain {
+        chain_id: L3_CHAIN_ID,
+        block_hashes: block_hashes.address(),
+    };
+    let escrow = deploy_escrow(client.clone(), proxy.address(), l3, &IMAGE_IDS).await?;
+    Ok((proxy, escrow))
+}
+
+fn native(amount: U256) -> L3Payment {
+    L3Payment {
+        kind: AssetKind::Native as u8,
+        token: Address::zero(),
+        token_id: U256::zero(),
+        amount,
+    }
+}
+
+/// Deposit `amount` wei for `creditor` and request a check of `tx_hash`, returning the submitted
+/// image ID and claim.
+async fn deposit_and_check(
+    proxy: &MockBonsaiProxy<ClientMiddleware>,
+    escrow: &L3L1Escrow<ClientMiddleware>,
+    creditor: Address,
+    amount: U256,
+    l3: L3Payment,
+    tx_hash: H256,
+) -> Result<([u8; 32], EscrowClaim), Box<dyn Error>> {
+    escrow
+        .deposit(creditor, amount, l3)
+        .value(amount)
+        .send()
+        .await?
+        .await?;
+    escrow
+        .check_payment_status(U256::zero(), tx_hash.into(), Bytes::default())
+        .send()
+        .await?
+        .await?;
+    let requests = proxy
+        .submit_request_filter()
+        .from_block(0u64)
+        .query()
+        .await?;
+    let request = requests.last().ok_or("no request submitted")?;
+    Ok((
+        request.image_id,
+        EscrowClaim::abi_decode(unframe(&request.input)?)?,
+    ))
+}
+
+/// Block of an aggregate part known to the block hash oracle, inside the window of `claim`.
+fn anchored_block(claim: &EscrowClaim) -> PartBlock {
+    PartBlock {
+        hash: L3_BLOCK_HASH,
+        number: L3_BLOCK_NUMBER,
+        timestamp: claim.not_before,
+    }
+}
+
+/// Move the clock of the chain forward by `seconds`.
+async fn increase_time(client: &Client, seconds: u64) -> Result<(), Box<dyn Error>> {
+    let provider = client.provider();
+    provider
+        .request::<_, u64>("evm_increaseTime", [seconds])
+        .await?;
+    provider.request::<_, String>("evm_mine", ()).await?;
+    Ok(())
+}
+
+/// Strip the length prefix `L3L1Escrow` frames guest inputs with, checking it.
+fn unframe(input: &[u8]) -> Result<&[u8], Box<dyn Error>> {
+    let (len, input) = input.split_at(LEN_PREFIX_LEN);
+    assert_eq!(decode_input_len(len)?, input.len());
+    Ok(input)
+}
+
+/// Journal of a successful check of `claim`.
+fn settling_journal(claim: &EscrowClaim) -> EscrowJournal {
+    EscrowJournal {
+        status: ClaimStatus::Accepted,
+        chain_id: L3_CHAIN_ID,
+        block_hash: L3_BLOCK_HASH,
+        block_number: L3_BLOCK_NUMBER,
+        block_timestamp: claim.not_before,
+        tx_hash: claim.tx_hash,
+        kind: claim.kind,
+        token: claim.token,
+        token_id: claim.token_id,
+        payer: claim.creditor,
+        payee: claim.depositor,
+        amount: claim.amount,
+        escrow: claim.escrow,
+        escrow_id: claim.escrow_id,
+        reference: claim.reference(),
+    }
+}
+
+#[tokio::test]
+async fn check_payment_status_submits_escrow_claim() -> Result<(), Box<dyn Error>> {
+    let (_ganache, client) = get_ganache_client().await?;
+    let (proxy, escrow) = deploy(&client).await?;
+
+    let creditor = Address::random();
+    let amount = U256::from(1_000_000u64);
+    escrow
+        .deposit(creditor, amount, native(amount))
+        .value(amount)
+        .send()
+        .await?
+        .await?;
+
+    let tx_hash = H256::random();
+    let witness = vec![0xde, 0xad, 0xbe, 0xef];
+    escrow
+        .check_payment_status(U256::zero(), tx_hash.into(), witness.clone().into())
+        .send()
+        .await?
+        .await?;
+
+    let requests = proxy
+        .submit_request_filter()
+        .from_block(0u64)
+        .query()
+        .await?;
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].image_id, NATIVE_IMAGE_ID);
+    let (claim, rest) = unframe(&requests[0].input)?.split_at(EscrowClaim::ENCODED_LEN);
+    let claim = EscrowClaim::abi_decode(claim)?;
+    let (_, _, _, _, earliest_time, latest_time) = escrow.deposits(U256::zero()).call().await?;
+    assert_eq!(
+        claim,
+        EscrowClaim {
+            escrow: escrow.address(),
+            escrow_id: U256::zero(),
+            tx_hash,
+            kind: AssetKind::Native,
+            token: Address::zero(),
+            token_id: U256::zero(),
+            depositor: client.address(),
+            creditor,
+            amount,
+            not_before: earliest_time.as_u64(),
+            not_after: latest_time.as_u64(),
+        }
+    );
+    assert_eq!(claim.not_after - claim.not_before, 7 * 24 * 60 * 60);
+    assert_eq!(rest, witness.as_slice());
+
+    // The guest reads its input in whole words.
+    let unaligned =
+        escrow.check_payment_status(U256::zero(), tx_hash.into(), witness[..3].to_vec().into());
+    assert!(unaligned.send().await.is_err());
+
+    // Host tools rebuild the same claim from the deposit record.
+    let rebuilt = deposit_claim(&escrow, U256::zero(), tx_hash).await?;
+    assert_eq!(rebuilt, Some(claim));
+    assert_eq!(deposit_claim(&escrow, U256::one(), tx_hash).await?, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn callback_accepts_escrow_journal() -> Result<(), Box<dyn Error>> {
+    let (_ganache, client) = get_ganache_client().await?;
+    let (proxy, escrow) = deploy(&client).await?;
+
+    let creditor = Address::random();
+    let amount = U256::from(1_000_000u64);
+    let (_, claim) = deposit_and_check(
+        &proxy,
+        &escrow,
+        creditor,
+        amount,
+        native(amount),
+        H256::random(),
+    )
+    .await?;
+
+    // The payment must carry the reference of this deposit, not just the right terms.
+    let reference: [u8; 32] = escrow.escrow_reference(U256::zero()).call().await?;
+    assert_eq!(H256::from(reference), claim.reference());
+    let mut untagged = settling_journal(&claim);
+    untagged.reference = H256::zero();
+    let untagged = proxy.send_callback(
+        escrow.address(),
+        NATIVE_IMAGE_ID,
+        untagged.abi_encode().into(),
+    );
+    assert!(untagged.send().await.is_err());
+
+    let journal = settling_journal(&claim);
+    proxy
+        .send_callback(
+            escrow.address(),
+            NATIVE_IMAGE_ID,
+            journal.abi_encode().into(),
+        )
+        .send()
+        .await?
+        .await?;
+
+    assert_eq!(client.get_balance(creditor, None).await?, amount);
+    let (depositor, _, deposit_amount, _, _, _) = escrow.deposits(U256::zero()).call().await?;
+    assert_eq!(depositor, Address::zero());
+    assert_eq!(deposit_amount, U256::zero());
+    Ok(())
+}
+
+#[tokio::test]
+async fn callback_records_guest_rejection() -> Result<(), Box<dyn Error>> {
+    let (_ganache, client) = get_ganache_client().await?;
+    let (proxy, escrow) = deploy(&client).await?;
+
+    let creditor = Address::random();
+    let amount = U256::from(1_000_000u64);
+    let (_, claim) = deposit_and_check(
+        &proxy,
+        &escrow,
+        creditor,
+        amount,
+        native(amount),
+        H256::random(),
+    )
+    .await?;
+
+    // The callback goes through, but the deposit stays open for another claim.
+    let mut journal = settling_journal(&claim);
+    journal.status = ClaimStatus::WrongAmount;
+    proxy
+        .send_callback(
+            escrow.address(),
+            NATIVE_IMAGE_ID,
+            journal.abi_encode().into(),
+        )
+        .send()
+        .await?
+        .await?;
+
+    assert_eq!(client.get_balance(creditor, None).await?, U256::zero());
+    let (_, _, deposit_amount, _, _, _) = escrow.deposits(U256::zero()).call().await?;
+    assert_eq!(deposit_amount, amount);
+    let rejections = escrow
+        .payment_rejected_filter()
+        .from_block(0u64)
+        .query()
+        .await?;
+    assert_eq!(rejections.len(), 1);
+    assert_eq!(rejections[0].escrow_id, U256::zero());
+    assert_eq!(rejections[0].status, ClaimStatus::WrongAmount as u8);
+    // Tagged with the claimed transaction, so its claimant can tell it from other claims.
+    assert_eq!(H256::from(rejections[0].tx_hash), claim.tx_hash);
+    Ok(())
+}
+
+#[tokio::test]
+async fn callback_rejects_payment_outside_window() -> Result<(), Box<dyn Error>> {
+    let (_ganache, client) = get_ganache_client().await?;
+    let (proxy, escrow) = deploy(&client).await?;
+
+    let creditor = Address::random();
+    let amount = U256::from(1_000_000u64);
+    let (_, claim) = deposit_and_check(
+        &proxy,
+        &escrow,
+        creditor,
+        amount,
+        native(amount),
+        H256::random(),
+    )
+    .await?;
+
+    // A payment made before the deposit existed cannot settle it, even if it matches.
+    for block_timestamp in [claim.not_before - 1, claim.not_after + 1] {
+        let mut journal = settling_journal(&claim);
+        journal.block_timestamp = block_timestamp;
+        let stale = proxy.send_callback(
+            escrow.address(),
+            NATIVE_IMAGE_ID,
+            journal.abi_encode().into(),
+        );
+        assert!(stale.send().await.is_err());
+    }
+    assert_eq!(client.get_balance(creditor, None).await?, U256::zero());
+    Ok(())
+}
+
+#[tokio::test]
+async fn callback_rejects_unanchored_l3_block() -> Result<(), Box<dyn Error>> {
+    let (_ganache, client) = get_ganache_client().await?;
+    let (proxy, escrow) = deploy(&client).await?;
+
+    let creditor = Address::random();
+    let amount = U256::from(1_000_000u64);
+    let (_, claim) = deposit_and_check(
+        &proxy,
+        &escrow,
+        creditor,
+        amount,
+        native(amount),
+        H256::random(),
+    )
+    .await?;
+
+    // A header made up by the caller hashes to a block the oracle does not know, and a payment
+    // on another chain, or without replay protection, does not count either.
+    let mut forged = settling_journal(&claim);
+    forged.block_hash = H256::random();
+    let mut other_number = settling_journal(&claim);
+    other_number.block_number = L3_BLOCK_NUMBER + 1;
+    let mut other_chain = settling_journal(&claim);
+    other_chain.chain_id = L3_CHAIN_ID + 1;
+    let mut unprotected = settling_journal(&claim);
+    unprotected.chain_id = 0;
+    for journal in [forged, other_number, other_chain, unprotected] {
+        let callback = proxy.send_callback(
+            escrow.address(),
+            NATIVE_IMAGE_ID,
+            journal.abi_encode().into(),
+        );
+        assert!(callback.send().await.is_err());
+    }
+    assert_eq!(client.get_balance(creditor, None).await?, U256::zero());
+
+    // Pinning chain zero would accept exactly those unprotected transactions.
+    let l3 = L3Chain {
+        chain_id: 0,
+        block_hashes: Address::zero(),
+    };
+    assert!(
+        deploy_escrow(client.clone(), proxy.address(), l3, &IMAGE_IDS)
+            .await
+            .is_err()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn deposit_rejects_zero_amounts() -> Result<(), Box<dyn Error>> {
+    let (_ganache, client) = get_ganache_client().await?;
+    let (_proxy, escrow) = deploy(&client).await?;
+
+    // Nothing locked on L1, nothing to refund or settle.
+    let empty = escrow.deposit(Address::random(), U256::zero(), native(U256::one()));
+    assert!(empty.send().await.is_err());
+
+    // Nothing asked for on L3.
+    let amount = U256::from(1_000_000u64);
+    let free = escrow
+        .deposit(Address::random(), amount, native(U256::zero()))
+        .value(amount);
+    assert!(free.send().await.is_err());
+
+    // A single ERC-721 token is paid, never none or several.
+    for nft_amount in [0u64, 2] {
+        let l3 = L3Payment {
+            kind: AssetKind::Erc721 as u8,
+            token: Address::random(),
+            token_id: U256::from(42),
+            amount: nft_amount.into(),
+        };
+        let nft = escrow.deposit(Address::random(), amount, l3).value(amount);
+        assert!(nft.send().await.is_err());
+    }
+    assert_eq!(escrow.next_escrow_id().call().await?, U256::zero());
+    Ok(())
+}
+
+#[tokio::test]
+async fn refund_returns_deposit_after_window() -> Result<(), Box<dyn Error>> {
+    let (_ganache, client) = get_ganache_client().await?;
+    let (_proxy, escrow) = deploy(&client).await?;
+
+    let amount = U256::from(1_000_000u64);
+    escrow
+        .deposit(Address::random(), amount, native(amount))
+        .value(amount)
+        .send()
+        .await?
+        .await?;
+    assert!(escrow.refund(U256::zero()).send().await.is_err());
+
+    // Claims made in the claim period after the payment window still hold the deposit.
+    let payment_period = escrow.payment_period().call().await?.as_u64();
+    increase_time(&client, payment_period + 1).await?;
+    assert!(escrow.refund(U256::zero()).send().await.is_err());
+
+    let claim_period = escrow.claim_period().call().await?.as_u64();
+    let proving_period = escrow.proving_period().call().await?.as_u64();
+    increase_time(&client, claim_period + proving_period).await?;
+    escrow.refund(U256::zero()).send().await?.await?;
+    let (_, _, deposit_amount, _, _, _) = escrow.deposits(U256::zero()).call().await?;
+    assert_eq!(deposit_amount, U256::zero());
+    let refunds = escrow
+        .deposit_refunded_filter()
+        .from_block(0u64)
+        .query()
+        .await?;
+    assert_eq!(refunds.len(), 1);
+    assert_eq!(refunds[0].depositor, client.address());
+    assert_eq!(refunds[0].amount, amount);
+    assert!(escrow.refund(U256::zero()).send().await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn refund_waits_for_claims_made_after_window() -> Result<(), Box<dyn Error>> {
+    let (_ganache, client) = get_ganache_client().await?;
+    let (proxy, escrow) = deploy(&client).await?;
+
+    let creditor = Address::random();
+    let amount = U256::from(1_000_000u64);
+    escrow
+        .deposit(creditor, amount, native(amount))
+        .value(amount)
+        .send()
+        .await?
+        .await?;
+
+    // The payment was made inside the window, but only claimed once it closed.
+    let payment_period = escrow.payment_period().call().await?.as_u64();
+    increase_time(&client, payment_period + 1).await?;
+    escrow
+        .check_payment_status(U256::zero(), H256::random().into(), Bytes::default())
+        .send()
+        .await?
+        .await?;
+    let requests = proxy
+        .submit_request_filter()
+        .from_block(0u64)
+        .query()
+        .await?;
+    let request = requests.last().ok_or("no request submitted")?;
+    let claim = EscrowClaim::abi_decode(unframe(&request.input)?)?;
+
+    // The depositor cannot take the deposit back while Bonsai proves the claim.
+    let claim_period = escrow.claim_period().call().await?.as_u64();
+    increase_time(&client, claim_period).await?;
+    assert!(escrow.refund(U256::zero()).send().await.is_err());
+
+    proxy
+        .send_callback(
+            escrow.address(),
+            NATIVE_IMAGE_ID,
+            settling_journal(&claim).abi_encode().into(),
+        )
+        .send()
+        .await?
+        .await?;
+    assert_eq!(client.get_balance(creditor, None).await?, amount);
+
+    let proving_period = escrow.proving_period().call().await?.as_u64();
+    increase_time(&client, proving_period).await?;
+    assert!(escrow.refund(U256::zero()).send().await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn check_payment_status_rejects_claims_after_claim_period() -> Result<(), Box<dyn Error>> {
+    let (_ganache, client) = get_ganache_client().await?;
+    let (_proxy, escrow) = deploy(&client).await?;
+
+    let amount = U256::from(1_000_000u64);
+    escrow
+        .deposit(Address::random(), amount, native(amount))
+        .value(amount)
+        .send()
+        .await?
+        .await?;
+
+    let payment_period = escrow.payment_period().call().await?.as_u64();
+    let claim_period = escrow.claim_period().call().await?.as_u64();
+    increase_time(&client, payment_period + claim_period + 1).await?;
+    let late = escrow.check_payment_status(U256::zero(), H256::random().into(), Bytes::default());
+    assert!(late.send().await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn erc20_deposit_uses_erc20_guest() -> Result<(), Box<dyn Error>> {
+    let (_ganache, client) = get_ganache_client().await?;
+    let (proxy, escrow) = deploy(&client).await?;
+
+    // Each wei deposited on L1 is paid for with 2500 units of the L3 token.
+    let creditor = Address::random();
+    let token = Address::random();
+    let amount = U256::from(1_000_000u64);
+    let l3 = L3Payment {
+        kind: AssetKind::Erc20 as u8,
+        token,
+        token_id: U256::zero(),
+        amount: U256::from(2_500_000_000u64),
+    };
+    let (image_id, claim) =
+        deposit_and_check(&proxy, &escrow, creditor, amount, l3, H256::random()).await?;
+    assert_eq!(image_id, ERC20_IMAGE_ID);
+    assert_eq!(claim.kind, AssetKind::Erc20);
+    assert_eq!(claim.token, token);
+    assert_eq!(claim.amount, U256::from(2_500_000_000u64));
+
+    // A native payment of the same amount must not settle a token deposit.
+    let mut journal = settling_journal(&claim);
+    journal.kind = AssetKind::Native;
+    journal.token = Address::zero();
+    let native = proxy.send_callback(
+        escrow.address(),
+        NATIVE_IMAGE_ID,
+        journal.abi_encode().into(),
+    );
+    assert!(native.send().await.is_err());
+
+    proxy
+        .send_callback(
+            escrow.address(),
+            ERC20_IMAGE_ID,
+            settling_journal(&claim).abi_encode().into(),
+        )
+        .send()
+        .await?
+        .await?;
+    assert_eq!(client.get_balance(creditor, None).await?, amount);
+    Ok(())
+}
+
+#[tokio::test]
+async fn nft_sale_settles_with_token_transfer() -> Result<(), Box<dyn Error>> {
+    let (_ganache, client) = get_ganache_client().await?;
+    let (proxy, escrow) = deploy(&client).await?;
+
+    // The buyer locks the price on L1, the seller proves they sent token 42 to the buyer on L3.
+    let seller = Address::random();
+    let collection = Address::random();
+    let price = U256::from(1_000_000u64);
+    let l3 = L3Payment {
+        kind: AssetKind::Erc721 as u8,
+        token: collection,
+        token_id: U256::from(42),
+        amount: U256::one(),
+    };
+    let (image_id, claim) =
+        deposit_and_check(&proxy, &escrow, seller, price, l3, H256::random()).await?;
+    assert_eq!(image_id, NFT_IMAGE_ID);
+    assert_eq!(claim.kind, AssetKind::Erc721);
+    assert_eq!(claim.token, collection);
+    assert_eq!(claim.token_id, U256::from(42));
+    assert_eq!(claim.depositor, client.address());
+    assert_eq!(claim.creditor, seller);
+
+    // Sending another token from the same collection does not complete the sale.
+    let mut journal = settling_journal(&claim);
+    journal.token_id = U256::from(43);
+    let other = proxy.send_callback(escrow.address(), NFT_IMAGE_ID, journal.abi_encode().into());
+    assert!(other.send().await.is_err());
+
+    proxy
+        .send_callback(
+            escrow.address(),
+            NFT_IMAGE_ID,
+            settling_journal(&claim).abi_encode().into(),
+        )
+        .send()
+        .await?
+        .await?;
+    assert_eq!(client.get_balance(seller, None).await?, price);
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_settles_each_accepted_result() -> Result<(), Box<dyn Error>> {
+    let (_ganache, client) = get_ganache_client().await?;
+    let (proxy, escrow) = deploy(&client).await?;
+
+    let creditors = [Address::random(), Address::random()];
+    let amount = U256::from(1_000_000u64);
+    for creditor in creditors {
+        escrow
+            .deposit(creditor, amount, native(amount))
+            .value(amount)
+            .send()
+            .await?
+            .await?;
+    }
+
+    let tx_hashes = [H256::random(), H256::random()];
+    escrow
+        .check_payment_status_batch(
+            vec![U256::zero(), U256::one()],
+            tx_hashes.map(H256::to_fixed_bytes).to_vec(),
+            Bytes::default(),
+        )
+        .send()
+        .await?
+        .await?;
+
+    let requests = proxy
+        .submit_request_filter()
+        .from_block(0u64)
+        .query()
+        .await?;
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].image_id, BATCH_IMAGE_ID);
+    let (count, claims) = unframe(&requests[0].input)?.split_at(32);
+    assert_eq!(U256::from_big_endian(count), U256::from(2));
+    let claims = claims
+        .chunks(EscrowClaim::ENCODED_LEN)
+        .map(EscrowClaim::abi_decode)
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(claims.len(), 2);
+    assert_eq!(claims[1].tx_hash, tx_hashes[1]);
+
+    // The second payment went to someone else, which must not block the first one.
+    let mut rejected = settling_journal(&claims[1]);
+    rejected.payee = Address::random();
+    let batch = BatchJournal {
+        results: vec![settling_journal(&claims[0]), rejected],
+    };
+    proxy
+        .send_callback(escrow.address(), BATCH_IMAGE_ID, batch.abi_encode().into())
+        .send()
+        .await?
+        .await?;
+
+    assert_eq!(client.get_balance(creditors[0], None).await?, amount);
+    assert_eq!(client.get_balance(creditors[1], None).await?, U256::zero());
+    let rejections = escrow
+        .payment_rejected_filter()
+        .from_block(0u64)
+        .query()
+        .await?;
+    assert_eq!(rejections.len(), 1);
+    assert_eq!(rejections[0].escrow_id, U256::one());
+    assert_eq!(rejections[0].status, ClaimStatus::Accepted as u8);
+    Ok(())
+}
+
+#[tokio::test]
+async fn aggregate_settles_payment_made_in_parts() -> Result<(), Box<dyn Error>> {
+    let (_ganache, client) = get_ganache_client().await?;
+    let (proxy, escrow) = deploy(&client).await?;
+
+    let creditor = Address::random();
+    let amount = U256::from(1_000_000u64);
+    escrow
+        .deposit(creditor, amount, native(amount))
+        .value(amount)
+        .send()
+        .await?
+        .await?;
+
+    let tx_hashes = vec![H256::random(), H256::random()];
+    escrow
+        .check_payment_status_aggregate(
+            U256::zero(),
+            tx_hashes.iter().map(H256::to_fixed_bytes).collect(),
+            Bytes::default(),
+        )
+        .send()
+        .await?
+        .await?;
+
+    let requests = proxy
+        .submit_request_filter()
+        .from_block(0u64)
+        .query()
+        .await?;
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].image_id, AGGREGATE_IMAGE_ID);
+    let (claim, hashes) = unframe(&requests[0].input)?.split_at(EscrowClaim::ENCODED_LEN);
+    let claim = EscrowClaim::abi_decode(claim)?;
+    assert_eq!(claim.tx_hash, aggregate_tx_hash(&tx_hashes));
+    assert_eq!(hashes.to_vec(), encode_tx_hashes(&tx_hashes));
+
+    // The journal must list the transactions its hash was computed from.
+    let mut journal = AggregateJournal {
+        result: settling_journal(&claim),
+        tx_hashes: tx_hashes[..1].to_vec(),
+        blocks: vec![anchored_block(&claim); 1],
+    };
+    let partial = proxy.send_callback(
+        escrow.address(),
+        AGGREGATE_IMAGE_ID,
+        journal.abi_encode().into(),
+    );
+    assert!(partial.send().await.is_err());
+
+    // Unless the guest could not decode its input, which is recorded whatever the hashes.
+    let undecodable = AggregateJournal {
+        result: EscrowJournal {
+            status: ClaimStatus::DecodeError,
+            ..journal.result.clone()
+        },
+        tx_hashes: vec![],
+        blocks: vec![],
+    };
+    proxy
+        .send_callback(
+            escrow.address(),
+            AGGREGATE_IMAGE_ID,
+            undecodable.abi_encode().into(),
+        )
+        .send()
+        .await?
+        .await?;
+    let rejections = escrow
+        .payment_rejected_filter()
+        .from_block(0u64)
+        .query()
+        .await?;
+    assert_eq!(rejections.len(), 1);
+    assert_eq!(rejections[0].status, ClaimStatus::DecodeError as u8);
+
+    journal.tx_hashes = tx_hashes;
+    journal.blocks = vec![anchored_block(&claim); 2];
+    proxy
+        .send_callback(
+            escrow.address(),
+            AGGREGATE_IMAGE_ID,
+            journal.abi_encode().into(),
+        )
+        .send()
+        .await?
+        .await?;
+    assert_eq!(client.get_balance(creditor, None).await?, amount);
+    Ok(())
+}
+
+#[tokio::test]
+async fn aggregate_rejects_unanchored_parts() -> Result<(), Box<dyn Error>> {
+    let (_ganache, client) = get_ganache_client().await?;
+    let (proxy, escrow) = deploy(&client).await?;
+
+    let creditor = Address::random();
+    let amount = U256::from(1_000_000u64);
+    escrow
+        .deposit(creditor, amount, native(amount))
+        .value(amount)
+        .send()
+        .await?
+        .await?;
+    let tx_hashes = vec![H256::random(), H256::random()];
+    escrow
+        .check_payment_status_aggregate(
+            U256::zero(),
+            tx_hashes.iter().map(H256::to_fixed_bytes).collect(),
+            Bytes::default(),
+        )
+        .send()
+        .await?
+        .await?;
+    let requests = proxy
+        .submit_request_filter()
+        .from_block(0u64)
+        .query()
+        .await?;
+    let claim = EscrowClaim::abi_decode(&unframe(&requests[0].input)?[..EscrowClaim::ENCODED_LEN])?;
+
+    // The latest part is anchored, but the earlier one was paid in a block made up by the
+    // creditor, lies outside the window, or is missing altogether.
+    let forged = PartBlock {
+        hash: H256::random(),
+        number: L3_BLOCK_NUMBER + 1,
+        ..anchored_block(&claim)
+    };
+    let late = PartBlock {
+        timestamp: claim.not_after + 1,
+        ..anchored_block(&claim)
+    };
+    for blocks in [
+        vec![forged, anchored_block(&claim)],
+        vec![late, anchored_block(&claim)],
+        vec![anchored_block(&claim)],
+    ] {
+        let journal = AggregateJournal {
+            result: settling_journal(&claim),
+            tx_hashes: tx_hashes.clone(),
+            blocks,
+        };
+        let callback = proxy.send_callback(
+            escrow.address(),
+            AGGREGATE_IMAGE_ID,
+            journal.abi_encode().into(),
+        );
+        assert!(callback.send().await.is_err());
+    }
+    assert_eq!(client.get_balance(creditor, None).await?, U256::zero());
+    let (_, _, deposit_amount, _, _, _) = escrow.deposits(U256::zero()).call().await?;
+    assert_eq!(deposit_amount, amount);
+    Ok(())
+}
+
+#[tokio::test]
+async fn deployed_image_ids_match_manifest() -> Result<(), Box<dyn Error>> {
+    let (_ganache, client) = get_ganache_client().await?;
+    let manifest = GuestManifest::embedded();
+
+    // The placeholder image IDs of the other tests are not those of the local build.
+    let (proxy, escrow) = deploy(&client).await?;
+    let mismatches = manifest.mismatches(deployed_image_ids(&escrow).await?);
+    assert_eq!(mismatches.len(), 5);
+
+    let image_ids = EscrowImageIds::from_guests(|name| {
+        manifest
+            .get(name)
+            .map(|entry| entry.image_id)
+            .ok_or(format!("{name} guest is missing from the manifest"))
+    })?;
+    let l3 = L3Chain {
+        chain_id: L3_CHAIN_ID,
+        block_hashes: Address::zero(),
+    };
+    let escrow = deploy_escrow(client.clone(), proxy.address(), l3, &image_ids).await?;
+    assert_eq!(
+        manifest.mismatches(deployed_image_ids(&escrow).await?),
+        vec![]
+    );
+    Ok(())
+}
@@ -25,16 +25,15 @@ use std::sync::Arc;
 use ethers::core::k256::ecdsa::SigningKey;
 use ethers::prelude::*;
 use ethers::utils::{Ganache, GanacheInstance};
-use risc0_zkvm::sha::DIGEST_BYTES;
-use risc0_zkvm::{Prover, ProverOpts};
+use hello_bonsai_contracts::deploy::deploy_mock_proxy;
+use hello_bonsai_methods::cache::ProofCache;
+use hello_bonsai_methods::manifest::GuestManifest;
+use hello_bonsai_methods::prover::ProvingClient;
+use hello_bonsai_methods::GuestImage;
+use risc0_zkvm::sha::{Digest, DIGEST_BYTES};
 use tokio::sync::oneshot;
 use tokio::task::JoinHandle;
 
-abigen!(
-    MockBonsaiProxy,
-    "artifacts/MockBonsaiProxy.sol/MockBonsaiProxy.json"
-);
-
 pub type Client = Arc<SignerMiddleware<Provider<Ws>, Wallet<SigningKey>>>;
 
 pub async fn get_ganache_client() -> Result<(GanacheInstance, Client), Box<dyn Error>> {
@@ -56,6 +55,22 @@ pub async fn get_ganache_client() -> Result<(GanacheInstance, Client), Box<dyn E
     Ok((ganache, client))
 }
 
+/// Registry of every guest in the manifest of `hello-bonsai-methods`, keyed by image ID.
+pub fn guest_registry() -> HashMap<[u8; DIGEST_BYTES], &'static [u8]> {
+    GuestManifest::embedded()
+        .guests
+        .into_iter()
+        .map(|entry| {
+            let guest = GuestImage::from_name(&entry.name).expect("unknown guest in manifest");
+            assert_eq!(
+                entry.image_id.0,
+                <[u8; DIGEST_BYTES]>::from(Digest::from(guest.image_id()))
+            );
+            (entry.image_id.0, guest.elf())
+        })
+        .collect()
+}
+
 pub struct BonsaiMock {
     pub contract_address: Address,
     pub task_handle: JoinHandle<()>,
@@ -70,11 +85,7 @@ impl BonsaiMock {
 
         let task_handle: JoinHandle<()> = tokio::spawn(async move {
             // Deploy the MockBonsaiProxy
-            let mock_bonsai_proxy = MockBonsaiProxy::deploy(client.clone(), ())
-                .unwrap()
-                .send()
-                .await
-                .unwrap();
+            let mock_bonsai_proxy = deploy_mock_proxy(client.clone()).await.unwrap();
 
             let events = mock_bonsai_proxy.events();
             let mut subscription = events.subscribe().await.unwrap();
@@ -83,26 +94,26 @@ impl BonsaiMock {
             while let Some(event) = subscription.next().await {
                 let submit_request_log =
                     event.expect("error in getting next event from subscription");
-                let receipt = {
-                    let elf = registry.get(&submit_request_log.image_id).expect(&format!(
-                        "image ID not found in registry: {:x?}",
-                        submit_request_log.image_id
-                    ));
-                    let mut prover = Prover::new_with_opts(
+                let elf = registry.get(&submit_request_log.image_id).expect(&format!(
+                    "image ID not found in registry: {:x?}",
+                    submit_request_log.image_id
+                ));
+                // Tests submit the same claims on every run, so reuse earlier receipts.
+                let proof = ProvingClient::new()
+                    .with_skip_seal(true)
+                    .with_cache(ProofCache::from_env())
+                    .run_elf(
                         elf.as_ref(),
                         submit_request_log.image_id,
-                        ProverOpts::default().with_skip_seal(true),
+                        submit_request_log.input.deref(),
                     )
-                    .expect("failed to create prover");
-                    prover.add_input_u8_slice(submit_request_log.input.deref());
-                    prover.run().expect("failed to run guest")
-                };
+                    .expect("failed to run guest");
 
                 mock_bonsai_proxy
                     .send_callback(
                         submit_request_log.callback_address,
                         submit_request_log.image_id.into(),
-                        receipt.journal.into(),
+                        proof.journal.into(),
                     )
                     .send()
                     .await
@@ -19,7 +19,7 @@ use revm::{Account, AccountInfo, Bytecode};
 // use log::info;
 
 // Re-export revm members for external usage.
-pub use revm::{Env, ExecutionResult, Return, TransactTo, EVM};
+pub use revm::{Env, ExecutionResult, Log, Return, TransactOut, TransactTo, EVM};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Default, Deserialize, Serialize)]
@@ -55,6 +55,26 @@ pub struct ZkDb {
     block: ResTrack<H256>,
 }
 
+/// Number of values of each kind recorded in a [ZkDb] during preflight.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZkDbReads {
+    pub accounts: usize,
+    pub code: usize,
+    pub storage: usize,
+    pub block_hashes: usize,
+}
+
+impl ZkDb {
+    pub fn reads(&self) -> ZkDbReads {
+        ZkDbReads {
+            accounts: self.basic.elms.len(),
+            code: self.code_hash.elms.len(),
+            storage: self.storage.elms.len(),
+            block_hashes: self.block.elms.len(),
+        }
+    }
+}
+
 impl Database for ZkDb {
     type Error = ();
     /// Get basic account information.
@@ -75,6 +95,49 @@ impl Database for ZkDb {
     }
 }
 
+/// Database remembering every account as it was first read, before the transaction changed it, so
+/// [state_diff] can report the original values next to the new ones.
+#[derive(Debug)]
+pub struct OriginalAccounts<D> {
+    db: D,
+    accounts: HashMap<Address, AccountInfo>,
+}
+
+impl<D> OriginalAccounts<D> {
+    pub fn new(db: D) -> Self {
+        Self {
+            db,
+            accounts: HashMap::new(),
+        }
+    }
+
+    /// The wrapped database, and the original state of each account read through it. Accounts
+    /// that did not exist are recorded as empty.
+    pub fn into_parts(self) -> (D, HashMap<Address, AccountInfo>) {
+        (self.db, self.accounts)
+    }
+}
+
+impl<D: Database> Database for OriginalAccounts<D> {
+    type Error = D::Error;
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let info = self.db.basic(address)?;
+        self.accounts
+            .entry(address)
+            .or_insert_with(|| info.clone().unwrap_or_default());
+        Ok(info)
+    }
+    fn code_by_hash(&mut self, code_hash: H256) -> Result<Bytecode, Self::Error> {
+        self.db.code_by_hash(code_hash)
+    }
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.db.storage(address, index)
+    }
+    fn block_hash(&mut self, number: U256) -> Result<H256, Self::Error> {
+        self.db.block_hash(number)
+    }
+}
+
 #[cfg(feature = "ethers")]
 pub mod ether_trace {
     use std::sync::Arc;
@@ -224,10 +287,89 @@ pub mod ether_trace {
     }
 }
 
+/// Result of a transaction, committed by the EVM replay guest.
+///
+/// The state is a [state_diff] rather than the map returned by the EVM, so the journal has the
+/// same bytes for the same transaction whatever the iteration order of the map, and verifiers can
+/// recompute it exactly.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct EvmResult {
     pub exit_reason: Return,
-    pub state: HashMap<Address, Account>,
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+    pub state: Vec<AccountDiff>,
+}
+
+/// Account accessed by a transaction, with its state before and after.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AccountDiff {
+    pub address: Address,
+    pub original_balance: U256,
+    pub balance: U256,
+    pub original_nonce: u64,
+    pub nonce: u64,
+    pub original_code_hash: H256,
+    pub code_hash: H256,
+    pub destroyed: bool,
+    /// Storage slots accessed by the transaction, sorted by index.
+    pub storage: Vec<StorageDiff>,
+}
+
+/// Storage slot accessed by a transaction, with its value before and after.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StorageDiff {
+    pub index: U256,
+    pub original: U256,
+    pub present: U256,
+}
+
+/// Accounts in `state`, sorted by address, so the same state always yields the same list whatever
+/// the iteration order of the map. Original values are taken from `original`, as recorded by
+/// [OriginalAccounts]; accounts missing from it are taken not to have existed.
+pub fn state_diff(
+    state: &HashMap<Address, Account>,
+    original: &HashMap<Address, AccountInfo>,
+) -> Vec<AccountDiff> {
+    let mut accounts: Vec<AccountDiff> = state
+        .iter()
+        .map(|(address, account)| {
+            let mut storage: Vec<StorageDiff> = account
+                .storage
+                .iter()
+                .map(|(index, slot)| StorageDiff {
+                    index: *index,
+                    original: slot.original_value,
+                    present: slot.present_value,
+                })
+                .collect();
+            storage.sort_by_key(|slot| slot.index);
+            let before = original.get(address).cloned().unwrap_or_default();
+            AccountDiff {
+                address: *address,
+                original_balance: before.balance,
+                balance: account.info.balance,
+                original_nonce: before.nonce,
+                nonce: account.info.nonce,
+                original_code_hash: before.code_hash,
+                code_hash: account.info.code_hash,
+                destroyed: account.is_destroyed,
+                storage,
+            }
+        })
+        .collect();
+    accounts.sort_by_key(|account| account.address);
+    accounts
+}
+
+/// Result of a read-only call, committed by the view call guest.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ViewCallResult {
+    pub exit_reason: Return,
+    pub block_number: U256,
+    pub caller: Address,
+    pub to: Address,
+    pub input: Vec<u8>,
+    pub output: Vec<u8>,
 }
 
 #[cfg(test)]
@@ -241,6 +383,88 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn state_diff_is_sorted() {
+        let mut state = HashMap::new();
+        let mut original = HashMap::new();
+        for byte in [3u8, 1, 2] {
+            let info = AccountInfo {
+                balance: U256::from(100 + byte),
+                nonce: u64::from(byte),
+                ..AccountInfo::default()
+            };
+            let mut account = Account::from(info);
+            for index in [7u64, 5, 6] {
+                account.storage.insert(
+                    U256::from(index),
+                    revm::StorageSlot {
+                        original_value: U256::from(index),
+                        present_value: U256::from(byte),
+                    },
+                );
+            }
+            // The transaction paid 10 wei and bumped the nonce of every account but the third,
+            // which it created.
+            if byte != 3 {
+                let info = AccountInfo {
+                    balance: U256::from(110 + byte),
+                    nonce: u64::from(byte) - 1,
+                    code_hash: H256::repeat_byte(byte),
+                    ..AccountInfo::default()
+                };
+                original.insert(Address::repeat_byte(byte), info);
+            }
+            state.insert(Address::repeat_byte(byte), account);
+        }
+
+        let diff = state_diff(&state, &original);
+        let addresses: Vec<_> = diff.iter().map(|account| account.address).collect();
+        assert_eq!(addresses, [1u8, 2, 3].map(Address::repeat_byte).to_vec());
+        let slots: Vec<_> = diff[0].storage.iter().map(|slot| slot.index).collect();
+        assert_eq!(slots, [5u64, 6, 7].map(U256::from).to_vec());
+        assert_eq!(diff[0].storage[0].original, U256::from(5));
+        assert_eq!(diff[0].storage[0].present, U256::one());
+
+        let balances: Vec<_> = diff
+            .iter()
+            .map(|account| (account.original_balance, account.balance))
+            .collect();
+        assert_eq!(
+            balances,
+            [(111u64, 101u64), (112, 102), (0, 103)]
+                .map(|(old, new)| (U256::from(old), U256::from(new)))
+                .to_vec()
+        );
+        let nonces: Vec<_> = diff
+            .iter()
+            .map(|account| (account.original_nonce, account.nonce))
+            .collect();
+        assert_eq!(nonces, [(0, 1), (1, 2), (0, 3)]);
+        assert_eq!(diff[1].original_code_hash, H256::repeat_byte(2));
+        assert_eq!(diff[1].code_hash, AccountInfo::default().code_hash);
+        assert_eq!(diff[2].original_code_hash, AccountInfo::default().code_hash);
+    }
+
+    #[test]
+    fn original_accounts_keep_first_read() {
+        let first = AccountInfo {
+            balance: U256::from(5),
+            ..AccountInfo::default()
+        };
+        let mut db = ZkDb::default();
+        db.basic.set(&Some(first.clone()));
+        db.basic.set(&None);
+        db.basic.set(&None);
+
+        let mut db = OriginalAccounts::new(db);
+        db.basic(Address::repeat_byte(1)).unwrap();
+        db.basic(Address::repeat_byte(1)).unwrap();
+        db.basic(Address::repeat_byte(2)).unwrap();
+        let (_, original) = db.into_parts();
+        assert_eq!(original[&Address::repeat_byte(1)], first);
+        assert_eq!(original[&Address::repeat_byte(2)], AccountInfo::default());
+    }
+
     // Ignored because it requires a live RPC_URL to run
     #[ignore]
     #[tokio::test]
@@ -0,0 +1,99 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers for the fixed-size, versioned ABI tuples exchanged with `L3L1Escrow`.
+
+use alloc::vec::{IntoIter, Vec};
+
+use ethabi::{ParamType, Token};
+
+use crate::{Address, AssetKind, ClaimStatus, DecodeError, H256, U256};
+
+/// Decode `data` as the static tuple `types`, whose first element is a `uint8` version.
+pub(crate) fn decode_versioned(
+    data: &[u8],
+    types: &[ParamType],
+    version: u8,
+) -> Result<Tokens, DecodeError> {
+    let expected = types.len() * 32;
+    if data.len() != expected {
+        return Err(DecodeError::InvalidLength {
+            expected,
+            actual: data.len(),
+        });
+    }
+    let mut tokens = Tokens(
+        ethabi::decode(types, data)
+            .map_err(|_| DecodeError::InvalidAbi)?
+            .into_iter(),
+    );
+    let actual = tokens.uint()?;
+    if actual != version.into() {
+        return Err(DecodeError::UnsupportedVersion(actual.low_u32() as u8));
+    }
+    Ok(tokens)
+}
+
+/// Reject values that decode, but are not what Solidity's `abi.encode` would emit for them.
+///
+/// ethabi ignores dirty padding, so compare against the canonical encoding.
+pub(crate) fn ensure_canonical(encoded: Vec<u8>, data: &[u8]) -> Result<(), DecodeError> {
+    match encoded == data {
+        true => Ok(()),
+        false => Err(DecodeError::NonCanonical),
+    }
+}
+
+/// Typed access to decoded tokens, in order.
+pub(crate) struct Tokens(IntoIter<Token>);
+
+impl Tokens {
+    fn next(&mut self) -> Result<Token, DecodeError> {
+        self.0.next().ok_or(DecodeError::InvalidAbi)
+    }
+
+    pub(crate) fn uint(&mut self) -> Result<U256, DecodeError> {
+        self.next()?.into_uint().ok_or(DecodeError::InvalidAbi)
+    }
+
+    /// Values above `u64::MAX` are truncated here and rejected by [ensure_canonical].
+    pub(crate) fn u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(self.uint()?.low_u64())
+    }
+
+    /// Values above `u8::MAX` are truncated here and rejected by [ensure_canonical].
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.uint()?.low_u32() as u8)
+    }
+
+    pub(crate) fn asset_kind(&mut self) -> Result<AssetKind, DecodeError> {
+        AssetKind::from_u8(self.u8()?).ok_or(DecodeError::InvalidAbi)
+    }
+
+    pub(crate) fn claim_status(&mut self) -> Result<ClaimStatus, DecodeError> {
+        ClaimStatus::from_u8(self.u8()?).ok_or(DecodeError::InvalidAbi)
+    }
+
+    pub(crate) fn address(&mut self) -> Result<Address, DecodeError> {
+        self.next()?.into_address().ok_or(DecodeError::InvalidAbi)
+    }
+
+    pub(crate) fn bytes32(&mut self) -> Result<H256, DecodeError> {
+        let bytes = self
+            .next()?
+            .into_fixed_bytes()
+            .ok_or(DecodeError::InvalidAbi)?;
+        Ok(H256::from_slice(&bytes))
+    }
+}
@@ -0,0 +1,314 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Payments split across several L3 transactions, checked together against one deposit.
+//!
+//! Wallet limits sometimes force the creditor to pay in parts. An aggregate claim is an
+//! [EscrowClaim] whose `tx_hash` is [aggregate_tx_hash] of the contributing transactions,
+//! followed by their hashes as encoded by [encode_tx_hashes]. Each transaction must be sent by the
+//! creditor to the depositor on the same chain, inside the payment window and tagged with the
+//! escrow reference; the amounts they transfer are summed and compared to the claimed amount.
+//!
+//! The guest trusts the block header in each witness, so the [AggregateJournal] lists the block of
+//! every part for `L3L1Escrow` to check against the L3 block hashes it trusts, not only the latest.
+
+use alloc::vec::Vec;
+
+use ethabi::{ParamType, Token};
+
+use crate::abi::{decode_versioned, ensure_canonical};
+use crate::batch::COUNT_LEN;
+use crate::{keccak256, DecodeError, EscrowJournal, H256, U256};
+
+/// Version of the [AggregateJournal] layout. Must match `AGGREGATE_VERSION` in `L3L1Escrow.sol`.
+pub const AGGREGATE_VERSION: u8 = 2;
+
+/// Largest number of transactions in an aggregate claim. Must match `MAX_AGGREGATE_TXS` in
+/// `L3L1Escrow.sol`.
+pub const MAX_AGGREGATE_TXS: usize = 16;
+
+/// Hash standing in for the transaction hash of an aggregate claim, computed as
+/// `keccak256(abi.encodePacked(txHashes))`.
+pub fn aggregate_tx_hash(tx_hashes: &[H256]) -> H256 {
+    let packed: Vec<u8> = tx_hashes.iter().flat_map(|hash| hash.0).collect();
+    keccak256(packed)
+}
+
+/// Encode the transaction hashes following an aggregate claim the same way
+/// `L3L1Escrow.checkPaymentStatusAggregate` does: `abi.encode(uint256 count)` followed by the
+/// packed hashes. The witnesses follow in the same order.
+pub fn encode_tx_hashes(tx_hashes: &[H256]) -> Vec<u8> {
+    let mut encoded = ethabi::encode(&[Token::Uint(tx_hashes.len().into())]);
+    for hash in tx_hashes {
+        encoded.extend_from_slice(hash.as_bytes());
+    }
+    encoded
+}
+
+/// Decode the transaction count preceding the hashes of an aggregate claim.
+pub fn decode_tx_count(data: &[u8]) -> Result<usize, DecodeError> {
+    if data.len() != COUNT_LEN {
+        return Err(DecodeError::InvalidLength {
+            expected: COUNT_LEN,
+            actual: data.len(),
+        });
+    }
+    let count = U256::from_big_endian(data);
+    if count.is_zero() || count > MAX_AGGREGATE_TXS.into() {
+        return Err(DecodeError::InvalidAbi);
+    }
+    Ok(count.as_usize())
+}
+
+/// L3 block a part of an aggregate payment was checked against.
+///
+/// Encoded as the static tuple `(bytes32 hash, uint64 number, uint64 timestamp)`, the `L3Block`
+/// struct of `L3L1Escrow.sol`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PartBlock {
+    pub hash: H256,
+    pub number: u64,
+    pub timestamp: u64,
+}
+
+impl PartBlock {
+    const ENCODED_LEN: usize = 3 * 32;
+
+    fn abi_encode(&self) -> Vec<u8> {
+        ethabi::encode(&[
+            Token::FixedBytes(self.hash.as_bytes().to_vec()),
+            Token::Uint(self.number.into()),
+            Token::Uint(self.timestamp.into()),
+        ])
+    }
+
+    /// Decode a block, leaving out-of-range numbers for the canonical check to reject.
+    fn abi_decode(data: &[u8]) -> Self {
+        Self {
+            hash: H256::from_slice(&data[..32]),
+            number: U256::from_big_endian(&data[32..64]).low_u64(),
+            timestamp: U256::from_big_endian(&data[64..96]).low_u64(),
+        }
+    }
+}
+
+/// Result of checking an aggregate claim, with the transactions that were summed and the blocks
+/// they were found in.
+///
+/// Encoded as `abi.encode(uint8 version, Journal result, bytes32[] txHashes, L3Block[] blocks)`.
+/// In `result`, `tx_hash` is the [aggregate_tx_hash] of `tx_hashes`, `amount` the total paid, and
+/// the block fields describe the latest of the transactions. `blocks` holds the block of each of
+/// `tx_hashes` in the same order, and is empty if the claim was rejected before every part was
+/// checked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AggregateJournal {
+    pub result: EscrowJournal,
+    pub tx_hashes: Vec<H256>,
+    pub blocks: Vec<PartBlock>,
+}
+
+impl AggregateJournal {
+    /// Version, the inline result and the offsets of the hashes and the blocks.
+    const HEAD_LEN: usize = 32 + EscrowJournal::ENCODED_LEN + 2 * 32;
+
+    pub fn abi_encode(&self) -> Vec<u8> {
+        let blocks_offset = Self::HEAD_LEN + COUNT_LEN + self.tx_hashes.len() * 32;
+        let mut encoded = ethabi::encode(&[Token::Uint(AGGREGATE_VERSION.into())]);
+        encoded.extend(self.result.abi_encode());
+        encoded.extend(ethabi::encode(&[
+            Token::Uint(Self::HEAD_LEN.into()),
+            Token::Uint(blocks_offset.into()),
+        ]));
+        encoded.extend(encode_tx_hashes(&self.tx_hashes));
+        encoded.extend(ethabi::encode(&[Token::Uint(self.blocks.len().into())]));
+        for block in &self.blocks {
+            encoded.extend(block.abi_encode());
+        }
+        encoded
+    }
+
+    /// Decode an aggregate journal, accepting only the exact bytes the aggregate guest commits.
+    pub fn abi_decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let min_len = Self::HEAD_LEN + 2 * COUNT_LEN;
+        if data.len() < min_len {
+            return Err(DecodeError::InvalidLength {
+                expected: min_len,
+                actual: data.len(),
+            });
+        }
+        decode_versioned(&data[..32], &[ParamType::Uint(8)], AGGREGATE_VERSION)?;
+        let result = EscrowJournal::abi_decode(&data[32..32 + EscrowJournal::ENCODED_LEN])?;
+        let tx_count = decode_count(&data[Self::HEAD_LEN..Self::HEAD_LEN + COUNT_LEN])?;
+        let hashes_start = Self::HEAD_LEN + COUNT_LEN;
+        let blocks_start = hashes_start + tx_count * 32 + COUNT_LEN;
+        let block_count = match data.get(blocks_start - COUNT_LEN..blocks_start) {
+            Some(count) => decode_count(count)?,
+            None => return Err(DecodeError::InvalidAbi),
+        };
+        let expected = blocks_start + block_count * PartBlock::ENCODED_LEN;
+        if data.len() != expected {
+            return Err(DecodeError::InvalidLength {
+                expected,
+                actual: data.len(),
+            });
+        }
+
+        let journal = Self {
+            result,
+            tx_hashes: data[hashes_start..blocks_start - COUNT_LEN]
+                .chunks(32)
+                .map(H256::from_slice)
+                .collect(),
+            blocks: data[blocks_start..]
+                .chunks(PartBlock::ENCODED_LEN)
+                .map(PartBlock::abi_decode)
+                .collect(),
+        };
+        ensure_canonical(journal.abi_encode(), data)?;
+        Ok(journal)
+    }
+}
+
+/// Decode the length of an array of the journal, which holds at most one entry per transaction.
+fn decode_count(data: &[u8]) -> Result<usize, DecodeError> {
+    let count = U256::from_big_endian(data);
+    match count <= MAX_AGGREGATE_TXS.into() {
+        true => Ok(count.as_usize()),
+        false => Err(DecodeError::InvalidAbi),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, AssetKind, ClaimStatus};
+
+    fn tx_hashes() -> Vec<H256> {
+        (1..=3).map(H256::from_low_u64_be).collect()
+    }
+
+    fn journal() -> AggregateJournal {
+        AggregateJournal {
+            result: EscrowJournal {
+                status: ClaimStatus::Accepted,
+                chain_id: 1001,
+                block_hash: H256::repeat_byte(0xbb),
+                block_number: 14,
+                block_timestamp: 0x170,
+                tx_hash: aggregate_tx_hash(&tx_hashes()),
+                kind: AssetKind::Native,
+                token: Address::zero(),
+                token_id: U256::zero(),
+                payer: Address::repeat_byte(0x22),
+                payee: Address::repeat_byte(0x11),
+                amount: U256::from(30),
+                escrow: Address::repeat_byte(0xee),
+                escrow_id: U256::from(7),
+                reference: H256::repeat_byte(0xcc),
+            },
+            tx_hashes: tx_hashes(),
+            blocks: (0..3)
+                .map(|i| PartBlock {
+                    hash: H256::repeat_byte(0xb0 + i as u8),
+                    number: 12 + i,
+                    timestamp: 0x160 + i,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn hashes_packed_tx_hashes() {
+        let encoded = encode_tx_hashes(&tx_hashes());
+        assert_eq!(decode_tx_count(&encoded[..COUNT_LEN]), Ok(3));
+        assert_eq!(
+            aggregate_tx_hash(&tx_hashes()),
+            keccak256(&encoded[COUNT_LEN..])
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_counts() {
+        let zero = encode_tx_hashes(&[]);
+        assert_eq!(decode_tx_count(&zero), Err(DecodeError::InvalidAbi));
+        let too_many = encode_tx_hashes(&[H256::zero(); MAX_AGGREGATE_TXS + 1]);
+        assert_eq!(
+            decode_tx_count(&too_many[..COUNT_LEN]),
+            Err(DecodeError::InvalidAbi)
+        );
+    }
+
+    #[test]
+    fn encodes_like_solidity() {
+        let journal = journal();
+        let result = ethabi::decode(
+            &vec![ParamType::Uint(256); 16],
+            &journal.result.abi_encode(),
+        )
+        .unwrap();
+        let encoded = ethabi::encode(&[
+            Token::Uint(AGGREGATE_VERSION.into()),
+            Token::Tuple(result),
+            Token::Array(
+                tx_hashes()
+                    .iter()
+                    .map(|hash| Token::FixedBytes(hash.as_bytes().to_vec()))
+                    .collect(),
+            ),
+            Token::Array(
+                journal
+                    .blocks
+                    .iter()
+                    .map(|block| {
+                        Token::Tuple(vec![
+                            Token::FixedBytes(block.hash.as_bytes().to_vec()),
+                            Token::Uint(block.number.into()),
+                            Token::Uint(block.timestamp.into()),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ]);
+        assert_eq!(journal.abi_encode(), encoded);
+    }
+
+    #[test]
+    fn round_trips() {
+        let encoded = journal().abi_encode();
+        assert_eq!(AggregateJournal::abi_decode(&encoded), Ok(journal()));
+        assert!(AggregateJournal::abi_decode(&encoded[..encoded.len() - 32]).is_err());
+
+        // A rejected claim carries no blocks.
+        let rejected = AggregateJournal {
+            blocks: vec![],
+            ..journal()
+        };
+        assert_eq!(
+            AggregateJournal::abi_decode(&rejected.abi_encode()),
+            Ok(rejected)
+        );
+    }
+
+    #[test]
+    fn rejects_block_numbers_out_of_range() {
+        let mut encoded = journal().abi_encode();
+        // High byte of the number of the last block.
+        let last = encoded.len() - PartBlock::ENCODED_LEN;
+        encoded[last + 32] = 1;
+        assert_eq!(
+            AggregateJournal::abi_decode(&encoded),
+            Err(DecodeError::NonCanonical)
+        );
+    }
+}
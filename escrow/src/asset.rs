@@ -0,0 +1,47 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Kinds of L3 assets an escrow deposit can be settled with.
+
+/// Standard the L3 payment follows. Encoded as the `uint8` value of `L3L1Escrow.AssetKind`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum AssetKind {
+    /// Value carried by the transaction itself.
+    #[default]
+    Native = 0,
+    /// Fungible token amount, from an ERC-20 `Transfer` event.
+    Erc20 = 1,
+    /// Single token ID, from an ERC-721 `Transfer` event.
+    Erc721 = 2,
+    /// Amount of a token ID, from an ERC-1155 `TransferSingle` or `TransferBatch` event.
+    Erc1155 = 3,
+}
+
+impl AssetKind {
+    pub fn from_u8(kind: u8) -> Option<Self> {
+        match kind {
+            0 => Some(AssetKind::Native),
+            1 => Some(AssetKind::Erc20),
+            2 => Some(AssetKind::Erc721),
+            3 => Some(AssetKind::Erc1155),
+            _ => None,
+        }
+    }
+
+    /// Whether the asset has token IDs, i.e. is a non-fungible or multi-token collection.
+    pub fn is_nft(self) -> bool {
+        matches!(self, AssetKind::Erc721 | AssetKind::Erc1155)
+    }
+}
@@ -0,0 +1,214 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Batches of claims checked in a single guest run, and the journal committing their results.
+
+use alloc::vec::Vec;
+
+use ethabi::{ParamType, Token};
+
+use crate::abi::{decode_versioned, ensure_canonical};
+use crate::{DecodeError, EscrowClaim, EscrowJournal, U256};
+
+/// Version of the [BatchJournal] layout. Must match `BATCH_VERSION` in `L3L1Escrow.sol`.
+pub const BATCH_VERSION: u8 = 1;
+
+/// Largest number of claims in a batch. Must match `MAX_BATCH_SIZE` in `L3L1Escrow.sol`.
+pub const MAX_BATCH_SIZE: usize = 64;
+
+/// Length in bytes of the claim count preceding the claims of a batch.
+pub const COUNT_LEN: usize = 32;
+
+/// Encode the claims of a batch the same way `L3L1Escrow.checkPaymentStatusBatch` does:
+/// `abi.encode(uint256 count)` followed by each encoded claim. The witnesses follow in the same
+/// order.
+pub fn encode_claims(claims: &[EscrowClaim]) -> Vec<u8> {
+    let mut encoded = ethabi::encode(&[Token::Uint(claims.len().into())]);
+    for claim in claims {
+        encoded.extend(claim.abi_encode());
+    }
+    encoded
+}
+
+/// Decode the claim count preceding the claims of a batch.
+pub fn decode_claim_count(data: &[u8]) -> Result<usize, DecodeError> {
+    if data.len() != COUNT_LEN {
+        return Err(DecodeError::InvalidLength {
+            expected: COUNT_LEN,
+            actual: data.len(),
+        });
+    }
+    let count = U256::from_big_endian(data);
+    if count.is_zero() || count > MAX_BATCH_SIZE.into() {
+        return Err(DecodeError::InvalidAbi);
+    }
+    Ok(count.as_usize())
+}
+
+/// Results of checking a batch of claims, in the order of the claims.
+///
+/// Encoded as `abi.encode(uint8 version, Journal[] results)`, where each result is laid out like
+/// a single [EscrowJournal].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BatchJournal {
+    pub results: Vec<EscrowJournal>,
+}
+
+impl BatchJournal {
+    /// Version, offset of the results array and its length.
+    const HEAD_LEN: usize = 3 * 32;
+
+    const HEAD_TYPES: [ParamType; 3] = [
+        ParamType::Uint(8),
+        ParamType::Uint(256),
+        ParamType::Uint(256),
+    ];
+
+    pub fn abi_encode(&self) -> Vec<u8> {
+        let mut encoded = ethabi::encode(&[
+            Token::Uint(BATCH_VERSION.into()),
+            Token::Uint(64.into()),
+            Token::Uint(self.results.len().into()),
+        ]);
+        for result in &self.results {
+            encoded.extend(result.abi_encode());
+        }
+        encoded
+    }
+
+    /// Decode a batch journal, accepting only the exact bytes the batch guest commits.
+    pub fn abi_decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let head = data
+            .get(..Self::HEAD_LEN)
+            .ok_or(DecodeError::InvalidLength {
+                expected: Self::HEAD_LEN,
+                actual: data.len(),
+            })?;
+        let mut tokens = decode_versioned(head, &Self::HEAD_TYPES, BATCH_VERSION)?;
+        tokens.uint()?;
+        let count = tokens.uint()?;
+        let expected = (count <= MAX_BATCH_SIZE.into())
+            .then(|| Self::HEAD_LEN + count.as_usize() * EscrowJournal::ENCODED_LEN)
+            .ok_or(DecodeError::InvalidAbi)?;
+        if data.len() != expected {
+            return Err(DecodeError::InvalidLength {
+                expected,
+                actual: data.len(),
+            });
+        }
+
+        let batch = Self {
+            results: data[Self::HEAD_LEN..]
+                .chunks(EscrowJournal::ENCODED_LEN)
+                .map(EscrowJournal::abi_decode)
+                .collect::<Result<_, _>>()?,
+        };
+        ensure_canonical(batch.abi_encode(), data)?;
+        Ok(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, AssetKind, ClaimStatus, H256};
+
+    fn journal(escrow_id: u64) -> EscrowJournal {
+        EscrowJournal {
+            status: match escrow_id {
+                1 => ClaimStatus::WrongAmount,
+                _ => ClaimStatus::Accepted,
+            },
+            chain_id: 1001,
+            block_hash: H256::repeat_byte(0xbb),
+            block_number: 12,
+            block_timestamp: 0x150,
+            tx_hash: H256::from_low_u64_be(escrow_id),
+            kind: AssetKind::Native,
+            token: Address::zero(),
+            token_id: U256::zero(),
+            payer: Address::repeat_byte(0x22),
+            payee: Address::repeat_byte(0x11),
+            amount: U256::from(10),
+            escrow: Address::repeat_byte(0xee),
+            escrow_id: escrow_id.into(),
+            reference: H256::repeat_byte(0xcc),
+        }
+    }
+
+    fn batch() -> BatchJournal {
+        BatchJournal {
+            results: (0..3).map(journal).collect(),
+        }
+    }
+
+    #[test]
+    fn encodes_like_solidity() {
+        let journals = batch().results;
+        let encoded = ethabi::encode(&[
+            Token::Uint(BATCH_VERSION.into()),
+            Token::Array(
+                journals
+                    .iter()
+                    .map(|journal| {
+                        let types = vec![ParamType::Uint(256); 16];
+                        Token::Tuple(ethabi::decode(&types, &journal.abi_encode()).unwrap())
+                    })
+                    .collect(),
+            ),
+        ]);
+        assert_eq!(batch().abi_encode(), encoded);
+    }
+
+    #[test]
+    fn round_trips() {
+        let encoded = batch().abi_encode();
+        assert_eq!(BatchJournal::abi_decode(&encoded), Ok(batch()));
+    }
+
+    #[test]
+    fn rejects_truncated_batch() {
+        let encoded = batch().abi_encode();
+        assert_eq!(
+            BatchJournal::abi_decode(&encoded[..encoded.len() - 32]),
+            Err(DecodeError::InvalidLength {
+                expected: encoded.len(),
+                actual: encoded.len() - 32,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_offset() {
+        let mut encoded = batch().abi_encode();
+        encoded[63] = 0x60;
+        assert_eq!(
+            BatchJournal::abi_decode(&encoded),
+            Err(DecodeError::NonCanonical)
+        );
+    }
+
+    #[test]
+    fn decodes_claim_count() {
+        let claims = encode_claims(&[]);
+        assert_eq!(claims.len(), COUNT_LEN);
+        assert_eq!(decode_claim_count(&claims), Err(DecodeError::InvalidAbi));
+
+        let mut count = [0u8; COUNT_LEN];
+        count[31] = 2;
+        assert_eq!(decode_claim_count(&count), Ok(2));
+        count[31] = MAX_BATCH_SIZE as u8 + 1;
+        assert_eq!(decode_claim_count(&count), Err(DecodeError::InvalidAbi));
+    }
+}
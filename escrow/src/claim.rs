@@ -0,0 +1,237 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Input submitted by `L3L1Escrow.checkPaymentStatus` and read by the escrow guest.
+
+use alloc::vec::Vec;
+
+use ethabi::{ParamType, Token};
+
+use crate::abi::{decode_versioned, ensure_canonical};
+use crate::{escrow_reference, Address, AssetKind, DecodeError, H256, U256};
+
+/// Version of the [EscrowClaim] layout. Must match `CLAIM_VERSION` in `L3L1Escrow.sol`.
+pub const CLAIM_VERSION: u8 = 5;
+
+/// Claim that the creditor paid the depositor `amount` of `token` on L3 in the transaction
+/// `tx_hash`, in order to settle deposit `escrow_id` of the escrow contract `escrow`. The payment
+/// must be in an L3 block with a timestamp between `not_before` and `not_after`.
+///
+/// Encoded as `abi.encode(uint8 version, address escrow, uint256 escrowId, bytes32 txHash,
+/// uint8 kind, address token, uint256 tokenId, address depositor, address creditor,
+/// uint256 amount, uint64 notBefore, uint64 notAfter)`. All fields are static, so the encoding
+/// has a fixed length and the guest splits it off the front of its input, once the input itself
+/// has been read through the length prefix decoded by
+/// [decode_input_len](crate::input::decode_input_len).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EscrowClaim {
+    pub escrow: Address,
+    pub escrow_id: U256,
+    pub tx_hash: H256,
+    pub kind: AssetKind,
+    /// L3 token contract or NFT collection the payment is made in, zero for the native currency.
+    pub token: Address,
+    /// Token ID for ERC-721 and ERC-1155 payments, zero otherwise.
+    pub token_id: U256,
+    /// Owner of the L1 deposit, who must receive the payment on L3.
+    pub depositor: Address,
+    /// Beneficiary of the L1 deposit, who must send the payment on L3.
+    pub creditor: Address,
+    /// Value or token amount to pay, one for ERC-721 tokens.
+    pub amount: U256,
+    /// Earliest L3 block timestamp of the payment, the creation time of the deposit.
+    pub not_before: u64,
+    /// Latest L3 block timestamp of the payment, inclusive.
+    pub not_after: u64,
+}
+
+impl EscrowClaim {
+    /// Length in bytes of an ABI encoded claim.
+    pub const ENCODED_LEN: usize = 12 * 32;
+
+    const PARAM_TYPES: [ParamType; 12] = [
+        ParamType::Uint(8),
+        ParamType::Address,
+        ParamType::Uint(256),
+        ParamType::FixedBytes(32),
+        ParamType::Uint(8),
+        ParamType::Address,
+        ParamType::Uint(256),
+        ParamType::Address,
+        ParamType::Address,
+        ParamType::Uint(256),
+        ParamType::Uint(64),
+        ParamType::Uint(64),
+    ];
+
+    /// Whether an L3 block with the given timestamp is inside the payment window.
+    pub fn in_window(&self, timestamp: u64) -> bool {
+        (self.not_before..=self.not_after).contains(&timestamp)
+    }
+
+    /// Reference the L3 payment must carry to settle this claim, see [crate::reference].
+    pub fn reference(&self) -> H256 {
+        escrow_reference(self.escrow, self.escrow_id)
+    }
+
+    /// Encode the claim the same way `L3L1Escrow` does.
+    pub fn abi_encode(&self) -> Vec<u8> {
+        ethabi::encode(&[
+            Token::Uint(CLAIM_VERSION.into()),
+            Token::Address(self.escrow),
+            Token::Uint(self.escrow_id),
+            Token::FixedBytes(self.tx_hash.as_bytes().to_vec()),
+            Token::Uint((self.kind as u8).into()),
+            Token::Address(self.token),
+            Token::Uint(self.token_id),
+            Token::Address(self.depositor),
+            Token::Address(self.creditor),
+            Token::Uint(self.amount),
+            Token::Uint(self.not_before.into()),
+            Token::Uint(self.not_after.into()),
+        ])
+    }
+
+    /// Decode a claim, accepting only the exact bytes `L3L1Escrow` would emit.
+    pub fn abi_decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let mut tokens = decode_versioned(data, &Self::PARAM_TYPES, CLAIM_VERSION)?;
+        let claim = Self {
+            escrow: tokens.address()?,
+            escrow_id: tokens.uint()?,
+            tx_hash: tokens.bytes32()?,
+            kind: tokens.asset_kind()?,
+            token: tokens.address()?,
+            token_id: tokens.uint()?,
+            depositor: tokens.address()?,
+            creditor: tokens.address()?,
+            amount: tokens.uint()?,
+            not_before: tokens.u64()?,
+            not_after: tokens.u64()?,
+        };
+        ensure_canonical(claim.abi_encode(), data)?;
+        Ok(claim)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim() -> EscrowClaim {
+        EscrowClaim {
+            escrow: Address::repeat_byte(0xee),
+            escrow_id: U256::from(7),
+            tx_hash: H256::repeat_byte(0xab),
+            kind: AssetKind::Erc1155,
+            token: Address::repeat_byte(0x70),
+            token_id: U256::from(42),
+            depositor: Address::repeat_byte(0x11),
+            creditor: Address::repeat_byte(0x22),
+            amount: U256::from(10),
+            not_before: 0x100,
+            not_after: 0x200,
+        }
+    }
+
+    /// Words as laid out by `abi.encode(CLAIM_VERSION, escrow, escrowId, txHash, kind, token,
+    /// tokenId, depositor, creditor, amount, notBefore, notAfter)`.
+    fn solidity_words() -> Vec<u8> {
+        let mut words = vec![0u8; EscrowClaim::ENCODED_LEN];
+        words[31] = CLAIM_VERSION;
+        words[44..64].copy_from_slice(&[0xee; 20]);
+        words[95] = 7;
+        words[96..128].copy_from_slice(&[0xab; 32]);
+        words[159] = 3;
+        words[172..192].copy_from_slice(&[0x70; 20]);
+        words[223] = 42;
+        words[236..256].copy_from_slice(&[0x11; 20]);
+        words[268..288].copy_from_slice(&[0x22; 20]);
+        words[319] = 10;
+        words[350] = 1;
+        words[382] = 2;
+        words
+    }
+
+    #[test]
+    fn encodes_like_solidity() {
+        assert_eq!(claim().abi_encode(), solidity_words());
+    }
+
+    #[test]
+    fn decodes_solidity_encoding() {
+        assert_eq!(EscrowClaim::abi_decode(&solidity_words()), Ok(claim()));
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut words = solidity_words();
+        words[31] = CLAIM_VERSION + 1;
+        assert_eq!(
+            EscrowClaim::abi_decode(&words),
+            Err(DecodeError::UnsupportedVersion(CLAIM_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn rejects_dirty_padding() {
+        let mut words = solidity_words();
+        words[32] = 1;
+        assert_eq!(
+            EscrowClaim::abi_decode(&words),
+            Err(DecodeError::NonCanonical)
+        );
+    }
+
+    #[test]
+    fn rejects_length_prefixed_input() {
+        let mut words = (EscrowClaim::ENCODED_LEN as u32).to_le_bytes().to_vec();
+        words.extend(solidity_words());
+        assert_eq!(
+            EscrowClaim::abi_decode(&words),
+            Err(DecodeError::InvalidLength {
+                expected: EscrowClaim::ENCODED_LEN,
+                actual: EscrowClaim::ENCODED_LEN + 4,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_asset_kind() {
+        let mut words = solidity_words();
+        words[159] = 4;
+        assert_eq!(
+            EscrowClaim::abi_decode(&words),
+            Err(DecodeError::InvalidAbi)
+        );
+    }
+
+    #[test]
+    fn rejects_oversized_window() {
+        let mut words = solidity_words();
+        words[343] = 1;
+        assert_eq!(
+            EscrowClaim::abi_decode(&words),
+            Err(DecodeError::NonCanonical)
+        );
+    }
+
+    #[test]
+    fn window_is_inclusive() {
+        let claim = claim();
+        assert!(!claim.in_window(0xff));
+        assert!(claim.in_window(0x100));
+        assert!(claim.in_window(0x200));
+        assert!(!claim.in_window(0x201));
+    }
+}
@@ -0,0 +1,46 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal view of an L3 block header.
+
+use crate::rlp_util::{decode_h256, decode_u64, list};
+use crate::{keccak256, DecodeError, H256};
+
+/// Fields of an RLP encoded block header that the escrow guest relies on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockHeader {
+    /// Keccak hash of the encoded header, i.e. the block hash.
+    pub hash: H256,
+    pub number: u64,
+    pub timestamp: u64,
+    pub transactions_root: H256,
+    pub receipts_root: H256,
+}
+
+impl BlockHeader {
+    /// Decode an RLP encoded header. Fields added by later forks are hashed but otherwise ignored.
+    pub fn decode(encoded: &[u8]) -> Result<Self, DecodeError> {
+        let rlp = list(encoded)?;
+        if rlp.item_count()? < 15 {
+            return Err(DecodeError::InvalidRlp);
+        }
+        Ok(Self {
+            hash: keccak256(encoded),
+            number: decode_u64(&rlp.at(8)?)?,
+            timestamp: decode_u64(&rlp.at(11)?)?,
+            transactions_root: decode_h256(&rlp.at(4)?)?,
+            receipts_root: decode_h256(&rlp.at(5)?)?,
+        })
+    }
+}
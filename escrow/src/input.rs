@@ -0,0 +1,118 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Framing of the raw input of the escrow guests.
+//!
+//! `env::read_slice` must be told how much to read and `env::read` panics on malformed data, so
+//! the guests are given their input prefixed with its length, read it in one go and decode it
+//! from there. Input that does not decode is committed as [crate::ClaimStatus::DecodeError]
+//! rather than failing to prove.
+
+use alloc::vec::Vec;
+
+use ethabi::Token;
+
+use crate::{DecodeError, U256};
+
+/// Length in bytes of the length prefix of a guest input.
+pub const LEN_PREFIX_LEN: usize = 32;
+
+/// Size of the words the guest reads its input in.
+const WORD_LEN: usize = 4;
+
+/// Frame a guest input the same way `L3L1Escrow` does: `abi.encode(uint256 length)` followed by
+/// the input itself.
+///
+/// # Panics
+///
+/// If the input is not a whole number of 32-bit words, which the contract refuses too.
+pub fn frame_input(input: &[u8]) -> Vec<u8> {
+    assert_eq!(input.len() % WORD_LEN, 0, "input is not word-aligned");
+    let mut framed = ethabi::encode(&[Token::Uint(input.len().into())]);
+    framed.extend_from_slice(input);
+    framed
+}
+
+/// Decode the length prefix of a guest input.
+pub fn decode_input_len(data: &[u8]) -> Result<usize, DecodeError> {
+    if data.len() != LEN_PREFIX_LEN {
+        return Err(DecodeError::InvalidLength {
+            expected: LEN_PREFIX_LEN,
+            actual: data.len(),
+        });
+    }
+    let len = U256::from_big_endian(data);
+    if len > u32::MAX.into() || !(len % WORD_LEN).is_zero() {
+        return Err(DecodeError::InvalidAbi);
+    }
+    Ok(len.as_usize())
+}
+
+/// Split the next `len` bytes off the front of a guest input.
+pub fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8], DecodeError> {
+    if input.len() < len {
+        return Err(DecodeError::InvalidLength {
+            expected: len,
+            actual: input.len(),
+        });
+    }
+    let (head, tail) = input.split_at(len);
+    *input = tail;
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length_round_trips() {
+        let framed = frame_input(&[7u8; 12]);
+        assert_eq!(framed.len(), LEN_PREFIX_LEN + 12);
+        assert_eq!(decode_input_len(&framed[..LEN_PREFIX_LEN]), Ok(12));
+        assert_eq!(framed[LEN_PREFIX_LEN..], [7u8; 12]);
+    }
+
+    #[test]
+    fn rejects_lengths_the_guest_cannot_read() {
+        let prefix = |len: u64| ethabi::encode(&[Token::Uint(len.into())]);
+        assert_eq!(decode_input_len(&prefix(0)), Ok(0));
+        assert_eq!(decode_input_len(&prefix(13)), Err(DecodeError::InvalidAbi));
+        assert_eq!(
+            decode_input_len(&prefix(1 << 32)),
+            Err(DecodeError::InvalidAbi)
+        );
+        assert_eq!(
+            decode_input_len(&[0u8; 4]),
+            Err(DecodeError::InvalidLength {
+                expected: LEN_PREFIX_LEN,
+                actual: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn take_fails_past_the_end() {
+        let mut input: &[u8] = &[1, 2, 3];
+        assert_eq!(take(&mut input, 2), Ok(&[1u8, 2][..]));
+        assert_eq!(
+            take(&mut input, 2),
+            Err(DecodeError::InvalidLength {
+                expected: 2,
+                actual: 1,
+            })
+        );
+        assert_eq!(input, [3]);
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Result committed by the escrow guest and decoded by `L3L1Escrow.bonsai_callback`.
+
+use alloc::vec::Vec;
+
+use ethabi::{ParamType, Token};
+
+use crate::abi::{decode_versioned, ensure_canonical};
+use crate::{Address, AssetKind, ClaimStatus, DecodeError, EscrowClaim, H256, U256};
+
+/// Version of the [EscrowJournal] layout. Must match `JOURNAL_VERSION` in `L3L1Escrow.sol`.
+pub const JOURNAL_VERSION: u8 = 6;
+
+/// Outcome of checking an [EscrowClaim](crate::EscrowClaim), bound to the L3 transaction and the
+/// escrow deposit it settles.
+///
+/// Encoded as `abi.encode(uint8 version, uint8 status, uint256 chainId, bytes32 blockHash,
+/// uint64 blockNumber, uint64 blockTimestamp, bytes32 txHash, uint8 kind, address token,
+/// uint256 tokenId, address payer, address payee, uint256 amount, address escrow,
+/// uint256 escrowId, bytes32 reference)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EscrowJournal {
+    /// Whether the transaction pays the claimed amount of the claimed token from the creditor to
+    /// the depositor, or why not.
+    pub status: ClaimStatus,
+    /// Chain ID the L3 transaction was signed for, zero if it is not replay protected.
+    pub chain_id: u64,
+    /// Hash of the L3 block that includes the transaction.
+    pub block_hash: H256,
+    /// Number of that block, read from the verified header.
+    pub block_number: u64,
+    /// Timestamp of that block, checked against the payment window of the claim.
+    pub block_timestamp: u64,
+    pub tx_hash: H256,
+    pub kind: AssetKind,
+    /// L3 token contract or NFT collection the payment was made in, zero for the native currency.
+    pub token: Address,
+    /// Token ID for ERC-721 and ERC-1155 payments, zero otherwise.
+    pub token_id: U256,
+    /// Sender of the L3 payment.
+    pub payer: Address,
+    /// Recipient of the L3 payment, zero for contract creations.
+    pub payee: Address,
+    /// Value or token amount transferred by the L3 payment.
+    pub amount: U256,
+    pub escrow: Address,
+    pub escrow_id: U256,
+    /// Escrow reference carried by the L3 transaction calldata, zero if it carries none.
+    pub reference: H256,
+}
+
+impl EscrowJournal {
+    /// Length in bytes of an ABI encoded journal.
+    pub const ENCODED_LEN: usize = 16 * 32;
+
+    /// Journal for a claim rejected before the payment could be looked at, e.g. because the
+    /// witness does not decode. Only the fields taken from the claim are set.
+    pub fn rejected(claim: &EscrowClaim, status: ClaimStatus) -> Self {
+        Self {
+            status,
+            chain_id: 0,
+            block_hash: H256::zero(),
+            block_number: 0,
+            block_timestamp: 0,
+            tx_hash: claim.tx_hash,
+            kind: claim.kind,
+            token: claim.token,
+            token_id: claim.token_id,
+            payer: Address::zero(),
+            payee: Address::zero(),
+            amount: U256::zero(),
+            escrow: claim.escrow,
+            escrow_id: claim.escrow_id,
+            reference: H256::zero(),
+        }
+    }
+
+    const PARAM_TYPES: [ParamType; 16] = [
+        ParamType::Uint(8),
+        ParamType::Uint(8),
+        ParamType::Uint(256),
+        ParamType::FixedBytes(32),
+        ParamType::Uint(64),
+        ParamType::Uint(64),
+        ParamType::FixedBytes(32),
+        ParamType::Uint(8),
+        ParamType::Address,
+        ParamType::Uint(256),
+        ParamType::Address,
+        ParamType::Address,
+        ParamType::Uint(256),
+        ParamType::Address,
+        ParamType::Uint(256),
+        ParamType::FixedBytes(32),
+    ];
+
+    pub fn abi_encode(&self) -> Vec<u8> {
+        ethabi::encode(&[
+            Token::Uint(JOURNAL_VERSION.into()),
+            Token::Uint((self.status as u8).into()),
+            Token::Uint(self.chain_id.into()),
+            Token::FixedBytes(self.block_hash.as_bytes().to_vec()),
+            Token::Uint(self.block_number.into()),
+            Token::Uint(self.block_timestamp.into()),
+            Token::FixedBytes(self.tx_hash.as_bytes().to_vec()),
+            Token::Uint((self.kind as u8).into()),
+            Token::Address(self.token),
+            Token::Uint(self.token_id),
+            Token::Address(self.payer),
+            Token::Address(self.payee),
+            Token::Uint(self.amount),
+            Token::Address(self.escrow),
+            Token::Uint(self.escrow_id),
+            Token::FixedBytes(self.reference.as_bytes().to_vec()),
+        ])
+    }
+
+    /// Decode a journal, accepting only the exact bytes the guest commits.
+    pub fn abi_decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let mut tokens = decode_versioned(data, &Self::PARAM_TYPES, JOURNAL_VERSION)?;
+        let journal = Self {
+            status: tokens.claim_status()?,
+            chain_id: tokens.u64()?,
+            block_hash: tokens.bytes32()?,
+            block_number: tokens.u64()?,
+            block_timestamp: tokens.u64()?,
+            tx_hash: tokens.bytes32()?,
+            kind: tokens.asset_kind()?,
+            token: tokens.address()?,
+            token_id: tokens.uint()?,
+            payer: tokens.address()?,
+            payee: tokens.address()?,
+            amount: tokens.uint()?,
+            escrow: tokens.address()?,
+            escrow_id: tokens.uint()?,
+            reference: tokens.bytes32()?,
+        };
+        ensure_canonical(journal.abi_encode(), data)?;
+        Ok(journal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn journal() -> EscrowJournal {
+        EscrowJournal {
+            status: ClaimStatus::Accepted,
+            chain_id: 1001,
+            block_hash: H256::repeat_byte(0xbb),
+            block_number: 12,
+            block_timestamp: 0x150,
+            tx_hash: H256::repeat_byte(0xab),
+            kind: AssetKind::Erc721,
+            token: Address::repeat_byte(0x70),
+            token_id: U256::from(42),
+            payer: Address::repeat_byte(0x22),
+            payee: Address::repeat_byte(0x11),
+            amount: U256::from(10),
+            escrow: Address::repeat_byte(0xee),
+            escrow_id: U256::from(7),
+            reference: H256::repeat_byte(0xcc),
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let encoded = journal().abi_encode();
+        assert_eq!(encoded.len(), EscrowJournal::ENCODED_LEN);
+        assert_eq!(EscrowJournal::abi_decode(&encoded), Ok(journal()));
+    }
+
+    #[test]
+    fn rejects_oversized_chain_id() {
+        let mut encoded = journal().abi_encode();
+        encoded[64] = 1;
+        assert_eq!(
+            EscrowJournal::abi_decode(&encoded),
+            Err(DecodeError::NonCanonical)
+        );
+    }
+
+    #[test]
+    fn rejects_legacy_journal() {
+        let legacy = ethabi::encode(&[Token::Bool(true), Token::Address(Address::zero())]);
+        assert!(EscrowJournal::abi_decode(&legacy).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_status() {
+        let mut encoded = journal().abi_encode();
+        encoded[63] = 13;
+        assert_eq!(
+            EscrowJournal::abi_decode(&encoded),
+            Err(DecodeError::InvalidAbi)
+        );
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Types shared between the escrow guest, the host tools and the contract bindings.
+//!
+//! Everything in this crate is `no_std` so the exact same encoders and decoders run inside the
+//! RISC Zero guest and on the host.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod abi;
+pub mod aggregate;
+pub mod asset;
+pub mod batch;
+pub mod claim;
+pub mod header;
+pub mod input;
+pub mod journal;
+pub mod mpt;
+pub mod receipt;
+pub mod reference;
+mod rlp_util;
+pub mod status;
+#[cfg(test)]
+mod test_utils;
+pub mod transaction;
+pub mod verify;
+pub mod witness;
+
+use core::fmt;
+
+pub use aggregate::{AggregateJournal, PartBlock, AGGREGATE_VERSION};
+pub use asset::AssetKind;
+pub use batch::{BatchJournal, BATCH_VERSION};
+pub use claim::{EscrowClaim, CLAIM_VERSION};
+pub use ethabi::ethereum_types::{Address, H256, U256};
+pub use journal::{EscrowJournal, JOURNAL_VERSION};
+pub use reference::escrow_reference;
+use sha3::{Digest, Keccak256};
+pub use status::ClaimStatus;
+pub use witness::PaymentWitness;
+
+/// Keccak-256 hash as used throughout Ethereum.
+pub fn keccak256(data: impl AsRef<[u8]>) -> H256 {
+    H256::from_slice(&Keccak256::digest(data.as_ref()))
+}
+
+/// Error returned when bytes do not decode into one of the escrow types.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input does not have the length of the encoded type.
+    InvalidLength { expected: usize, actual: usize },
+    /// The input is not valid ABI for the expected layout.
+    InvalidAbi,
+    /// The input decodes, but is not what Solidity's `abi.encode` would emit for those values.
+    NonCanonical,
+    /// The input was encoded with a version this crate does not understand.
+    UnsupportedVersion(u8),
+    /// The input is not valid RLP for the expected structure.
+    InvalidRlp,
+    /// The transaction is of an EIP-2718 type this crate does not understand.
+    UnsupportedTransactionType(u8),
+    /// The transaction signature does not recover to a sender.
+    InvalidSignature,
+    /// The Merkle Patricia proof does not prove the value against the root.
+    InvalidProof,
+    /// The witness is not serialized the way `witness_input` on the host serializes it.
+    InvalidWitness,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidLength { expected, actual } => {
+                write!(f, "expected {expected} bytes, got {actual}")
+            }
+            DecodeError::InvalidAbi => write!(f, "invalid ABI encoding"),
+            DecodeError::NonCanonical => write!(f, "non-canonical ABI encoding"),
+            DecodeError::UnsupportedVersion(version) => write!(f, "unsupported version {version}"),
+            DecodeError::InvalidRlp => write!(f, "invalid RLP encoding"),
+            DecodeError::UnsupportedTransactionType(tx_type) => {
+                write!(f, "unsupported transaction type {tx_type}")
+            }
+            DecodeError::InvalidSignature => write!(f, "invalid transaction signature"),
+            DecodeError::InvalidProof => write!(f, "invalid Merkle Patricia proof"),
+            DecodeError::InvalidWitness => write!(f, "invalid witness serialization"),
+        }
+    }
+}
+
+impl From<rlp::DecoderError> for DecodeError {
+    fn from(_: rlp::DecoderError) -> Self {
+        DecodeError::InvalidRlp
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
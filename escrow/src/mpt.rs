@@ -0,0 +1,308 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merkle Patricia trie proofs, used to show that a transaction is part of an L3 block.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use rlp::{Rlp, RlpStream};
+
+use crate::{keccak256, DecodeError, H256};
+
+/// Root of a trie without any entries.
+pub const EMPTY_ROOT: H256 = H256([
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+/// Reference from a trie node to one of its children.
+enum NodeRef<'a> {
+    Empty,
+    Hash(H256),
+    /// Nodes shorter than 32 bytes are embedded in their parent.
+    Inline(&'a [u8]),
+}
+
+impl<'a> NodeRef<'a> {
+    fn decode(rlp: &Rlp<'a>) -> Result<Self, DecodeError> {
+        if rlp.is_list() {
+            return Ok(NodeRef::Inline(rlp.as_raw()));
+        }
+        match rlp.data()? {
+            [] => Ok(NodeRef::Empty),
+            hash if hash.len() == 32 => Ok(NodeRef::Hash(H256::from_slice(hash))),
+            _ => Err(DecodeError::InvalidProof),
+        }
+    }
+}
+
+/// Verify a proof for `key` against the trie `root` and return the value stored under the key.
+///
+/// The proof lists the hashed nodes on the path from the root to the value, as returned by
+/// `eth_getProof` or [build_proof].
+pub fn verify_proof<'a>(
+    root: H256,
+    key: &[u8],
+    proof: &'a [Vec<u8>],
+) -> Result<&'a [u8], DecodeError> {
+    let nibbles = to_nibbles(key);
+    let mut path = &nibbles[..];
+    let mut proof = proof.iter();
+    let mut next = NodeRef::Hash(root);
+
+    loop {
+        let node = match next {
+            NodeRef::Empty => return Err(DecodeError::InvalidProof),
+            NodeRef::Hash(hash) => {
+                let node = proof.next().ok_or(DecodeError::InvalidProof)?;
+                if keccak256(node) != hash {
+                    return Err(DecodeError::InvalidProof);
+                }
+                Rlp::new(node)
+            }
+            NodeRef::Inline(node) => Rlp::new(node),
+        };
+
+        match node.item_count()? {
+            17 => match path.split_first() {
+                None => {
+                    let value = node.at(16)?.data()?;
+                    return match value {
+                        [] => Err(DecodeError::InvalidProof),
+                        value => Ok(value),
+                    };
+                }
+                Some((&nibble, rest)) => {
+                    next = NodeRef::decode(&node.at(nibble as usize)?)?;
+                    path = rest;
+                }
+            },
+            2 => {
+                let (prefix, is_leaf) = decode_compact(node.at(0)?.data()?)?;
+                if !path.starts_with(&prefix) {
+                    return Err(DecodeError::InvalidProof);
+                }
+                path = &path[prefix.len()..];
+                if is_leaf {
+                    return match path {
+                        [] => Ok(node.at(1)?.data()?),
+                        _ => Err(DecodeError::InvalidProof),
+                    };
+                }
+                next = NodeRef::decode(&node.at(1)?)?;
+            }
+            _ => return Err(DecodeError::InvalidProof),
+        }
+    }
+}
+
+/// Build the trie holding `items` and return its root with a proof for `key`.
+///
+/// Keys must be unique. The proof is empty if `key` is not in the trie.
+pub fn build_proof(items: &[(Vec<u8>, Vec<u8>)], key: &[u8]) -> (H256, Vec<Vec<u8>>) {
+    if items.is_empty() {
+        return (EMPTY_ROOT, Vec::new());
+    }
+    let mut entries: Vec<(Vec<u8>, &[u8])> = items
+        .iter()
+        .map(|(key, value)| (to_nibbles(key), value.as_slice()))
+        .collect();
+    entries.sort();
+
+    let target = to_nibbles(key);
+    let on_path = entries.iter().any(|(nibbles, _)| *nibbles == target);
+    let mut proof = Vec::new();
+    let root = encode_node(&entries, 0, &target, on_path, true, &mut proof);
+
+    proof.reverse();
+    (keccak256(root), proof)
+}
+
+/// RLP encode the node holding the sorted `entries`, which all share their first `depth` nibbles.
+/// Hashed nodes on the path to `target` are pushed to `proof`, children first.
+fn encode_node(
+    entries: &[(Vec<u8>, &[u8])],
+    depth: usize,
+    target: &[u8],
+    on_path: bool,
+    is_root: bool,
+    proof: &mut Vec<Vec<u8>>,
+) -> Vec<u8> {
+    let mut stream = RlpStream::new();
+    if let [(nibbles, value)] = entries {
+        stream.begin_list(2);
+        stream.append(&encode_compact(&nibbles[depth..], true));
+        stream.append(value);
+    } else {
+        let first = &entries[0].0;
+        let last = &entries[entries.len() - 1].0;
+        let shared = first[depth..]
+            .iter()
+            .zip(&last[depth..])
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        if shared > 0 {
+            let child = encode_node(entries, depth + shared, target, on_path, false, proof);
+            stream.begin_list(2);
+            stream.append(&encode_compact(&first[depth..depth + shared], false));
+            append_ref(&mut stream, child);
+        } else {
+            stream.begin_list(17);
+            // Entries are sorted, so a key ending at this node comes first.
+            let (value, mut rest) = match entries.split_first() {
+                Some(((nibbles, value), rest)) if nibbles.len() == depth => (*value, rest),
+                _ => (&[][..], entries),
+            };
+            for nibble in 0..16u8 {
+                let split = rest
+                    .iter()
+                    .position(|(nibbles, _)| nibbles[depth] != nibble)
+                    .unwrap_or(rest.len());
+                let (group, tail) = rest.split_at(split);
+                rest = tail;
+                if group.is_empty() {
+                    stream.append_empty_data();
+                } else {
+                    let child_on_path = on_path && target.get(depth) == Some(&nibble);
+                    let child = encode_node(group, depth + 1, target, child_on_path, false, proof);
+                    append_ref(&mut stream, child);
+                }
+            }
+            stream.append(&value);
+        }
+    }
+
+    let encoded = stream.out().to_vec();
+    if on_path && (is_root || encoded.len() >= 32) {
+        proof.push(encoded.clone());
+    }
+    encoded
+}
+
+fn append_ref(stream: &mut RlpStream, child: Vec<u8>) {
+    if child.len() < 32 {
+        stream.append_raw(&child, 1);
+    } else {
+        stream.append(&keccak256(&child).as_bytes());
+    }
+}
+
+fn to_nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+/// Hex-prefix encoding of a path, flagging whether it ends in a leaf.
+fn encode_compact(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 2 } else { 0 };
+    let (mut encoded, rest) = match nibbles.split_first() {
+        Some((&first, rest)) if nibbles.len() % 2 == 1 => (vec![(flag + 1) << 4 | first], rest),
+        _ => (vec![flag << 4], nibbles),
+    };
+    encoded.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+    encoded
+}
+
+fn decode_compact(encoded: &[u8]) -> Result<(Vec<u8>, bool), DecodeError> {
+    let (&first, rest) = encoded.split_first().ok_or(DecodeError::InvalidProof)?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return Err(DecodeError::InvalidProof);
+    }
+    let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+    if flag & 1 == 1 {
+        nibbles.push(first & 0x0f);
+    }
+    nibbles.extend(to_nibbles(rest));
+    Ok((nibbles, flag & 2 == 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(pairs: &[(&str, &str)]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.as_bytes().to_vec(), value.as_bytes().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn empty_trie() {
+        assert_eq!(build_proof(&[], b"key").0, EMPTY_ROOT);
+        assert_eq!(keccak256([0x80]), EMPTY_ROOT);
+    }
+
+    // Root from the "puppy" case of the Ethereum trie tests.
+    #[test]
+    fn matches_reference_root() {
+        let items = items(&[
+            ("do", "verb"),
+            ("horse", "stallion"),
+            ("doge", "coin"),
+            ("dog", "puppy"),
+        ]);
+        let (root, _) = build_proof(&items, b"dog");
+        assert_eq!(
+            hex::encode(root),
+            "5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84"
+        );
+    }
+
+    #[test]
+    fn proves_every_key() {
+        let items: Vec<_> = (0u64..300)
+            .map(|i| {
+                (
+                    rlp::encode(&i).to_vec(),
+                    keccak256(i.to_be_bytes()).0.to_vec(),
+                )
+            })
+            .collect();
+        for (key, value) in &items {
+            let (root, proof) = build_proof(&items, key);
+            assert_eq!(verify_proof(root, key, &proof), Ok(&value[..]));
+        }
+    }
+
+    #[test]
+    fn rejects_missing_key() {
+        let items = items(&[("do", "verb"), ("dog", "puppy"), ("doge", "coin")]);
+        let (root, proof) = build_proof(&items, b"dog");
+        assert_eq!(
+            verify_proof(root, b"dot", &proof),
+            Err(DecodeError::InvalidProof)
+        );
+        assert_eq!(
+            verify_proof(root, b"d", &proof),
+            Err(DecodeError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_proof() {
+        let items = items(&[("do", "verb"), ("dog", "puppy"), ("doge", "coin")]);
+        let (root, mut proof) = build_proof(&items, b"dog");
+        let last = proof.last_mut().unwrap();
+        *last.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            verify_proof(root, b"dog", &proof),
+            Err(DecodeError::InvalidProof)
+        );
+    }
+}
@@ -0,0 +1,330 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding of L3 transaction receipts and the token transfer events they carry.
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use ethabi::{ParamType, Token};
+
+use crate::rlp_util::{decode_h256, list};
+use crate::{Address, AssetKind, DecodeError, H256, U256};
+
+/// Topic of `Transfer(address indexed from, address indexed to, uint256 value)`, emitted by
+/// ERC-20 tokens. ERC-721 emits the same event with an indexed token ID instead of the value.
+pub const TRANSFER_TOPIC: H256 = H256([
+    0xdd, 0xf2, 0x52, 0xad, 0x1b, 0xe2, 0xc8, 0x9b, 0x69, 0xc2, 0xb0, 0x68, 0xfc, 0x37, 0x8d, 0xaa,
+    0x95, 0x2b, 0xa7, 0xf1, 0x63, 0xc4, 0xa1, 0x16, 0x28, 0xf5, 0x5a, 0x4d, 0xf5, 0x23, 0xb3, 0xef,
+]);
+
+/// Topic of the ERC-1155 `TransferSingle(address indexed operator, address indexed from,
+/// address indexed to, uint256 id, uint256 value)` event.
+pub const TRANSFER_SINGLE_TOPIC: H256 = H256([
+    0xc3, 0xd5, 0x81, 0x68, 0xc5, 0xae, 0x73, 0x97, 0x73, 0x1d, 0x06, 0x3d, 0x5b, 0xbf, 0x3d, 0x65,
+    0x78, 0x54, 0x42, 0x73, 0x43, 0xf4, 0xc0, 0x83, 0x24, 0x0f, 0x7a, 0xac, 0xaa, 0x2d, 0x0f, 0x62,
+]);
+
+/// Topic of the ERC-1155 `TransferBatch(address indexed operator, address indexed from,
+/// address indexed to, uint256[] ids, uint256[] values)` event.
+pub const TRANSFER_BATCH_TOPIC: H256 = H256([
+    0x4a, 0x39, 0xdc, 0x06, 0xd4, 0xc0, 0xdb, 0xc6, 0x4b, 0x70, 0xaf, 0x90, 0xfd, 0x69, 0x8a, 0x23,
+    0x3a, 0x51, 0x8a, 0xa5, 0xd0, 0x7e, 0x59, 0x5d, 0x98, 0x3b, 0x8c, 0x05, 0x26, 0xc8, 0xf7, 0xfb,
+]);
+
+/// Receipt of an executed transaction, as stored in the receipts trie of a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Receipt {
+    /// Whether the transaction succeeded. Pre-Byzantium receipts, which carry a state root
+    /// instead of a status, are never successful.
+    pub success: bool,
+    pub logs: Vec<Log>,
+}
+
+/// Event emitted by a contract.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Log {
+    pub address: Address,
+    pub topics: Vec<H256>,
+    pub data: Vec<u8>,
+}
+
+/// Token movement described by an ERC-20, ERC-721 or ERC-1155 transfer event.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TokenTransfer {
+    pub kind: AssetKind,
+    pub token: Address,
+    /// Token ID for ERC-721 and ERC-1155 transfers, zero for ERC-20.
+    pub token_id: U256,
+    pub from: Address,
+    pub to: Address,
+    /// Amount transferred, one for ERC-721.
+    pub value: U256,
+}
+
+impl Receipt {
+    /// Decode a receipt as it is stored in the receipts trie, i.e. the RLP list for legacy
+    /// transactions and the EIP-2718 envelope for typed ones.
+    pub fn decode(encoded: &[u8]) -> Result<Self, DecodeError> {
+        let payload = match encoded.first() {
+            Some(1 | 2) => &encoded[1..],
+            Some(&tx_type) if tx_type <= 0x7f => {
+                return Err(DecodeError::UnsupportedTransactionType(tx_type))
+            }
+            _ => encoded,
+        };
+        let rlp = list(payload)?;
+        if rlp.item_count()? != 4 {
+            return Err(DecodeError::InvalidRlp);
+        }
+        let success = rlp.at(0)?.data()? == [1];
+        let logs = rlp
+            .at(3)?
+            .iter()
+            .map(|log| {
+                if log.item_count()? != 3 {
+                    return Err(DecodeError::InvalidRlp);
+                }
+                let address = log.at(0)?.data()?;
+                if address.len() != 20 {
+                    return Err(DecodeError::InvalidRlp);
+                }
+                Ok(Log {
+                    address: Address::from_slice(address),
+                    topics: log
+                        .at(1)?
+                        .iter()
+                        .map(|topic| decode_h256(&topic))
+                        .collect::<Result<_, _>>()?,
+                    data: log.at(2)?.data()?.to_vec(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { success, logs })
+    }
+
+    /// Transfers of `kind` emitted by `token`, in the order they were logged.
+    pub fn token_transfers(
+        &self,
+        kind: AssetKind,
+        token: Address,
+    ) -> impl Iterator<Item = TokenTransfer> + '_ {
+        self.logs
+            .iter()
+            .filter(move |log| log.address == token)
+            .flat_map(Log::token_transfers)
+            .filter(move |transfer| transfer.kind == kind)
+    }
+}
+
+impl Log {
+    /// Interpret the log as token transfer events.
+    ///
+    /// ERC-20 and ERC-721 share the `Transfer` signature and are told apart by the number of
+    /// indexed topics. A `TransferBatch` yields one transfer per token ID. Other logs, and
+    /// transfer events with malformed data, yield nothing.
+    pub fn token_transfers(&self) -> Vec<TokenTransfer> {
+        let transfer = |kind, token_id, from: &H256, to: &H256, value| TokenTransfer {
+            kind,
+            token: self.address,
+            token_id,
+            from: topic_address(from),
+            to: topic_address(to),
+            value,
+        };
+        match self.topics.as_slice() {
+            [topic, from, to] if *topic == TRANSFER_TOPIC && self.data.len() == 32 => {
+                let value = U256::from_big_endian(&self.data);
+                vec![transfer(AssetKind::Erc20, U256::zero(), from, to, value)]
+            }
+            [topic, from, to, id] if *topic == TRANSFER_TOPIC && self.data.is_empty() => {
+                let id = U256::from_big_endian(id.as_bytes());
+                vec![transfer(AssetKind::Erc721, id, from, to, U256::one())]
+            }
+            [topic, _operator, from, to] if *topic == TRANSFER_SINGLE_TOPIC => {
+                if self.data.len() != 64 {
+                    return Vec::new();
+                }
+                let id = U256::from_big_endian(&self.data[..32]);
+                let value = U256::from_big_endian(&self.data[32..]);
+                vec![transfer(AssetKind::Erc1155, id, from, to, value)]
+            }
+            [topic, _operator, from, to] if *topic == TRANSFER_BATCH_TOPIC => {
+                let array = ParamType::Array(Box::new(ParamType::Uint(256)));
+                let decoded = ethabi::decode(&[array.clone(), array], &self.data);
+                let (ids, values) = match decoded.as_deref() {
+                    Ok([Token::Array(ids), Token::Array(values)]) if ids.len() == values.len() => {
+                        (ids, values)
+                    }
+                    _ => return Vec::new(),
+                };
+                ids.iter()
+                    .zip(values)
+                    .filter_map(|(id, value)| {
+                        let (id, value) = (id.clone().into_uint()?, value.clone().into_uint()?);
+                        Some(transfer(AssetKind::Erc1155, id, from, to, value))
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Address stored in an indexed event topic.
+fn topic_address(topic: &H256) -> Address {
+    Address::from_slice(&topic[12..])
+}
+
+#[cfg(test)]
+mod tests {
+    use rlp::RlpStream;
+
+    use super::*;
+    use crate::keccak256;
+
+    fn topic(address: Address) -> H256 {
+        H256::from(address)
+    }
+
+    /// Typed receipt with a single log, encoded like `eth_getTransactionReceipt` results.
+    fn encode_receipt(status: u8, topics: &[H256], data: &[u8]) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(4);
+        stream
+            .append(&status)
+            .append(&21_000u64)
+            .append(&[0u8; 256].as_slice());
+        stream.begin_list(1).begin_list(3);
+        stream.append(&token().as_bytes());
+        stream.begin_list(topics.len());
+        for topic in topics {
+            stream.append(&topic.as_bytes());
+        }
+        stream.append(&data);
+        let mut encoded = vec![2];
+        encoded.extend_from_slice(&stream.out());
+        encoded
+    }
+
+    fn token() -> Address {
+        Address::repeat_byte(0x70)
+    }
+
+    fn parties() -> [H256; 2] {
+        [
+            topic(Address::repeat_byte(0x22)),
+            topic(Address::repeat_byte(0x11)),
+        ]
+    }
+
+    fn transfer(kind: AssetKind, token_id: u64, value: u64) -> TokenTransfer {
+        TokenTransfer {
+            kind,
+            token: token(),
+            token_id: U256::from(token_id),
+            from: Address::repeat_byte(0x22),
+            to: Address::repeat_byte(0x11),
+            value: U256::from(value),
+        }
+    }
+
+    #[test]
+    fn topics_match_signatures() {
+        assert_eq!(
+            keccak256("Transfer(address,address,uint256)"),
+            TRANSFER_TOPIC
+        );
+        assert_eq!(
+            keccak256("TransferSingle(address,address,address,uint256,uint256)"),
+            TRANSFER_SINGLE_TOPIC
+        );
+        assert_eq!(
+            keccak256("TransferBatch(address,address,address,uint256[],uint256[])"),
+            TRANSFER_BATCH_TOPIC
+        );
+    }
+
+    #[test]
+    fn decodes_erc20_transfer() {
+        let [from, to] = parties();
+        let value = ethabi::encode(&[Token::Uint(10.into())]);
+        let receipt =
+            Receipt::decode(&encode_receipt(1, &[TRANSFER_TOPIC, from, to], &value)).unwrap();
+        assert!(receipt.success);
+
+        let transfers: Vec<_> = receipt.token_transfers(AssetKind::Erc20, token()).collect();
+        assert_eq!(transfers, [transfer(AssetKind::Erc20, 0, 10)]);
+        assert_eq!(
+            receipt
+                .token_transfers(AssetKind::Erc20, Address::zero())
+                .count(),
+            0
+        );
+        assert_eq!(
+            receipt.token_transfers(AssetKind::Erc721, token()).count(),
+            0
+        );
+    }
+
+    #[test]
+    fn decodes_erc721_transfer() {
+        let [from, to] = parties();
+        let id = H256::from_low_u64_be(42);
+        let receipt =
+            Receipt::decode(&encode_receipt(1, &[TRANSFER_TOPIC, from, to, id], &[])).unwrap();
+        assert_eq!(
+            receipt.logs[0].token_transfers(),
+            [transfer(AssetKind::Erc721, 42, 1)]
+        );
+    }
+
+    #[test]
+    fn decodes_erc1155_transfers() {
+        let [from, to] = parties();
+        let operator = topic(Address::repeat_byte(0x33));
+
+        let data = ethabi::encode(&[Token::Uint(42.into()), Token::Uint(5.into())]);
+        let topics = [TRANSFER_SINGLE_TOPIC, operator, from, to];
+        let receipt = Receipt::decode(&encode_receipt(1, &topics, &data)).unwrap();
+        assert_eq!(
+            receipt.logs[0].token_transfers(),
+            [transfer(AssetKind::Erc1155, 42, 5)]
+        );
+
+        let uints =
+            |values: &[u64]| Token::Array(values.iter().map(|&v| Token::Uint(v.into())).collect());
+        let data = ethabi::encode(&[uints(&[42, 43]), uints(&[5, 6])]);
+        let topics = [TRANSFER_BATCH_TOPIC, operator, from, to];
+        let receipt = Receipt::decode(&encode_receipt(1, &topics, &data)).unwrap();
+        assert_eq!(
+            receipt.logs[0].token_transfers(),
+            [
+                transfer(AssetKind::Erc1155, 42, 5),
+                transfer(AssetKind::Erc1155, 43, 6)
+            ]
+        );
+
+        // Batches with a value missing are malformed.
+        let data = ethabi::encode(&[uints(&[42, 43]), uints(&[5])]);
+        let receipt = Receipt::decode(&encode_receipt(1, &topics, &data)).unwrap();
+        assert!(receipt.logs[0].token_transfers().is_empty());
+    }
+
+    #[test]
+    fn decodes_failed_receipt() {
+        let receipt = Receipt::decode(&encode_receipt(0, &[], &[])).unwrap();
+        assert!(!receipt.success);
+    }
+}
@@ -0,0 +1,88 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! References tying an L3 payment to the escrow deposit it settles.
+//!
+//! Without a reference, one transfer from the creditor to the depositor could settle every
+//! deposit with the same terms, or an unrelated earlier payment could be claimed as proof. The
+//! payer therefore ends the calldata of the L3 transaction with [escrow_reference]:
+//!
+//! - native payments carry the reference as their whole calldata,
+//! - ERC-20 payments append it to the `transfer` calldata, which tokens ignore,
+//! - NFT payments pass it as the `data` argument of `safeTransferFrom`, which ABI encoding places
+//!   in the last 32 bytes.
+
+use alloc::vec::Vec;
+
+use ethabi::Token;
+
+use crate::{keccak256, Address, H256, U256};
+
+/// Reference of deposit `escrow_id` of the escrow contract `escrow`, computed as
+/// `keccak256(abi.encode(escrow, escrowId))` like `L3L1Escrow.escrowReference`.
+pub fn escrow_reference(escrow: Address, escrow_id: U256) -> H256 {
+    keccak256(ethabi::encode(&[
+        Token::Address(escrow),
+        Token::Uint(escrow_id),
+    ]))
+}
+
+/// Reference carried by the given transaction calldata, if it is long enough to hold one.
+pub fn calldata_reference(input: &[u8]) -> Option<H256> {
+    let start = input.len().checked_sub(H256::len_bytes())?;
+    Some(H256::from_slice(&input[start..]))
+}
+
+/// Append `reference` to the calldata of a payment.
+pub fn tag_calldata(input: &[u8], reference: H256) -> Vec<u8> {
+    let mut tagged = Vec::with_capacity(input.len() + H256::len_bytes());
+    tagged.extend_from_slice(input);
+    tagged.extend_from_slice(reference.as_bytes());
+    tagged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_solidity_encoding() {
+        let mut encoded = [0u8; 64];
+        encoded[12..32].copy_from_slice(&[0xee; 20]);
+        encoded[63] = 7;
+        assert_eq!(
+            escrow_reference(Address::repeat_byte(0xee), U256::from(7)),
+            keccak256(encoded)
+        );
+    }
+
+    #[test]
+    fn differs_between_deposits() {
+        let escrow = Address::repeat_byte(0xee);
+        assert_ne!(
+            escrow_reference(escrow, U256::from(7)),
+            escrow_reference(escrow, U256::from(8))
+        );
+    }
+
+    #[test]
+    fn reads_trailing_word() {
+        let reference = H256::repeat_byte(0xab);
+        let transfer = [0xa9, 0x05, 0x9c, 0xbb];
+        let tagged = tag_calldata(&transfer, reference);
+        assert_eq!(calldata_reference(&tagged), Some(reference));
+        assert_eq!(calldata_reference(reference.as_bytes()), Some(reference));
+        assert_eq!(calldata_reference(&transfer), None);
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers for decoding the RLP scalars used in headers and transactions.
+
+use rlp::Rlp;
+
+use crate::{Address, DecodeError, H256, U256};
+
+pub(crate) fn decode_u64(rlp: &Rlp) -> Result<u64, DecodeError> {
+    Ok(rlp.as_val::<u64>()?)
+}
+
+pub(crate) fn decode_u256(rlp: &Rlp) -> Result<U256, DecodeError> {
+    let data = rlp.data()?;
+    if data.len() > 32 || data.first() == Some(&0) {
+        return Err(DecodeError::InvalidRlp);
+    }
+    Ok(U256::from_big_endian(data))
+}
+
+pub(crate) fn decode_h256(rlp: &Rlp) -> Result<H256, DecodeError> {
+    let data = rlp.data()?;
+    if data.len() != 32 {
+        return Err(DecodeError::InvalidRlp);
+    }
+    Ok(H256::from_slice(data))
+}
+
+/// Decode an address which is empty for contract creations.
+pub(crate) fn decode_address_opt(rlp: &Rlp) -> Result<Option<Address>, DecodeError> {
+    match rlp.data()? {
+        [] => Ok(None),
+        data if data.len() == 20 => Ok(Some(Address::from_slice(data))),
+        _ => Err(DecodeError::InvalidRlp),
+    }
+}
+
+/// Open `encoded` as a single RLP list without trailing bytes.
+pub(crate) fn list(encoded: &[u8]) -> Result<Rlp<'_>, DecodeError> {
+    let rlp = Rlp::new(encoded);
+    if !rlp.is_list() || rlp.payload_info()?.total() != encoded.len() {
+        return Err(DecodeError::InvalidRlp);
+    }
+    Ok(rlp)
+}
@@ -0,0 +1,128 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Outcome codes committed by the escrow guests for each claim.
+
+use core::fmt;
+
+use crate::DecodeError;
+
+/// Why a claim was accepted or rejected. Encoded as the `uint8` value of
+/// `L3L1Escrow.ClaimStatus`.
+///
+/// The guests commit a status for every claim instead of panicking, so a rejected claim gets a
+/// callback like an accepted one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ClaimStatus {
+    /// The payment settles the claim.
+    Accepted = 0,
+    /// The claim, block header, transaction or receipt could not be decoded.
+    DecodeError = 1,
+    /// The witness does not prove the claimed transaction in an L3 block.
+    TxNotFound = 2,
+    /// The transaction was included, but reverted.
+    TxReverted = 3,
+    /// The guest does not check payments of the claimed asset kind.
+    UnsupportedAsset = 4,
+    /// The transaction emitted no transfer of the claimed token or token ID.
+    TransferNotFound = 5,
+    /// The payment was not sent by the creditor.
+    WrongPayer = 6,
+    /// The payment was not sent to the depositor.
+    WrongRecipient = 7,
+    /// The payment does not carry the claimed amount.
+    WrongAmount = 8,
+    /// The L3 block is outside the time window of the claim.
+    StaleBlock = 9,
+    /// The transaction calldata does not end with the reference of the claimed deposit.
+    MissingReference = 10,
+    /// An aggregate claim lists the same transaction more than once.
+    DuplicateTransaction = 11,
+    /// The parts of an aggregate claim were paid on different chains.
+    WrongChain = 12,
+}
+
+impl ClaimStatus {
+    pub fn from_u8(status: u8) -> Option<Self> {
+        use ClaimStatus::*;
+        [
+            Accepted,
+            DecodeError,
+            TxNotFound,
+            TxReverted,
+            UnsupportedAsset,
+            TransferNotFound,
+            WrongPayer,
+            WrongRecipient,
+            WrongAmount,
+            StaleBlock,
+            MissingReference,
+            DuplicateTransaction,
+            WrongChain,
+        ]
+        .into_iter()
+        .find(|candidate| *candidate as u8 == status)
+    }
+
+    pub fn is_accepted(self) -> bool {
+        self == ClaimStatus::Accepted
+    }
+}
+
+impl From<DecodeError> for ClaimStatus {
+    fn from(err: DecodeError) -> Self {
+        match err {
+            DecodeError::InvalidProof => ClaimStatus::TxNotFound,
+            _ => ClaimStatus::DecodeError,
+        }
+    }
+}
+
+impl fmt::Display for ClaimStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            ClaimStatus::Accepted => "payment accepted",
+            ClaimStatus::DecodeError => "claim or witness could not be decoded",
+            ClaimStatus::TxNotFound => "transaction is not proven to be in an L3 block",
+            ClaimStatus::TxReverted => "transaction reverted",
+            ClaimStatus::UnsupportedAsset => "asset kind is not checked by this guest",
+            ClaimStatus::TransferNotFound => "transaction did not transfer the claimed token",
+            ClaimStatus::WrongPayer => "payment was not sent by the creditor",
+            ClaimStatus::WrongRecipient => "payment was not sent to the depositor",
+            ClaimStatus::WrongAmount => "payment amount does not match the claim",
+            ClaimStatus::StaleBlock => "payment is outside the time window of the claim",
+            ClaimStatus::MissingReference => "payment does not reference the deposit",
+            ClaimStatus::DuplicateTransaction => "transaction is counted more than once",
+            ClaimStatus::WrongChain => "parts of the payment were made on different chains",
+        };
+        f.write_str(reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_codes() {
+        for code in 0..=12 {
+            assert_eq!(
+                ClaimStatus::from_u8(code).map(|status| status as u8),
+                Some(code)
+            );
+        }
+        assert_eq!(ClaimStatus::from_u8(13), None);
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Builders for signed L3 transactions, receipts and witnesses, shared by the tests.
+
+use alloc::vec::Vec;
+
+use k256::ecdsa::signature::DigestSigner;
+use k256::ecdsa::{recoverable, SigningKey};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use rlp::RlpStream;
+use sha3::{Digest, Keccak256};
+
+use crate::receipt::Log;
+use crate::{keccak256, mpt, Address, PaymentWitness, H256, U256};
+
+/// Chain ID the test transactions are signed for.
+pub(crate) const CHAIN_ID: u64 = 1001;
+
+/// L3 account signing test transactions.
+pub(crate) struct Wallet(SigningKey);
+
+impl Wallet {
+    pub(crate) fn new(seed: u8) -> Self {
+        Self(SigningKey::from_bytes(&[seed; 32]).unwrap())
+    }
+
+    pub(crate) fn address(&self) -> Address {
+        let point = self.0.verifying_key().to_encoded_point(false);
+        Address::from_slice(&keccak256(&point.as_bytes()[1..])[12..])
+    }
+
+    /// Sign an EIP-155 legacy transaction for [CHAIN_ID].
+    pub(crate) fn sign(&self, nonce: u64, to: Address, value: U256, input: &[u8]) -> Vec<u8> {
+        self.sign_on(CHAIN_ID, nonce, to, value, input)
+    }
+
+    /// Sign an EIP-155 legacy transaction for `chain_id`.
+    pub(crate) fn sign_on(
+        &self,
+        chain_id: u64,
+        nonce: u64,
+        to: Address,
+        value: U256,
+        input: &[u8],
+    ) -> Vec<u8> {
+        let fields = |stream: &mut RlpStream| {
+            stream
+                .append(&nonce)
+                .append(&1_000_000_000u64)
+                .append(&100_000u64)
+                .append(&to.as_bytes())
+                .append(&trimmed(&value))
+                .append(&input);
+        };
+        let mut unsigned = RlpStream::new_list(9);
+        fields(&mut unsigned);
+        unsigned
+            .append(&chain_id)
+            .append_empty_data()
+            .append_empty_data();
+
+        let signature: recoverable::Signature = self
+            .0
+            .sign_digest(Keccak256::new_with_prefix(unsigned.out()));
+        let bytes = signature.as_ref();
+        let mut signed = RlpStream::new_list(9);
+        fields(&mut signed);
+        signed
+            .append(&(chain_id * 2 + 35 + u64::from(bytes[64])))
+            .append(&trimmed(&U256::from_big_endian(&bytes[..32])))
+            .append(&trimmed(&U256::from_big_endian(&bytes[32..64])));
+        signed.out().to_vec()
+    }
+}
+
+/// Big-endian bytes of `value` without leading zeros, as RLP encodes integers.
+fn trimmed(value: &U256) -> Vec<u8> {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    let start = bytes.iter().position(|&byte| byte != 0).unwrap_or(32);
+    bytes[start..].to_vec()
+}
+
+/// Legacy receipt with the given outcome and logs.
+pub(crate) fn receipt(success: bool, logs: &[Log]) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(4);
+    stream
+        .append(&u8::from(success))
+        .append(&21_000u64)
+        .append(&[0u8; 256].as_slice());
+    stream.begin_list(logs.len());
+    for log in logs {
+        stream.begin_list(3).append(&log.address.as_bytes());
+        stream.begin_list(log.topics.len());
+        for topic in &log.topics {
+            stream.append(&topic.as_bytes());
+        }
+        stream.append(&log.data);
+    }
+    stream.out().to_vec()
+}
+
+/// Header of a block with the given number, timestamp and trie roots.
+pub(crate) fn header(
+    number: u64,
+    timestamp: u64,
+    transactions_root: H256,
+    receipts_root: H256,
+) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(15);
+    stream
+        .append(&H256::zero().as_bytes())
+        .append(&H256::zero().as_bytes())
+        .append(&[0u8; 20].as_slice())
+        .append(&H256::zero().as_bytes())
+        .append(&transactions_root.as_bytes())
+        .append(&receipts_root.as_bytes())
+        .append(&[0u8; 256].as_slice())
+        .append(&0u64)
+        .append(&number)
+        .append(&30_000_000u64)
+        .append(&21_000u64)
+        .append(&timestamp)
+        .append_empty_data()
+        .append(&H256::zero().as_bytes())
+        .append(&[0u8; 8].as_slice());
+    stream.out().to_vec()
+}
+
+/// Witness of a block with the given number and timestamp that includes only `transaction`.
+pub(crate) fn witness(
+    transaction: Vec<u8>,
+    receipt: Vec<u8>,
+    number: u64,
+    timestamp: u64,
+) -> PaymentWitness {
+    let key = rlp::encode(&0u64).to_vec();
+    let (transactions_root, tx_proof) =
+        mpt::build_proof(&[(key.clone(), transaction.clone())], &key);
+    let (receipts_root, receipt_proof) = mpt::build_proof(&[(key.clone(), receipt.clone())], &key);
+    PaymentWitness {
+        header: header(number, timestamp, transactions_root, receipts_root),
+        transaction,
+        tx_index: 0,
+        tx_proof,
+        receipt,
+        receipt_proof,
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding of signed L3 transactions, including sender recovery.
+
+use alloc::vec::Vec;
+
+use k256::ecdsa::recoverable;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use rlp::RlpStream;
+
+use crate::rlp_util::{decode_address_opt, decode_u256, decode_u64, list};
+use crate::{keccak256, Address, DecodeError, H256, U256};
+
+/// Signed transaction together with the sender recovered from its signature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transaction {
+    pub hash: H256,
+    /// Chain ID the transaction was signed for, `None` for pre-EIP-155 legacy transactions.
+    pub chain_id: Option<u64>,
+    pub from: Address,
+    /// Recipient of the transaction, `None` for contract creations.
+    pub to: Option<Address>,
+    pub value: U256,
+    pub input: Vec<u8>,
+}
+
+impl Transaction {
+    /// Decode a signed transaction as it is stored in the transactions trie of a block, i.e. the
+    /// RLP list for legacy transactions and the EIP-2718 envelope for typed ones.
+    pub fn decode(encoded: &[u8]) -> Result<Self, DecodeError> {
+        let (tx_type, payload) = match encoded.first() {
+            Some(&tx_type) if tx_type <= 0x7f => (tx_type, &encoded[1..]),
+            _ => (0, encoded),
+        };
+        // Number of unsigned fields and positions of `to`, `value` and `input` per type.
+        let (fields, to, value, input) = match tx_type {
+            0 => (6, 3, 4, 5),
+            1 => (8, 4, 5, 6),
+            2 => (9, 5, 6, 7),
+            _ => return Err(DecodeError::UnsupportedTransactionType(tx_type)),
+        };
+
+        let rlp = list(payload)?;
+        if rlp.item_count()? != fields + 3 {
+            return Err(DecodeError::InvalidRlp);
+        }
+        let v = decode_u64(&rlp.at(fields)?)?;
+        let r = decode_u256(&rlp.at(fields + 1)?)?;
+        let s = decode_u256(&rlp.at(fields + 2)?)?;
+
+        let mut unsigned = RlpStream::new();
+        let (chain_id, recovery_id) = match tx_type {
+            0 if v >= 35 => {
+                // EIP-155: the chain ID is folded into `v` and appended to the signed payload.
+                let chain_id = (v - 35) / 2;
+                unsigned.begin_list(fields + 3);
+                for i in 0..fields {
+                    unsigned.append_raw(rlp.at(i)?.as_raw(), 1);
+                }
+                unsigned
+                    .append(&chain_id)
+                    .append_empty_data()
+                    .append_empty_data();
+                (Some(chain_id), v - 35 - 2 * chain_id)
+            }
+            0 if v == 27 || v == 28 => {
+                unsigned.begin_list(fields);
+                for i in 0..fields {
+                    unsigned.append_raw(rlp.at(i)?.as_raw(), 1);
+                }
+                (None, v - 27)
+            }
+            0 => return Err(DecodeError::InvalidSignature),
+            _ => {
+                unsigned.begin_list(fields);
+                for i in 0..fields {
+                    unsigned.append_raw(rlp.at(i)?.as_raw(), 1);
+                }
+                (Some(decode_u64(&rlp.at(0)?)?), v)
+            }
+        };
+        let sighash = match tx_type {
+            0 => keccak256(unsigned.out()),
+            _ => {
+                let mut preimage = Vec::with_capacity(unsigned.len() + 1);
+                preimage.push(tx_type);
+                preimage.extend_from_slice(&unsigned.out());
+                keccak256(preimage)
+            }
+        };
+
+        Ok(Self {
+            hash: keccak256(encoded),
+            chain_id,
+            from: recover_signer(sighash, r, s, recovery_id)?,
+            to: decode_address_opt(&rlp.at(to)?)?,
+            value: decode_u256(&rlp.at(value)?)?,
+            input: rlp.at(input)?.data()?.to_vec(),
+        })
+    }
+}
+
+/// Recover the address that produced the signature `(r, s, recovery_id)` over `sighash`.
+fn recover_signer(
+    sighash: H256,
+    r: U256,
+    s: U256,
+    recovery_id: u64,
+) -> Result<Address, DecodeError> {
+    let mut bytes = [0u8; 65];
+    r.to_big_endian(&mut bytes[..32]);
+    s.to_big_endian(&mut bytes[32..64]);
+    bytes[64] = u8::try_from(recovery_id).map_err(|_| DecodeError::InvalidSignature)?;
+
+    let signature =
+        recoverable::Signature::try_from(&bytes[..]).map_err(|_| DecodeError::InvalidSignature)?;
+    let key = signature
+        .recover_verifying_key_from_digest_bytes(&sighash.0.into())
+        .map_err(|_| DecodeError::InvalidSignature)?;
+    let point = key.to_encoded_point(false);
+    Ok(Address::from_slice(
+        &keccak256(&point.as_bytes()[1..])[12..],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Example transaction from EIP-155, signed with the private key 0x4646...46.
+    const EIP155_TX: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+    fn signer() -> Address {
+        Address::from_slice(&hex::decode("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f").unwrap())
+    }
+
+    #[test]
+    fn decodes_eip155_transaction() {
+        let encoded = hex::decode(EIP155_TX).unwrap();
+        let tx = Transaction::decode(&encoded).unwrap();
+        assert_eq!(tx.hash, keccak256(&encoded));
+        assert_eq!(tx.chain_id, Some(1));
+        assert_eq!(tx.from, signer());
+        assert_eq!(tx.to, Some(Address::repeat_byte(0x35)));
+        assert_eq!(tx.value, U256::exp10(18));
+        assert!(tx.input.is_empty());
+    }
+
+    #[test]
+    fn rejects_tampered_signature() {
+        let mut encoded = hex::decode(EIP155_TX).unwrap();
+        let last = encoded.len() - 1;
+        encoded[last] ^= 1;
+        // A modified signature either fails to recover or recovers someone else.
+        let tx = Transaction::decode(&encoded);
+        assert!(tx.map_or(true, |tx| tx.from != signer()));
+    }
+
+    #[test]
+    fn rejects_unknown_type() {
+        assert_eq!(
+            Transaction::decode(&[0x7e, 0xc0]),
+            Err(DecodeError::UnsupportedTransactionType(0x7e))
+        );
+    }
+}
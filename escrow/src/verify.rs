@@ -0,0 +1,734 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checks run by the escrow guests, so single, batched and aggregate claims are judged alike.
+//!
+//! The guests in `methods/guest` are thin wrappers reading their input and committing what these
+//! functions return. Living here, the same checks also run natively in host tests, with the same
+//! inputs and outputs as in the zkVM.
+//!
+//! None of the checks panic. Every failure is reported as a [ClaimStatus] in the journal, so
+//! Bonsai always calls back and the depositor can tell a rejected claim from a pending one.
+
+use alloc::vec::Vec;
+
+use crate::aggregate::aggregate_tx_hash;
+use crate::header::BlockHeader;
+use crate::receipt::Receipt;
+use crate::reference::calldata_reference;
+use crate::transaction::Transaction;
+use crate::{
+    Address, AggregateJournal, AssetKind, ClaimStatus, DecodeError, EscrowClaim, EscrowJournal,
+    PartBlock, PaymentWitness, H256, U256,
+};
+
+/// Signature shared by the checks for each asset kind.
+pub type Check = fn(&EscrowClaim, &PaymentWitness) -> EscrowJournal;
+
+/// Decode a claim as read by the guests and check it against its witness, rejecting claims or
+/// witnesses that do not decode.
+pub fn check_encoded(
+    claim: &[u8],
+    witness: Result<PaymentWitness, DecodeError>,
+    check: Check,
+) -> EscrowJournal {
+    match (EscrowClaim::abi_decode(claim), witness) {
+        (Ok(claim), Ok(witness)) => check(&claim, &witness),
+        (claim, _) => EscrowJournal::rejected(&claim.unwrap_or_default(), ClaimStatus::DecodeError),
+    }
+}
+
+/// Check a claim of any asset kind, as the batch guest does.
+pub fn check_claim(claim: &EscrowClaim, witness: &PaymentWitness) -> EscrowJournal {
+    match claim.kind {
+        AssetKind::Native => check_native(claim, witness),
+        AssetKind::Erc20 => check_erc20(claim, witness),
+        AssetKind::Erc721 | AssetKind::Erc1155 => check_nft(claim, witness),
+    }
+}
+
+/// Check a claim against the value carried by the L3 transaction.
+pub fn check_native(claim: &EscrowClaim, witness: &PaymentWitness) -> EscrowJournal {
+    if claim.kind != AssetKind::Native {
+        return EscrowJournal::rejected(claim, ClaimStatus::UnsupportedAsset);
+    }
+    // Check the transaction against the L3 block header instead of asking an RPC node.
+    let (header, tx, receipt) = match verify(claim, witness) {
+        Ok(verified) => verified,
+        Err(status) => return EscrowJournal::rejected(claim, status),
+    };
+    let payee = tx.to.unwrap_or_default();
+    let reference = calldata_reference(&tx.input).unwrap_or_default();
+
+    let status = if !claim.in_window(header.timestamp) {
+        // Payments made before the deposit existed, or after it lapsed, do not count.
+        ClaimStatus::StaleBlock
+    } else if !receipt.success {
+        // A reverted transaction does not move any value.
+        ClaimStatus::TxReverted
+    } else if tx.from != claim.creditor {
+        ClaimStatus::WrongPayer
+    } else if payee != claim.depositor {
+        ClaimStatus::WrongRecipient
+    } else if tx.value != claim.amount {
+        ClaimStatus::WrongAmount
+    } else if reference != claim.reference() {
+        ClaimStatus::MissingReference
+    } else {
+        ClaimStatus::Accepted
+    };
+
+    EscrowJournal {
+        status,
+        chain_id: chain_id(&tx),
+        block_hash: header.hash,
+        block_number: header.number,
+        block_timestamp: header.timestamp,
+        tx_hash: tx.hash,
+        kind: AssetKind::Native,
+        token: Address::zero(),
+        token_id: U256::zero(),
+        payer: tx.from,
+        payee,
+        amount: tx.value,
+        escrow: claim.escrow,
+        escrow_id: claim.escrow_id,
+        reference,
+    }
+}
+
+/// Check a claim against an ERC-20 `Transfer` event in the receipt of the L3 transaction.
+pub fn check_erc20(claim: &EscrowClaim, witness: &PaymentWitness) -> EscrowJournal {
+    if claim.kind != AssetKind::Erc20 {
+        return EscrowJournal::rejected(claim, ClaimStatus::UnsupportedAsset);
+    }
+    check_token_transfer(claim, witness)
+}
+
+/// Check a claim against an ERC-721 or ERC-1155 transfer event in the receipt of the L3
+/// transaction. ERC-1155 batches are split into one transfer per token ID.
+pub fn check_nft(claim: &EscrowClaim, witness: &PaymentWitness) -> EscrowJournal {
+    if !claim.kind.is_nft() {
+        return EscrowJournal::rejected(claim, ClaimStatus::UnsupportedAsset);
+    }
+    check_token_transfer(claim, witness)
+}
+
+/// Verify the witness and that it proves the claimed transaction.
+fn verify(
+    claim: &EscrowClaim,
+    witness: &PaymentWitness,
+) -> Result<(BlockHeader, Transaction, Receipt), ClaimStatus> {
+    let (header, tx, receipt) = witness.verify()?;
+    if tx.hash != claim.tx_hash {
+        return Err(ClaimStatus::TxNotFound);
+    }
+    Ok((header, tx, receipt))
+}
+
+fn check_token_transfer(claim: &EscrowClaim, witness: &PaymentWitness) -> EscrowJournal {
+    let (header, tx, receipt) = match verify(claim, witness) {
+        Ok(verified) => verified,
+        Err(status) => return EscrowJournal::rejected(claim, status),
+    };
+
+    // The receipt is proven against the same header as the transaction, so its logs are the
+    // events this transaction emitted. Report the matching transfer if there is one, otherwise
+    // the first transfer of the token ID so a failed claim still shows what was sent.
+    let transfers: Vec<_> = receipt
+        .token_transfers(claim.kind, claim.token)
+        .filter(|transfer| transfer.token_id == claim.token_id)
+        .collect();
+    let matching = transfers.iter().find(|transfer| {
+        transfer.from == claim.creditor
+            && transfer.to == claim.depositor
+            && transfer.value == claim.amount
+    });
+    let reported = matching.or(transfers.first()).cloned().unwrap_or_default();
+    // The reference is read from the calldata of the transaction itself, so it was signed by the
+    // payer. A token contract called through another contract cannot be tagged this way.
+    let reference = calldata_reference(&tx.input).unwrap_or_default();
+
+    let status = if !claim.in_window(header.timestamp) {
+        ClaimStatus::StaleBlock
+    } else if !receipt.success {
+        ClaimStatus::TxReverted
+    } else if transfers.is_empty() {
+        ClaimStatus::TransferNotFound
+    } else if reported.from != claim.creditor {
+        ClaimStatus::WrongPayer
+    } else if reported.to != claim.depositor {
+        ClaimStatus::WrongRecipient
+    } else if reported.value != claim.amount {
+        ClaimStatus::WrongAmount
+    } else if reference != claim.reference() {
+        ClaimStatus::MissingReference
+    } else {
+        ClaimStatus::Accepted
+    };
+
+    EscrowJournal {
+        status,
+        chain_id: chain_id(&tx),
+        block_hash: header.hash,
+        block_number: header.number,
+        block_timestamp: header.timestamp,
+        tx_hash: tx.hash,
+        kind: claim.kind,
+        token: claim.token,
+        token_id: claim.token_id,
+        payer: reported.from,
+        payee: reported.to,
+        amount: reported.value,
+        escrow: claim.escrow,
+        escrow_id: claim.escrow_id,
+        reference,
+    }
+}
+
+/// Check an aggregate claim, summing what each of `tx_hashes` paid the depositor. The witnesses
+/// are in the same order as the hashes.
+pub fn check_aggregate(
+    claim: &EscrowClaim,
+    tx_hashes: &[H256],
+    witnesses: &[PaymentWitness],
+) -> AggregateJournal {
+    let (result, blocks) = match sum_parts(claim, tx_hashes, witnesses) {
+        Ok((header, blocks, chain_id, total)) => {
+            let result = EscrowJournal {
+                status: match total == claim.amount {
+                    true => ClaimStatus::Accepted,
+                    false => ClaimStatus::WrongAmount,
+                },
+                chain_id,
+                block_hash: header.hash,
+                block_number: header.number,
+                block_timestamp: header.timestamp,
+                tx_hash: claim.tx_hash,
+                kind: claim.kind,
+                token: claim.token,
+                token_id: claim.token_id,
+                payer: claim.creditor,
+                payee: claim.depositor,
+                amount: total,
+                escrow: claim.escrow,
+                escrow_id: claim.escrow_id,
+                reference: claim.reference(),
+            };
+            (result, blocks)
+        }
+        Err(status) => (EscrowJournal::rejected(claim, status), Vec::new()),
+    };
+    AggregateJournal {
+        result,
+        tx_hashes: tx_hashes.to_vec(),
+        blocks,
+    }
+}
+
+/// Total paid by the parts of an aggregate claim, with the header of the latest part, the block of
+/// every part and the chain ID they share. Fails with the status of the first part that does not
+/// count.
+///
+/// Only one chain ID ends up in the journal, so every part must be paid on the same chain for the
+/// one checked on L1 to hold for all of them. The header of each part is taken on trust here and
+/// must be checked on L1 through its block.
+fn sum_parts(
+    claim: &EscrowClaim,
+    tx_hashes: &[H256],
+    witnesses: &[PaymentWitness],
+) -> Result<(BlockHeader, Vec<PartBlock>, u64, U256), ClaimStatus> {
+    if tx_hashes.len() != witnesses.len() || aggregate_tx_hash(tx_hashes) != claim.tx_hash {
+        return Err(ClaimStatus::TxNotFound);
+    }
+    if (0..tx_hashes.len()).any(|i| tx_hashes[..i].contains(&tx_hashes[i])) {
+        return Err(ClaimStatus::DuplicateTransaction);
+    }
+
+    let mut total = U256::zero();
+    let mut blocks = Vec::with_capacity(tx_hashes.len());
+    let mut latest: Option<(BlockHeader, u64)> = None;
+    for (tx_hash, witness) in tx_hashes.iter().zip(witnesses) {
+        let part = EscrowClaim {
+            tx_hash: *tx_hash,
+            ..claim.clone()
+        };
+        let (header, chain_id, paid) = contribution(&part, witness)?;
+        if matches!(&latest, Some((_, latest_chain)) if *latest_chain != chain_id) {
+            return Err(ClaimStatus::WrongChain);
+        }
+        total = total.saturating_add(paid);
+        blocks.push(PartBlock {
+            hash: header.hash,
+            number: header.number,
+            timestamp: header.timestamp,
+        });
+        let is_latest = match &latest {
+            Some((latest, _)) => header.timestamp >= latest.timestamp,
+            None => true,
+        };
+        if is_latest {
+            latest = Some((header, chain_id));
+        }
+    }
+    let (header, chain_id) = latest.ok_or(ClaimStatus::TxNotFound)?;
+    Ok((header, blocks, chain_id, total))
+}
+
+/// Amount a single transaction pays towards `claim`, which it must settle on its own except for
+/// the amount.
+fn contribution(
+    claim: &EscrowClaim,
+    witness: &PaymentWitness,
+) -> Result<(BlockHeader, u64, U256), ClaimStatus> {
+    let (header, tx, receipt) = verify(claim, witness)?;
+    if !claim.in_window(header.timestamp) {
+        return Err(ClaimStatus::StaleBlock);
+    }
+    if !receipt.success {
+        return Err(ClaimStatus::TxReverted);
+    }
+    if calldata_reference(&tx.input) != Some(claim.reference()) {
+        return Err(ClaimStatus::MissingReference);
+    }
+
+    let paid = match claim.kind {
+        AssetKind::Native if tx.from != claim.creditor => return Err(ClaimStatus::WrongPayer),
+        AssetKind::Native if tx.to != Some(claim.depositor) => {
+            return Err(ClaimStatus::WrongRecipient)
+        }
+        AssetKind::Native => tx.value,
+        _ => receipt
+            .token_transfers(claim.kind, claim.token)
+            .filter(|transfer| {
+                transfer.token_id == claim.token_id
+                    && transfer.from == claim.creditor
+                    && transfer.to == claim.depositor
+            })
+            .fold(U256::zero(), |paid, transfer| {
+                paid.saturating_add(transfer.value)
+            }),
+    };
+    match paid.is_zero() {
+        true => Err(ClaimStatus::TransferNotFound),
+        false => Ok((header, chain_id(&tx), paid)),
+    }
+}
+
+/// The chain a transaction is signed for. Legacy transactions without EIP-155 replay protection
+/// report chain 0, which `L3L1Escrow` never settles since it is pinned to a non-zero L3 chain ID.
+fn chain_id(tx: &Transaction) -> u64 {
+    tx.chain_id.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use ethabi::Token;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::aggregate::aggregate_tx_hash;
+    use crate::receipt::{Log, TRANSFER_SINGLE_TOPIC, TRANSFER_TOPIC};
+    use crate::test_utils::{self, Wallet, CHAIN_ID};
+    use crate::{keccak256, reference};
+
+    const NUMBER: u64 = 42;
+    const TIMESTAMP: u64 = 1_700_000_000;
+
+    fn creditor() -> Wallet {
+        Wallet::new(0x22)
+    }
+
+    fn depositor() -> Address {
+        Address::repeat_byte(0x11)
+    }
+
+    fn token() -> Address {
+        Address::repeat_byte(0x70)
+    }
+
+    /// Claim of 10 units paid in `kind`, with the hash of the transaction still to be filled in.
+    fn claim(kind: AssetKind) -> EscrowClaim {
+        EscrowClaim {
+            escrow: Address::repeat_byte(0xee),
+            escrow_id: U256::from(7),
+            tx_hash: H256::zero(),
+            kind,
+            token: match kind {
+                AssetKind::Native => Address::zero(),
+                _ => token(),
+            },
+            token_id: match kind {
+                AssetKind::Erc1155 => U256::from(42),
+                _ => U256::zero(),
+            },
+            depositor: depositor(),
+            creditor: creditor().address(),
+            amount: U256::from(10),
+            not_before: TIMESTAMP - 60,
+            not_after: TIMESTAMP + 60,
+        }
+    }
+
+    /// Witness for `transaction` mined at [TIMESTAMP], and `claim` pointed at it.
+    fn mined(
+        mut claim: EscrowClaim,
+        transaction: Vec<u8>,
+        receipt: Vec<u8>,
+    ) -> (EscrowClaim, PaymentWitness) {
+        claim.tx_hash = keccak256(&transaction);
+        let witness = test_utils::witness(transaction, receipt, NUMBER, TIMESTAMP);
+        (claim, witness)
+    }
+
+    /// Native payment of `value` from the creditor, tagged with `tag`.
+    fn native(value: u64, tag: H256) -> (EscrowClaim, PaymentWitness) {
+        let tx = creditor().sign(0, depositor(), value.into(), tag.as_bytes());
+        mined(claim(AssetKind::Native), tx, test_utils::receipt(true, &[]))
+    }
+
+    fn topic(address: Address) -> H256 {
+        H256::from(address)
+    }
+
+    /// ERC-20 `transfer` call from the creditor emitting a `Transfer` of `value` to `to`.
+    fn erc20(value: u64, to: Address) -> (EscrowClaim, PaymentWitness) {
+        let claim = claim(AssetKind::Erc20);
+        let input = reference::tag_calldata(&[0xa9, 0x05, 0x9c, 0xbb], claim.reference());
+        let tx = creditor().sign(0, token(), U256::zero(), &input);
+        let log = Log {
+            address: token(),
+            topics: vec![TRANSFER_TOPIC, topic(creditor().address()), topic(to)],
+            data: ethabi::encode(&[Token::Uint(value.into())]),
+        };
+        mined(claim, tx, test_utils::receipt(true, &[log]))
+    }
+
+    #[test]
+    fn accepts_native_payment() {
+        let (claim, witness) = native(10, claim(AssetKind::Native).reference());
+        let journal = check_native(&claim, &witness);
+        assert_eq!(
+            journal,
+            EscrowJournal {
+                status: ClaimStatus::Accepted,
+                chain_id: CHAIN_ID,
+                block_hash: keccak256(&witness.header),
+                block_number: NUMBER,
+                block_timestamp: TIMESTAMP,
+                tx_hash: claim.tx_hash,
+                kind: AssetKind::Native,
+                token: Address::zero(),
+                token_id: U256::zero(),
+                payer: claim.creditor,
+                payee: depositor(),
+                amount: U256::from(10),
+                escrow: claim.escrow,
+                escrow_id: claim.escrow_id,
+                reference: claim.reference(),
+            }
+        );
+        assert_eq!(check_claim(&claim, &witness), journal);
+        assert_eq!(
+            check_encoded(&claim.abi_encode(), Ok(witness), check_native),
+            journal
+        );
+    }
+
+    #[test]
+    fn reports_why_native_payment_fails() {
+        let reference = claim(AssetKind::Native).reference();
+        let status =
+            |(claim, witness): (EscrowClaim, PaymentWitness)| check_native(&claim, &witness).status;
+        assert_eq!(status(native(9, reference)), ClaimStatus::WrongAmount);
+        assert_eq!(
+            status(native(10, H256::zero())),
+            ClaimStatus::MissingReference
+        );
+
+        let (mut bad_claim, witness) = native(10, reference);
+        bad_claim.not_before = TIMESTAMP + 1;
+        assert_eq!(
+            check_native(&bad_claim, &witness).status,
+            ClaimStatus::StaleBlock
+        );
+
+        let (mut bad_claim, witness) = native(10, reference);
+        bad_claim.tx_hash = H256::repeat_byte(0xab);
+        let journal = check_native(&bad_claim, &witness);
+        assert_eq!(
+            journal,
+            EscrowJournal::rejected(&bad_claim, ClaimStatus::TxNotFound)
+        );
+
+        let (mut bad_claim, witness) = native(10, reference);
+        bad_claim.kind = AssetKind::Erc20;
+        assert_eq!(
+            check_native(&bad_claim, &witness).status,
+            ClaimStatus::UnsupportedAsset
+        );
+
+        let tx = creditor().sign(0, depositor(), 10.into(), reference.as_bytes());
+        let (claim, witness) = mined(
+            claim(AssetKind::Native),
+            tx,
+            test_utils::receipt(false, &[]),
+        );
+        assert_eq!(
+            check_native(&claim, &witness).status,
+            ClaimStatus::TxReverted
+        );
+    }
+
+    #[test]
+    fn checks_erc20_transfer_event() {
+        let (claim, witness) = erc20(10, depositor());
+        let journal = check_erc20(&claim, &witness);
+        assert_eq!(journal.status, ClaimStatus::Accepted);
+        assert_eq!(journal.token, token());
+        assert_eq!(journal.amount, U256::from(10));
+        assert_eq!(journal.reference, claim.reference());
+        // The native guest does not look at token transfers.
+        assert_eq!(
+            check_native(&claim, &witness).status,
+            ClaimStatus::UnsupportedAsset
+        );
+
+        let (claim, witness) = erc20(10, Address::repeat_byte(0x33));
+        let journal = check_claim(&claim, &witness);
+        assert_eq!(journal.status, ClaimStatus::WrongRecipient);
+        assert_eq!(journal.payee, Address::repeat_byte(0x33));
+    }
+
+    #[test]
+    fn checks_erc1155_token_id() {
+        let claim = claim(AssetKind::Erc1155);
+        let transfer = |id: u64| Log {
+            address: token(),
+            topics: vec![
+                TRANSFER_SINGLE_TOPIC,
+                topic(creditor().address()),
+                topic(creditor().address()),
+                topic(depositor()),
+            ],
+            data: ethabi::encode(&[Token::Uint(id.into()), Token::Uint(10.into())]),
+        };
+        let input = reference::tag_calldata(&[0xf2, 0x42, 0x43, 0x2a], claim.reference());
+        let tx = creditor().sign(0, token(), U256::zero(), &input);
+
+        let receipt = test_utils::receipt(true, &[transfer(43)]);
+        let (other, witness) = mined(claim.clone(), tx.clone(), receipt);
+        assert_eq!(
+            check_nft(&other, &witness).status,
+            ClaimStatus::TransferNotFound
+        );
+
+        let receipt = test_utils::receipt(true, &[transfer(43), transfer(42)]);
+        let (claim, witness) = mined(claim, tx, receipt);
+        assert_eq!(check_nft(&claim, &witness).status, ClaimStatus::Accepted);
+        assert_eq!(
+            check_erc20(&claim, &witness).status,
+            ClaimStatus::UnsupportedAsset
+        );
+    }
+
+    #[test]
+    fn rejects_undecodable_claim() {
+        let (_, witness) = native(10, H256::zero());
+        let journal = check_encoded(&[0u8; 7], Ok(witness), check_claim);
+        assert_eq!(
+            journal,
+            EscrowJournal::rejected(&EscrowClaim::default(), ClaimStatus::DecodeError)
+        );
+    }
+
+    #[test]
+    fn rejects_undecodable_witness_against_its_claim() {
+        let claim = claim(AssetKind::Native);
+        let journal = check_encoded(
+            &claim.abi_encode(),
+            Err(DecodeError::InvalidWitness),
+            check_claim,
+        );
+        assert_eq!(
+            journal,
+            EscrowJournal::rejected(&claim, ClaimStatus::DecodeError)
+        );
+    }
+
+    /// Native payments of `values` towards one deposit, each in its own block.
+    fn parts(values: &[u64]) -> (EscrowClaim, Vec<H256>, Vec<PaymentWitness>) {
+        let claim = claim(AssetKind::Native);
+        let (tx_hashes, witnesses) = values
+            .iter()
+            .enumerate()
+            .map(|(nonce, value)| {
+                let tag = claim.reference();
+                let tx =
+                    creditor().sign(nonce as u64, depositor(), (*value).into(), tag.as_bytes());
+                let number = NUMBER + nonce as u64;
+                let receipt = test_utils::receipt(true, &[]);
+                (
+                    keccak256(&tx),
+                    test_utils::witness(tx, receipt, number, TIMESTAMP + nonce as u64),
+                )
+            })
+            .unzip::<_, _, Vec<_>, Vec<_>>();
+        let claim = EscrowClaim {
+            tx_hash: aggregate_tx_hash(&tx_hashes),
+            ..claim
+        };
+        (claim, tx_hashes, witnesses)
+    }
+
+    #[test]
+    fn sums_aggregate_parts() {
+        let (claim, tx_hashes, witnesses) = parts(&[4, 6]);
+        let journal = check_aggregate(&claim, &tx_hashes, &witnesses);
+        assert_eq!(journal.tx_hashes, tx_hashes);
+        assert_eq!(journal.result.status, ClaimStatus::Accepted);
+        assert_eq!(journal.result.amount, U256::from(10));
+        assert_eq!(journal.result.tx_hash, claim.tx_hash);
+        // The latest part determines the reported block.
+        assert_eq!(journal.result.block_number, NUMBER + 1);
+        assert_eq!(journal.result.block_hash, keccak256(&witnesses[1].header));
+
+        let (claim, tx_hashes, witnesses) = parts(&[4, 5]);
+        let journal = check_aggregate(&claim, &tx_hashes, &witnesses);
+        assert_eq!(journal.result.status, ClaimStatus::WrongAmount);
+        assert_eq!(journal.result.amount, U256::from(9));
+    }
+
+    #[test]
+    fn commits_the_block_of_every_aggregate_part() {
+        let (claim, tx_hashes, mut witnesses) = parts(&[4, 6]);
+        // A header made up for an earlier part, which only L1 can tell from a real one.
+        let tx = witnesses[0].transaction.clone();
+        let forged_number = NUMBER + 100;
+        witnesses[0] =
+            test_utils::witness(tx, test_utils::receipt(true, &[]), forged_number, TIMESTAMP);
+
+        let journal = check_aggregate(&claim, &tx_hashes, &witnesses);
+        assert_eq!(journal.result.status, ClaimStatus::Accepted);
+        assert_eq!(journal.result.block_number, NUMBER + 1);
+        assert_eq!(
+            journal.blocks,
+            vec![
+                PartBlock {
+                    hash: keccak256(&witnesses[0].header),
+                    number: forged_number,
+                    timestamp: TIMESTAMP,
+                },
+                PartBlock {
+                    hash: keccak256(&witnesses[1].header),
+                    number: NUMBER + 1,
+                    timestamp: TIMESTAMP + 1,
+                },
+            ]
+        );
+
+        // Rejected claims commit no blocks.
+        let (claim, tx_hashes, witnesses) = parts(&[4, 6]);
+        let journal = check_aggregate(&claim, &tx_hashes[..1], &witnesses[..1]);
+        assert_eq!(journal.result.status, ClaimStatus::TxNotFound);
+        assert!(journal.blocks.is_empty());
+    }
+
+    #[test]
+    fn rejects_repeated_aggregate_part() {
+        let (claim, tx_hashes, witnesses) = parts(&[5]);
+        let tx_hashes = vec![tx_hashes[0]; 2];
+        let witnesses = vec![witnesses[0].clone(); 2];
+        let claim = EscrowClaim {
+            tx_hash: aggregate_tx_hash(&tx_hashes),
+            ..claim
+        };
+        let journal = check_aggregate(&claim, &tx_hashes, &witnesses);
+        assert_eq!(journal.result.status, ClaimStatus::DuplicateTransaction);
+    }
+
+    #[test]
+    fn rejects_aggregate_paid_on_several_chains() {
+        let (_, mut tx_hashes, mut witnesses) = parts(&[4, 6]);
+        let reference = claim(AssetKind::Native).reference();
+        let tx = creditor().sign_on(CHAIN_ID + 1, 1, depositor(), 6.into(), reference.as_bytes());
+        tx_hashes[1] = keccak256(&tx);
+        witnesses[1] =
+            test_utils::witness(tx, test_utils::receipt(true, &[]), NUMBER + 1, TIMESTAMP);
+        let claim = EscrowClaim {
+            tx_hash: aggregate_tx_hash(&tx_hashes),
+            ..claim(AssetKind::Native)
+        };
+
+        // Each part settles on its own, only their mix is rejected.
+        for (tx_hash, witness) in tx_hashes.iter().zip(&witnesses) {
+            let part = EscrowClaim {
+                tx_hash: *tx_hash,
+                amount: 6.into(),
+                ..claim.clone()
+            };
+            assert_ne!(check_native(&part, witness).status, ClaimStatus::WrongChain);
+        }
+        let journal = check_aggregate(&claim, &tx_hashes, &witnesses);
+        assert_eq!(journal.result.status, ClaimStatus::WrongChain);
+    }
+
+    #[test]
+    fn rejects_aggregate_of_other_transactions() {
+        let (claim, mut tx_hashes, witnesses) = parts(&[4, 6]);
+        tx_hashes.swap(0, 1);
+        let journal = check_aggregate(&claim, &tx_hashes, &witnesses);
+        assert_eq!(journal.result.status, ClaimStatus::TxNotFound);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn native_accepts_exact_amount(value in 0u64..20, amount in 0u64..20) {
+            let (mut claim, witness) = native(value, claim(AssetKind::Native).reference());
+            claim.amount = amount.into();
+            let journal = check_native(&claim, &witness);
+            prop_assert_eq!(journal.status.is_accepted(), value == amount);
+            prop_assert_eq!(journal.amount, U256::from(value));
+        }
+
+        #[test]
+        fn window_bounds_are_inclusive(before in 0u64..120, after in 0u64..120) {
+            let (mut claim, witness) = native(10, claim(AssetKind::Native).reference());
+            claim.not_before = TIMESTAMP + 60 - before;
+            claim.not_after = TIMESTAMP - 60 + after;
+            let in_window = before >= 60 && after >= 60;
+            let status = check_native(&claim, &witness).status;
+            prop_assert_eq!(status == ClaimStatus::StaleBlock, !in_window);
+        }
+
+        #[test]
+        fn arbitrary_input_never_panics(input in proptest::collection::vec(any::<u8>(), 0..400)) {
+            let (_, witness) = native(10, H256::zero());
+            let journal = check_encoded(&input, Ok(witness), check_claim);
+            prop_assert!(!journal.status.is_accepted());
+        }
+
+        #[test]
+        fn aggregate_accepts_any_split(split in proptest::collection::vec(1u64..5, 1..4)) {
+            let (mut claim, tx_hashes, witnesses) = parts(&split);
+            claim.amount = split.iter().sum::<u64>().into();
+            let journal = check_aggregate(&claim, &tx_hashes, &witnesses);
+            prop_assert_eq!(journal.result.status, ClaimStatus::Accepted);
+        }
+    }
+}
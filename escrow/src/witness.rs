@@ -0,0 +1,133 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Data the host supplies next to a claim so the guest can check the L3 payment itself.
+
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::header::BlockHeader;
+use crate::receipt::Receipt;
+use crate::transaction::Transaction;
+use crate::{mpt, DecodeError};
+
+/// Inclusion proof of an L3 transaction and its receipt in a block.
+///
+/// The guest deserializes this from the words of its input right after the fixed-size claim.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PaymentWitness {
+    /// RLP encoded header of the block including the payment.
+    pub header: Vec<u8>,
+    /// Signed payment transaction, as stored in the transactions trie.
+    pub transaction: Vec<u8>,
+    /// Index of the transaction in the block.
+    pub tx_index: u64,
+    /// Proof of `transaction` against the transactions root of `header`.
+    pub tx_proof: Vec<Vec<u8>>,
+    /// Receipt of the transaction, as stored in the receipts trie.
+    pub receipt: Vec<u8>,
+    /// Proof of `receipt` against the receipts root of `header`.
+    pub receipt_proof: Vec<Vec<u8>>,
+}
+
+impl PaymentWitness {
+    /// Check that the transaction and its receipt are included in the block and decode them.
+    pub fn verify(&self) -> Result<(BlockHeader, Transaction, Receipt), DecodeError> {
+        let header = BlockHeader::decode(&self.header)?;
+        let key = rlp::encode(&self.tx_index);
+        let value = mpt::verify_proof(header.transactions_root, &key, &self.tx_proof)?;
+        if value != self.transaction.as_slice() {
+            return Err(DecodeError::InvalidProof);
+        }
+        let value = mpt::verify_proof(header.receipts_root, &key, &self.receipt_proof)?;
+        if value != self.receipt.as_slice() {
+            return Err(DecodeError::InvalidProof);
+        }
+        let transaction = Transaction::decode(&self.transaction)?;
+        let receipt = Receipt::decode(&self.receipt)?;
+        Ok((header, transaction, receipt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{header, receipt};
+    use crate::H256;
+
+    // Example transaction from EIP-155.
+    const TX: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+    fn witness() -> PaymentWitness {
+        let other = hex::decode("c0").unwrap();
+        let transaction = hex::decode(TX).unwrap();
+        let items = vec![
+            (rlp::encode(&0u64).to_vec(), other),
+            (rlp::encode(&1u64).to_vec(), transaction.clone()),
+        ];
+        let (tx_root, tx_proof) = mpt::build_proof(&items, &rlp::encode(&1u64));
+
+        let receipt = receipt(true, &[]);
+        let items = vec![
+            (rlp::encode(&0u64).to_vec(), receipt.clone()),
+            (rlp::encode(&1u64).to_vec(), receipt.clone()),
+        ];
+        let (receipts_root, receipt_proof) = mpt::build_proof(&items, &rlp::encode(&1u64));
+        PaymentWitness {
+            header: header(42, 1_700_000_000, tx_root, receipts_root),
+            transaction,
+            tx_index: 1,
+            tx_proof,
+            receipt,
+            receipt_proof,
+        }
+    }
+
+    #[test]
+    fn verifies_included_transaction() {
+        let witness = witness();
+        let (header, tx, receipt) = witness.verify().unwrap();
+        assert_eq!(header.number, 42);
+        assert_eq!(header.timestamp, 1_700_000_000);
+        assert_eq!(tx.hash, crate::keccak256(&witness.transaction));
+        assert!(receipt.success);
+    }
+
+    #[test]
+    fn rejects_wrong_index() {
+        let mut witness = witness();
+        witness.tx_index = 0;
+        assert_eq!(witness.verify(), Err(DecodeError::InvalidProof));
+    }
+
+    #[test]
+    fn rejects_other_header() {
+        let mut witness = witness();
+        witness.header = header(
+            42,
+            1_700_000_000,
+            H256::repeat_byte(1),
+            H256::repeat_byte(2),
+        );
+        assert_eq!(witness.verify(), Err(DecodeError::InvalidProof));
+    }
+
+    #[test]
+    fn rejects_other_receipt() {
+        let mut witness = witness();
+        witness.receipt[1] ^= 1;
+        assert_eq!(witness.verify(), Err(DecodeError::InvalidProof));
+    }
+}
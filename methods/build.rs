@@ -12,6 +12,57 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use risc0_build::GuestListEntry;
+use risc0_zkp::core::sha::Digest as ImageId;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
 fn main() {
-    risc0_build::embed_methods();
+    let guests = risc0_build::embed_methods();
+    write_manifest(&guests);
+}
+
+/// Write `guests.json`, recording the image ID and ELF hash of every guest, next to `methods.rs`.
+fn write_manifest(guests: &[GuestListEntry]) {
+    let guests: Vec<_> = guests
+        .iter()
+        .map(|guest| {
+            let image_id = <[u8; 32]>::from(ImageId::from(guest.image_id));
+            json!({
+                "name": guest.name.to_lowercase(),
+                "image_id": format!("0x{}", hex::encode(image_id)),
+                "elf_sha256": format!("0x{}", hex::encode(Sha256::digest(&guest.elf))),
+            })
+        })
+        .collect();
+
+    let manifest = json!({
+        "risc0_version": risc0_version(),
+        "profile": env::var("PROFILE").unwrap(),
+        "guests": guests,
+    });
+    let manifest = serde_json::to_string_pretty(&manifest).unwrap();
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::write(out_dir.join("guests.json"), manifest).unwrap();
+}
+
+/// Version of `risc0-zkvm` locked for the workspace.
+fn risc0_version() -> String {
+    let lock = Path::new(env!("CARGO_MANIFEST_DIR")).join("../Cargo.lock");
+    let lock = fs::read_to_string(lock).unwrap_or_default();
+    let mut lines = lock.lines();
+    while let Some(line) = lines.next() {
+        if line == "name = \"risc0-zkvm\"" {
+            if let Some(version) = lines
+                .next()
+                .and_then(|line| line.strip_prefix("version = "))
+            {
+                return version.trim_matches('"').into();
+            }
+        }
+    }
+    "unknown".into()
 }
@@ -0,0 +1,59 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use escrow_core::aggregate::decode_tx_count;
+use escrow_core::batch::COUNT_LEN;
+use escrow_core::input::take;
+use escrow_core::verify::check_aggregate;
+use escrow_core::{AggregateJournal, ClaimStatus, EscrowClaim, EscrowJournal, H256};
+use hello_bonsai_methods_guest::{decode_witnesses, read_input};
+use risc0_zkvm::guest::env;
+
+risc0_zkvm::guest::entry!(main);
+
+pub fn main() {
+    // The claim is followed by the number of contributing transactions and their hashes, then one
+    // witness per transaction in the same order.
+    let mut input = read_input();
+    let claim = take(&mut input, EscrowClaim::ENCODED_LEN).and_then(EscrowClaim::abi_decode);
+    let hashes = take(&mut input, COUNT_LEN)
+        .and_then(decode_tx_count)
+        .and_then(|count| take(&mut input, count * 32));
+    let tx_hashes: Vec<H256> = hashes
+        .clone()
+        .unwrap_or_default()
+        .chunks(32)
+        .map(H256::from_slice)
+        .collect();
+    let witnesses: Result<Vec<_>, _> = decode_witnesses(input, tx_hashes.len())
+        .into_iter()
+        .collect();
+
+    let journal = match (claim, hashes, witnesses) {
+        (Ok(claim), Ok(_), Ok(witnesses)) => check_aggregate(&claim, &tx_hashes, &witnesses),
+        (claim, _, _) => AggregateJournal {
+            result: EscrowJournal::rejected(&claim.unwrap_or_default(), ClaimStatus::DecodeError),
+            tx_hashes,
+            blocks: Vec::new(),
+        },
+    };
+    env::commit_slice(&journal.abi_encode());
+}
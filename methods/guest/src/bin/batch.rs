@@ -0,0 +1,48 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use escrow_core::batch::{decode_claim_count, COUNT_LEN};
+use escrow_core::input::take;
+use escrow_core::verify::{check_claim, check_encoded};
+use escrow_core::{BatchJournal, EscrowClaim};
+use hello_bonsai_methods_guest::{decode_witnesses, read_input};
+use risc0_zkvm::guest::env;
+
+risc0_zkvm::guest::entry!(main);
+
+pub fn main() {
+    // The claims are preceded by their count. An invalid count leaves nothing to check, which
+    // commits an empty batch.
+    let mut input = read_input();
+    let count = take(&mut input, COUNT_LEN)
+        .and_then(decode_claim_count)
+        .unwrap_or(0);
+    let claims = take(&mut input, count * EscrowClaim::ENCODED_LEN).unwrap_or_default();
+
+    // Witnesses follow in the same order as the claims. Blocks may differ between claims, each
+    // witness carries its own header.
+    let results: Vec<_> = claims
+        .chunks(EscrowClaim::ENCODED_LEN)
+        .zip(decode_witnesses(input, count))
+        .map(|(claim, witness)| check_encoded(claim, witness, check_claim))
+        .collect();
+    env::commit_slice(&BatchJournal { results }.abi_encode());
+}
@@ -13,41 +13,27 @@
 // limitations under the License.
 
 #![no_main]
-#![no_std]
 
-use ethabi::ethereum_types::U256;
-use ethabi::{ParamType, Token};
+use evm_core::{state_diff, Env, EvmResult, OriginalAccounts, ZkDb, EVM};
 use risc0_zkvm::guest::env;
-use ethers::providers::{Http, Middleware, Provider};
-use ethers::providers::{Http, Middleware, Provider, StreamExt};
-use ethers::types::{H256, TxHash};
-use primitive_types::{H160 as Address, U256};
 
 risc0_zkvm::guest::entry!(main);
 
-#[tokio::main]
-pub async fn main() {
-    // NOTE: env::read_slice requires a length argument. Reads must be of known
-    // length. https://github.com/risc0/risc0/issues/402
-    let length: &[u32] = env::read_slice(1);
-    let input: &[u8] = env::read_slice(length[0] as usize);
-    let input = ethabi::decode_whole(&[ParamType::String, ParamType::String, ParamType::String, ParamType::Uint(256)], input).unwrap();
-
-    let tx_hash = input[0].clone().into_string().unwrap();
-    let msg_sender = input[1].clone().into_address().unwrap();
-    let creditor = input[2].clone().into_address().unwrap();
-    let amount: U256 = input[3].clone().into_uint().unwrap();
-
-
-    // RPC of Layer 3 (add your own RPC node here)
-    let client = Provider::<Http>::try_from("https://2241-194-65-8-182.eu.ngrok.io").expect("Invalid RPC url");
-
-    let tx_hash = H256::from_str(tx_hash).expect("Invalid transaction hash");
-
-    let tx = client.get_transaction(tx_hash).await.unwrap().unwrap();
-
-    // check
-    let res =  creditor == tx.from && amount == tx.value && tx.to == msg_sender;
-
-    env::commit_slice(&ethabi::encode(&[Token::Bool(res), Token::Address(msg_sender)]));
+pub fn main() {
+    let env: Env = env::read();
+    let db: ZkDb = env::read();
+
+    // Replay the transaction against the state recorded by the host during preflight.
+    let mut evm = EVM::new();
+    evm.database(OriginalAccounts::new(db));
+    evm.env = env;
+    let (res, state) = evm.transact();
+    let (_, original) = evm.take_db().into_parts();
+
+    env::commit(&EvmResult {
+        exit_reason: res.exit_reason,
+        gas_used: res.gas_used,
+        logs: res.logs,
+        state: state_diff(&state, &original),
+    });
 }
@@ -0,0 +1,33 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+#![no_std]
+
+use escrow_core::input::take;
+use escrow_core::verify::{check_encoded, check_nft};
+use escrow_core::EscrowClaim;
+use hello_bonsai_methods_guest::{decode_witnesses, read_input};
+use risc0_zkvm::guest::env;
+
+risc0_zkvm::guest::entry!(main);
+
+pub fn main() {
+    // The claim has a fixed-size encoding and is followed by the witness of the payment.
+    let mut input = read_input();
+    let claim = take(&mut input, EscrowClaim::ENCODED_LEN).unwrap_or_default();
+    let witness = decode_witnesses(input, 1).remove(0);
+
+    env::commit_slice(&check_encoded(claim, witness, check_nft).abi_encode());
+}
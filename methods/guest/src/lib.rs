@@ -0,0 +1,62 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Input handling shared by the escrow guests.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use escrow_core::input::{decode_input_len, LEN_PREFIX_LEN};
+use escrow_core::{DecodeError, PaymentWitness};
+use risc0_zkvm::guest::env;
+use risc0_zkvm::serde::Deserializer;
+use serde::Deserialize;
+
+/// Read the whole input of the guest, as framed by `frame_input`. An invalid length prefix reads
+/// as empty input, which then fails to decode.
+pub fn read_input() -> &'static [u8] {
+    // NOTE: env::read_slice requires a length argument. Reads must be of known
+    // length. https://github.com/risc0/risc0/issues/402
+    match decode_input_len(env::read_slice(LEN_PREFIX_LEN)) {
+        Ok(len) => env::read_slice(len),
+        Err(_) => &[],
+    }
+}
+
+/// Deserialize `count` witnesses serialized back to back by `witness_input` on the host.
+///
+/// Once a witness fails to deserialize, the position of the next one is unknown, so it and every
+/// witness after it are reported as [DecodeError::InvalidWitness].
+pub fn decode_witnesses(data: &[u8], count: usize) -> Vec<Result<PaymentWitness, DecodeError>> {
+    let words: Vec<u32> = data
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+        .collect();
+    let mut deserializer = Deserializer::new(words.as_slice());
+    let mut failed = data.len() % 4 != 0;
+    (0..count)
+        .map(|_| {
+            if !failed {
+                match PaymentWitness::deserialize(&mut deserializer) {
+                    Ok(witness) => return Ok(witness),
+                    Err(_) => failed = true,
+                }
+            }
+            Err(DecodeError::InvalidWitness)
+        })
+        .collect()
+}
@@ -0,0 +1,163 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Host-side discovery and input of payments split across several L3 transactions.
+
+use std::error::Error;
+use std::ops::RangeInclusive;
+
+use escrow_core::aggregate::{aggregate_tx_hash, encode_tx_hashes};
+use escrow_core::input::frame_input;
+use escrow_core::reference::calldata_reference;
+use escrow_core::{AssetKind, EscrowClaim, PaymentWitness, H256};
+use ethers_providers::{Http, Middleware, Provider};
+
+use crate::witness::{fetch_payment_witness, witness_input};
+
+/// Aggregate claim with the witnesses of its transactions, in the order of `tx_hashes`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Aggregate {
+    pub claim: EscrowClaim,
+    pub tx_hashes: Vec<H256>,
+    pub witnesses: Vec<PaymentWitness>,
+}
+
+impl Aggregate {
+    /// Aggregate `tx_hashes` towards `claim`, replacing its transaction hash with their
+    /// aggregate hash as `L3L1Escrow.checkPaymentStatusAggregate` does.
+    pub fn new(claim: EscrowClaim, tx_hashes: Vec<H256>, witnesses: Vec<PaymentWitness>) -> Self {
+        Self {
+            claim: EscrowClaim {
+                tx_hash: aggregate_tx_hash(&tx_hashes),
+                ..claim
+            },
+            tx_hashes,
+            witnesses,
+        }
+    }
+
+    /// Input of the aggregate guest, as submitted by `L3L1Escrow.checkPaymentStatusAggregate`.
+    pub fn guest_input(&self) -> Vec<u8> {
+        let mut input = self.claim.abi_encode();
+        input.extend(encode_tx_hashes(&self.tx_hashes));
+        input.extend(self.witness_input());
+        frame_input(&input)
+    }
+
+    /// Witnesses to pass to `L3L1Escrow.checkPaymentStatusAggregate`.
+    pub fn witness_input(&self) -> Vec<u8> {
+        self.witnesses.iter().flat_map(witness_input).collect()
+    }
+}
+
+/// Fetch the witness of each of `tx_hashes` from the L3 node and aggregate them towards `claim`.
+pub async fn fetch_aggregate(
+    client: &Provider<Http>,
+    claim: EscrowClaim,
+    tx_hashes: Vec<H256>,
+) -> Result<Aggregate, Box<dyn Error>> {
+    let mut witnesses = Vec::with_capacity(tx_hashes.len());
+    for tx_hash in &tx_hashes {
+        witnesses.push(fetch_payment_witness(client, *tx_hash).await?);
+    }
+    Ok(Aggregate::new(claim, tx_hashes, witnesses))
+}
+
+/// Find the transactions in the L3 `blocks` that pay towards `claim`: sent by the creditor inside
+/// the payment window, tagged with the escrow reference and not reverted.
+///
+/// Hashes are returned in chain order. Their number is not limited here, so callers should check
+/// it against `MAX_AGGREGATE_TXS` before submitting them.
+pub async fn discover_payments(
+    client: &Provider<Http>,
+    claim: &EscrowClaim,
+    blocks: RangeInclusive<u64>,
+) -> Result<Vec<H256>, Box<dyn Error>> {
+    let reference = claim.reference();
+    // Token payments are calls to the token contract, native ones go straight to the depositor.
+    let recipient = match claim.kind {
+        AssetKind::Native => claim.depositor,
+        _ => claim.token,
+    };
+
+    let mut tx_hashes = Vec::new();
+    for number in blocks {
+        let block = client
+            .get_block_with_txs(number)
+            .await?
+            .ok_or("block not found")?;
+        if !claim.in_window(block.timestamp.as_u64()) {
+            continue;
+        }
+        for tx in block.transactions {
+            if tx.from != claim.creditor
+                || tx.to != Some(recipient)
+                || calldata_reference(&tx.input) != Some(reference)
+            {
+                continue;
+            }
+            let receipt = client
+                .get_transaction_receipt(tx.hash)
+                .await?
+                .ok_or("receipt not found")?;
+            if receipt.status == Some(1.into()) {
+                tx_hashes.push(tx.hash);
+            }
+        }
+    }
+    Ok(tx_hashes)
+}
+
+#[cfg(test)]
+mod tests {
+    use escrow_core::aggregate::decode_tx_count;
+    use escrow_core::batch::COUNT_LEN;
+    use escrow_core::input::{decode_input_len, LEN_PREFIX_LEN};
+    use escrow_core::{Address, U256};
+
+    use super::*;
+
+    #[test]
+    fn guest_input_follows_claim_with_hashes() {
+        let tx_hashes: Vec<_> = (1..=2).map(H256::from_low_u64_be).collect();
+        let claim = EscrowClaim {
+            escrow: Address::repeat_byte(0xee),
+            escrow_id: U256::from(7),
+            tx_hash: H256::zero(),
+            kind: AssetKind::Native,
+            token: Address::zero(),
+            token_id: U256::zero(),
+            depositor: Address::repeat_byte(0x11),
+            creditor: Address::repeat_byte(0x22),
+            amount: U256::from(10),
+            not_before: 0,
+            not_after: u64::MAX,
+        };
+        let aggregate =
+            Aggregate::new(claim, tx_hashes.clone(), vec![PaymentWitness::default(); 2]);
+        assert_eq!(aggregate.claim.tx_hash, aggregate_tx_hash(&tx_hashes));
+
+        let framed = aggregate.guest_input();
+        let (len, input) = framed.split_at(LEN_PREFIX_LEN);
+        assert_eq!(decode_input_len(len), Ok(input.len()));
+        let (claim, rest) = input.split_at(EscrowClaim::ENCODED_LEN);
+        assert_eq!(EscrowClaim::abi_decode(claim), Ok(aggregate.claim.clone()));
+        assert_eq!(decode_tx_count(&rest[..COUNT_LEN]), Ok(2));
+        assert_eq!(
+            rest[COUNT_LEN..COUNT_LEN + 64],
+            encode_tx_hashes(&tx_hashes)[COUNT_LEN..]
+        );
+        assert_eq!(rest[COUNT_LEN + 64..], aggregate.witness_input());
+    }
+}
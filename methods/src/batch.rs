@@ -0,0 +1,179 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Host-side batching of escrow claims, so many payments are proven in a single guest run.
+
+use std::error::Error;
+use std::mem;
+
+use escrow_core::batch::{encode_claims, MAX_BATCH_SIZE};
+use escrow_core::input::frame_input;
+use escrow_core::{EscrowClaim, PaymentWitness, H256, U256};
+use ethers_providers::{Http, Provider};
+
+use crate::witness::{fetch_payment_witness, witness_input};
+
+/// Claims checked together by the batch guest, with their witnesses in the same order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Batch {
+    pub claims: Vec<EscrowClaim>,
+    pub witnesses: Vec<PaymentWitness>,
+}
+
+impl Batch {
+    pub fn len(&self) -> usize {
+        self.claims.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.claims.is_empty()
+    }
+
+    /// Input of the batch guest, as submitted by `L3L1Escrow.checkPaymentStatusBatch`.
+    pub fn guest_input(&self) -> Vec<u8> {
+        let mut input = encode_claims(&self.claims);
+        input.extend(self.witness_input());
+        frame_input(&input)
+    }
+
+    /// Witnesses to pass to `L3L1Escrow.checkPaymentStatusBatch`, which appends them to the claims.
+    pub fn witness_input(&self) -> Vec<u8> {
+        self.witnesses.iter().flat_map(witness_input).collect()
+    }
+
+    /// Escrow IDs to pass to `L3L1Escrow.checkPaymentStatusBatch`.
+    pub fn escrow_ids(&self) -> Vec<U256> {
+        self.claims.iter().map(|claim| claim.escrow_id).collect()
+    }
+
+    /// Transaction hashes to pass to `L3L1Escrow.checkPaymentStatusBatch`.
+    pub fn tx_hashes(&self) -> Vec<H256> {
+        self.claims.iter().map(|claim| claim.tx_hash).collect()
+    }
+}
+
+/// Collects pending claims and hands them out in batches of at most `max_size`.
+#[derive(Debug)]
+pub struct Batcher {
+    max_size: usize,
+    pending: Batch,
+}
+
+impl Batcher {
+    /// Create a batcher emitting batches of up to `max_size` claims.
+    ///
+    /// Panics if `max_size` is zero or larger than [MAX_BATCH_SIZE].
+    pub fn new(max_size: usize) -> Self {
+        assert!(
+            (1..=MAX_BATCH_SIZE).contains(&max_size),
+            "batch size must be between 1 and {MAX_BATCH_SIZE}"
+        );
+        Self {
+            max_size,
+            pending: Batch::default(),
+        }
+    }
+
+    /// Number of claims waiting for the next batch.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Add a claim, returning the batch it completes if it is full.
+    pub fn push(&mut self, claim: EscrowClaim, witness: PaymentWitness) -> Option<Batch> {
+        self.pending.claims.push(claim);
+        self.pending.witnesses.push(witness);
+        match self.pending.len() >= self.max_size {
+            true => self.flush(),
+            false => None,
+        }
+    }
+
+    /// Fetch the witness for `claim` from the L3 node and add it, returning the batch it completes
+    /// if it is full.
+    pub async fn push_claim(
+        &mut self,
+        client: &Provider<Http>,
+        claim: EscrowClaim,
+    ) -> Result<Option<Batch>, Box<dyn Error>> {
+        let witness = fetch_payment_witness(client, claim.tx_hash).await?;
+        Ok(self.push(claim, witness))
+    }
+
+    /// Take the pending claims as a batch, if there are any.
+    pub fn flush(&mut self) -> Option<Batch> {
+        match self.pending.is_empty() {
+            true => None,
+            false => Some(mem::take(&mut self.pending)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use escrow_core::batch::COUNT_LEN;
+    use escrow_core::input::{decode_input_len, LEN_PREFIX_LEN};
+    use escrow_core::{Address, AssetKind};
+
+    use super::*;
+
+    fn claim(escrow_id: u64) -> EscrowClaim {
+        EscrowClaim {
+            escrow: Address::repeat_byte(0xee),
+            escrow_id: escrow_id.into(),
+            tx_hash: H256::from_low_u64_be(escrow_id),
+            kind: AssetKind::Native,
+            token: Address::zero(),
+            token_id: U256::zero(),
+            depositor: Address::repeat_byte(0x11),
+            creditor: Address::repeat_byte(0x22),
+            amount: U256::from(10),
+            not_before: 0,
+            not_after: u64::MAX,
+        }
+    }
+
+    #[test]
+    fn emits_full_batches() {
+        let mut batcher = Batcher::new(2);
+        assert_eq!(batcher.push(claim(0), PaymentWitness::default()), None);
+        let batch = batcher
+            .push(claim(1), PaymentWitness::default())
+            .expect("batch is full");
+        assert_eq!(batch.escrow_ids(), [U256::zero(), U256::one()]);
+        assert!(batcher.is_empty());
+
+        assert_eq!(batcher.push(claim(2), PaymentWitness::default()), None);
+        assert_eq!(batcher.flush().map(|batch| batch.len()), Some(1));
+        assert_eq!(batcher.flush(), None);
+    }
+
+    #[test]
+    fn guest_input_starts_with_claims() {
+        let batch = Batch {
+            claims: vec![claim(0), claim(1)],
+            witnesses: vec![PaymentWitness::default(); 2],
+        };
+        let framed = batch.guest_input();
+        let (len, input) = framed.split_at(LEN_PREFIX_LEN);
+        assert_eq!(decode_input_len(len), Ok(input.len()));
+        let claims_len = COUNT_LEN + 2 * EscrowClaim::ENCODED_LEN;
+        assert_eq!(input[..claims_len], encode_claims(&batch.claims));
+        assert_eq!(input[claims_len..], batch.witness_input());
+    }
+}
@@ -0,0 +1,293 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Content-addressed cache of receipts on local disk.
+//!
+//! A guest is deterministic, so a receipt only depends on the image ID and the input bytes.
+//! [ProofCache] keeps one [ReceiptFile] per `(image ID, SHA-256 of input)` so re-running the same
+//! claim during development skips the zkVM entirely. Entries are evicted least recently used
+//! first once the cache grows past its size limit.
+
+use std::error::Error;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::{env, fs};
+
+use ethers_core::types::H256;
+use filetime::{set_file_mtime, FileTime};
+use risc0_zkvm::sha::Digest;
+use sha2::{Digest as _, Sha256};
+
+use crate::receipt::ReceiptFile;
+
+/// Key of a cached receipt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub image_id: H256,
+    /// SHA-256 of the input bytes of the guest.
+    pub input_hash: H256,
+}
+
+impl CacheKey {
+    pub fn new(image_id: impl Into<Digest>, input: &[u8]) -> Self {
+        let image_id: Digest = image_id.into();
+        Self {
+            image_id: H256(image_id.into()),
+            input_hash: H256::from_slice(&Sha256::digest(input)),
+        }
+    }
+
+    fn file_name(&self) -> String {
+        format!(
+            "{}-{}.json",
+            hex::encode(self.image_id),
+            hex::encode(self.input_hash)
+        )
+    }
+
+    fn from_file_name(name: &str) -> Option<Self> {
+        let (image_id, input_hash) = name.strip_suffix(".json")?.split_once('-')?;
+        let decode = |hash: &str| {
+            let bytes: [u8; 32] = hex::decode(hash).ok()?.try_into().ok()?;
+            Some(H256(bytes))
+        };
+        Some(Self {
+            image_id: decode(image_id)?,
+            input_hash: decode(input_hash)?,
+        })
+    }
+}
+
+/// Cached receipt, as listed by [ProofCache::entries].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CacheEntry {
+    pub key: CacheKey,
+    /// Size of the entry on disk, in bytes.
+    pub size: u64,
+    pub last_used: SystemTime,
+}
+
+/// Directory of cached receipts, bounded in size.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl ProofCache {
+    pub const DEFAULT_MAX_BYTES: u64 = 1 << 30;
+
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_bytes: Self::DEFAULT_MAX_BYTES,
+        }
+    }
+
+    /// Cache in `PROOF_CACHE_DIR`, or in the system temporary directory if it is not set.
+    pub fn from_env() -> Self {
+        let dir = env::var_os("PROOF_CACHE_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| env::temp_dir().join("hello-bonsai-proof-cache"));
+        Self::new(dir)
+    }
+
+    /// Evict entries once the cache takes more than `max_bytes` on disk.
+    pub fn with_max_bytes(self, max_bytes: u64) -> Self {
+        Self { max_bytes, ..self }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, key: &CacheKey) -> PathBuf {
+        self.dir.join(key.file_name())
+    }
+
+    /// Cached receipt for `key`, marking it as recently used.
+    pub fn get(&self, key: &CacheKey) -> Result<Option<ReceiptFile>, Box<dyn Error>> {
+        let path = self.path(key);
+        let file = match ReceiptFile::load(&path) {
+            Ok(file) => file,
+            Err(err) => match err.downcast_ref::<std::io::Error>() {
+                Some(io) if io.kind() == ErrorKind::NotFound => return Ok(None),
+                _ => return Err(err),
+            },
+        };
+        set_file_mtime(&path, FileTime::now())?;
+        Ok(Some(file))
+    }
+
+    /// Store `file` under `key`, then evict the least recently used entries above the size limit.
+    pub fn insert(&self, key: &CacheKey, file: &ReceiptFile) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(&self.dir)?;
+        // Write to a temporary file first so concurrent readers never see a partial entry.
+        let path = self.path(key);
+        let partial = path.with_extension("partial");
+        file.save(&partial)?;
+        fs::rename(&partial, &path)?;
+        self.evict()?;
+        Ok(())
+    }
+
+    /// All cached receipts, least recently used first.
+    pub fn entries(&self) -> Result<Vec<CacheEntry>, Box<dyn Error>> {
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut entries = Vec::new();
+        for dir_entry in dir {
+            let dir_entry = dir_entry?;
+            let name = dir_entry.file_name();
+            let key = match name.to_str().and_then(CacheKey::from_file_name) {
+                Some(key) => key,
+                None => continue,
+            };
+            let metadata = dir_entry.metadata()?;
+            entries.push(CacheEntry {
+                key,
+                size: metadata.len(),
+                last_used: metadata.modified()?,
+            });
+        }
+        entries.sort_by_key(|entry| entry.last_used);
+        Ok(entries)
+    }
+
+    /// Remove the entries for `image_id`, or all entries if `None`. Returns the number removed.
+    pub fn purge(&self, image_id: Option<H256>) -> Result<usize, Box<dyn Error>> {
+        let mut removed = 0;
+        for entry in self.entries()? {
+            let matches = match image_id {
+                Some(image_id) => entry.key.image_id == image_id,
+                None => true,
+            };
+            if matches {
+                fs::remove_file(self.path(&entry.key))?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Remove the least recently used entries until the cache fits in its size limit.
+    fn evict(&self) -> Result<(), Box<dyn Error>> {
+        let entries = self.entries()?;
+        let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+        for entry in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            fs::remove_file(self.path(&entry.key))?;
+            total -= entry.size;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::receipt::{ReceiptMetadata, RECEIPT_FILE_VERSION};
+    use crate::GuestImage;
+
+    fn cache(name: &str) -> ProofCache {
+        let dir = env::temp_dir().join(format!("proof-cache-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        ProofCache::new(dir)
+    }
+
+    fn receipt_file(journal: &[u8]) -> ReceiptFile {
+        ReceiptFile {
+            version: RECEIPT_FILE_VERSION,
+            image_id: H256::zero(),
+            journal: journal.to_vec().into(),
+            seal: Default::default(),
+            metadata: ReceiptMetadata::default(),
+        }
+    }
+
+    fn key(input: &[u8]) -> CacheKey {
+        CacheKey::new(GuestImage::NativeTransfer.image_id(), input)
+    }
+
+    /// Pretend `key` was last used `age` ago.
+    fn age(cache: &ProofCache, key: &CacheKey, age: Duration) {
+        let last_used = FileTime::from_system_time(SystemTime::now() - age);
+        set_file_mtime(cache.path(key), last_used).unwrap();
+    }
+
+    #[test]
+    fn key_round_trips_through_file_name() {
+        let key = key(b"input");
+        assert_eq!(CacheKey::from_file_name(&key.file_name()), Some(key));
+        assert_eq!(CacheKey::from_file_name("unrelated.json"), None);
+    }
+
+    #[test]
+    fn returns_inserted_receipt() -> Result<(), Box<dyn Error>> {
+        let cache = cache("insert");
+        assert_eq!(cache.get(&key(b"a"))?, None);
+
+        cache.insert(&key(b"a"), &receipt_file(b"journal"))?;
+        assert_eq!(cache.get(&key(b"a"))?, Some(receipt_file(b"journal")));
+        assert_eq!(cache.get(&key(b"b"))?, None);
+
+        fs::remove_dir_all(cache.dir())?;
+        Ok(())
+    }
+
+    #[test]
+    fn evicts_least_recently_used() -> Result<(), Box<dyn Error>> {
+        let unbounded = cache("evict");
+        unbounded.insert(&key(b"a"), &receipt_file(b"a"))?;
+        unbounded.insert(&key(b"b"), &receipt_file(b"b"))?;
+        let size = unbounded.entries()?[0].size;
+        age(&unbounded, &key(b"a"), Duration::from_secs(20));
+        age(&unbounded, &key(b"b"), Duration::from_secs(10));
+        // Using `a` makes `b` the least recently used entry.
+        unbounded.get(&key(b"a"))?;
+
+        let cache = unbounded.with_max_bytes(2 * size);
+        cache.insert(&key(b"c"), &receipt_file(b"c"))?;
+        let keys: Vec<_> = cache.entries()?.iter().map(|entry| entry.key).collect();
+        assert_eq!(keys.len(), 2);
+        assert!(!keys.contains(&key(b"b")));
+
+        fs::remove_dir_all(cache.dir())?;
+        Ok(())
+    }
+
+    #[test]
+    fn purges_by_image_id() -> Result<(), Box<dyn Error>> {
+        let cache = cache("purge");
+        let other = CacheKey::new(GuestImage::Batch.image_id(), b"a");
+        cache.insert(&key(b"a"), &receipt_file(b"a"))?;
+        cache.insert(&other, &receipt_file(b"a"))?;
+
+        assert_eq!(cache.purge(Some(other.image_id))?, 1);
+        assert_eq!(cache.entries()?.len(), 1);
+        assert_eq!(cache.purge(None)?, 1);
+        assert!(cache.entries()?.is_empty());
+
+        fs::remove_dir_all(cache.dir())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Record of a deployment of `L3L1Escrow`, written by `deploy` for every network.
+//!
+//! Records live in a deployments directory as `<network>.json`, e.g. `deployments/sepolia.json`,
+//! so the other commands can find the contracts by network name. Deploying again to a network
+//! replaces its record.
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ethers_core::types::{Address, H256};
+use serde::{Deserialize, Serialize};
+
+use crate::manifest::ManifestEntry;
+
+/// Version of the deployment record format, bumped on incompatible changes.
+pub const DEPLOYMENT_FILE_VERSION: u32 = 1;
+
+/// Directory of the deployment records, relative to the working directory.
+pub const DEFAULT_DEPLOYMENTS_DIR: &str = "deployments";
+
+/// Contracts deployed to a network, and the guests they trust.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deployment {
+    pub version: u32,
+    /// Name the record is saved under, e.g. `sepolia`.
+    pub network: String,
+    pub chain_id: u64,
+    /// Address of `L3L1Escrow`.
+    pub escrow: Address,
+    /// Address of the Bonsai proxy the escrow submits requests to.
+    pub proxy: Address,
+    /// Chain ID of the L3 the escrow settles payments made on.
+    pub l3_chain_id: u64,
+    /// Address of the `IL3BlockHashOracle` the escrow checks L3 blocks against.
+    pub l3_block_hash_oracle: Address,
+    /// Image ID and ELF hash of every guest, as uploaded to Bonsai.
+    pub guests: Vec<ManifestEntry>,
+    /// Transaction that deployed the escrow.
+    pub tx_hash: H256,
+    pub block_number: u64,
+    pub deployer: Address,
+    /// Seconds since the Unix epoch at which the record was saved.
+    pub deployed_at: u64,
+}
+
+impl Deployment {
+    /// Path of the record of `network` in `dir`.
+    pub fn path(dir: impl AsRef<Path>, network: &str) -> Result<PathBuf, Box<dyn Error>> {
+        // Network names become file names, so keep them to a safe alphabet.
+        let valid = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+        if network.is_empty() || !network.chars().all(valid) {
+            return Err(format!(
+                "invalid network name {network:?}: use letters, digits, '-' and '_'"
+            )
+            .into());
+        }
+        Ok(dir.as_ref().join(format!("{network}.json")))
+    }
+
+    /// Load the record of `network` from `dir`.
+    pub fn load(dir: impl AsRef<Path>, network: &str) -> Result<Self, Box<dyn Error>> {
+        let path = Self::path(dir, network)?;
+        let bytes = fs::read(&path)
+            .map_err(|err| format!("no deployment for {network} at {}: {err}", path.display()))?;
+        let deployment: Self = serde_json::from_slice(&bytes)?;
+        if deployment.version != DEPLOYMENT_FILE_VERSION {
+            return Err(
+                format!("unsupported deployment file version {}", deployment.version).into(),
+            );
+        }
+        if deployment.network != network {
+            return Err(format!(
+                "{} records a deployment to {}, not {network}",
+                path.display(),
+                deployment.network
+            )
+            .into());
+        }
+        Ok(deployment)
+    }
+
+    /// Save the record to `dir`, replacing any earlier deployment to the same network, and return
+    /// its path.
+    pub fn save(&self, dir: impl AsRef<Path>) -> Result<PathBuf, Box<dyn Error>> {
+        let path = Self::path(&dir, &self.network)?;
+        fs::create_dir_all(&dir)?;
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+
+    /// Fail unless the record is for the chain with ID `chain_id`, to catch pointing a command at
+    /// the node of another network.
+    pub fn check_chain(&self, chain_id: u64) -> Result<(), Box<dyn Error>> {
+        if self.chain_id != chain_id {
+            return Err(format!(
+                "deployment to {} is on chain {}, but the node is on chain {chain_id}",
+                self.network, self.chain_id
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    pub fn guest(&self, name: &str) -> Option<&ManifestEntry> {
+        self.guests.iter().find(|entry| entry.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::manifest::GuestManifest;
+
+    fn deployment(network: &str) -> Deployment {
+        Deployment {
+            version: DEPLOYMENT_FILE_VERSION,
+            network: network.into(),
+            chain_id: 11155111,
+            escrow: Address::repeat_byte(1),
+            proxy: Address::repeat_byte(2),
+            l3_chain_id: 1001,
+            l3_block_hash_oracle: Address::repeat_byte(6),
+            guests: GuestManifest::embedded().guests,
+            tx_hash: H256::repeat_byte(3),
+            block_number: 3_000_000,
+            deployer: Address::repeat_byte(4),
+            deployed_at: 1_680_000_000,
+        }
+    }
+
+    #[test]
+    fn redeploy_replaces_record() -> Result<(), Box<dyn Error>> {
+        let dir = env::temp_dir().join("hello-bonsai-deployments");
+        deployment("testnet").save(&dir)?;
+        let mut redeployed = deployment("testnet");
+        redeployed.escrow = Address::repeat_byte(5);
+        redeployed.save(&dir)?;
+
+        let loaded = Deployment::load(&dir, "testnet");
+        fs::remove_dir_all(&dir)?;
+        let loaded = loaded?;
+        assert_eq!(loaded, redeployed);
+        assert!(loaded.check_chain(11155111).is_ok());
+        assert!(loaded.check_chain(1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_unsafe_network_names() {
+        assert!(Deployment::path("deployments", "sepolia").is_ok());
+        assert!(Deployment::path("deployments", "local_3").is_ok());
+        assert!(Deployment::path("deployments", "").is_err());
+        assert!(Deployment::path("deployments", "../sepolia").is_err());
+    }
+}
@@ -0,0 +1,322 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Host side of the EVM replay guest: the preflight of an L3 transaction against an RPC node, and
+//! the witness it records for the guest.
+
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{fmt, fs};
+
+use ethers_core::types::{Bytes, H256};
+use ethers_providers::Middleware;
+use evm_core::ether_trace::{txenv_from_tx, Http, Provider, TraceTx};
+use evm_core::{
+    state_diff, AccountDiff, Address, Env, EvmResult, ExecutionResult, Log, OriginalAccounts,
+    Return, ZkDb, EVM,
+};
+use risc0_zkvm::serde::{from_slice, to_vec};
+use serde::{Deserialize, Serialize};
+
+use crate::prover::words_to_bytes;
+
+/// Version of the [EvmWitness] file format. Bump it on any incompatible change.
+pub const EVM_WITNESS_VERSION: u32 = 1;
+
+/// How a transaction ended, from its EVM exit reason.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    /// The transaction ran to completion: `STOP`, `RETURN` or `SELFDESTRUCT`.
+    Success,
+    /// The transaction reverted, e.g. with `REVERT` or by calling too deep.
+    Revert,
+    /// The transaction halted abnormally, e.g. out of gas or on an invalid opcode.
+    Halt,
+}
+
+impl Outcome {
+    pub fn from_exit_reason(exit_reason: Return) -> Self {
+        match exit_reason {
+            Return::Stop | Return::Return | Return::SelfDestruct => Outcome::Success,
+            Return::Revert | Return::CallTooDeep | Return::OutOfFund => Outcome::Revert,
+            _ => Outcome::Halt,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Success => write!(f, "succeeded"),
+            Outcome::Revert => write!(f, "reverted"),
+            Outcome::Halt => write!(f, "halted"),
+        }
+    }
+}
+
+/// Everything the EVM replay guest reads: the transaction environment and the state it accessed.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EvmWitness {
+    pub version: u32,
+    pub tx_hash: H256,
+    pub tx_type: Option<u64>,
+    pub block_number: u64,
+    pub env: Env,
+    pub db: ZkDb,
+}
+
+impl EvmWitness {
+    /// Save the witness as the words of `risc0_zkvm::serde`, in little-endian bytes.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        fs::write(path, words_to_bytes(&to_vec(self)?))?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let bytes = fs::read(path)?;
+        if bytes.len() % 4 != 0 {
+            return Err("witness is not a whole number of words".into());
+        }
+        let words: Vec<u32> = bytes
+            .chunks(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+        let witness: Self = from_slice(&words)?;
+        if witness.version != EVM_WITNESS_VERSION {
+            return Err(format!("unsupported witness version {}", witness.version).into());
+        }
+        Ok(witness)
+    }
+}
+
+/// Result of executing a transaction on the host against live L3 state.
+pub struct Preflight {
+    pub witness: EvmWitness,
+    pub result: ExecutionResult,
+    /// Accounts the transaction accessed, in canonical order.
+    pub state: Vec<AccountDiff>,
+    pub time: Duration,
+}
+
+impl Preflight {
+    pub fn outcome(&self) -> Outcome {
+        Outcome::from_exit_reason(self.result.exit_reason)
+    }
+
+    /// Result the EVM replay guest should commit for this transaction.
+    pub fn expected_result(&self) -> EvmResult {
+        EvmResult {
+            exit_reason: self.result.exit_reason,
+            gas_used: self.result.gas_used,
+            logs: self.result.logs.clone(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// Journal the EVM replay guest should commit for this transaction, byte for byte.
+    pub fn expected_journal(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(words_to_bytes(&to_vec(&self.expected_result())?))
+    }
+}
+
+/// Result of a transaction in a stable JSON form, for diffing against fixtures.
+///
+/// Accounts are sorted by address and storage slots by index, and numbers and byte strings are
+/// `0x` hex, so the same result always serializes to the same bytes.
+#[derive(Debug, Serialize)]
+pub struct ResultJson {
+    /// Hash of the transaction, when known. Receipts of the EVM replay guest do not record it.
+    pub tx_hash: Option<H256>,
+    pub exit_reason: Return,
+    pub outcome: Outcome,
+    pub gas_used: u64,
+    pub logs: Vec<LogJson>,
+    pub accounts: Vec<AccountDiff>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogJson {
+    pub address: Address,
+    pub topics: Vec<H256>,
+    pub data: Bytes,
+}
+
+impl ResultJson {
+    fn new(
+        tx_hash: Option<H256>,
+        exit_reason: Return,
+        gas_used: u64,
+        logs: &[Log],
+        accounts: Vec<AccountDiff>,
+    ) -> Self {
+        Self {
+            tx_hash,
+            exit_reason,
+            outcome: Outcome::from_exit_reason(exit_reason),
+            gas_used,
+            logs: logs
+                .iter()
+                .map(|log| LogJson {
+                    address: log.address,
+                    topics: log.topics.clone(),
+                    data: log.data.to_vec().into(),
+                })
+                .collect(),
+            accounts,
+        }
+    }
+
+    /// Result as executed on the host, before proving.
+    pub fn from_preflight(preflight: &Preflight) -> Self {
+        Self::new(
+            Some(preflight.witness.tx_hash),
+            preflight.result.exit_reason,
+            preflight.result.gas_used,
+            &preflight.result.logs,
+            preflight.state.clone(),
+        )
+    }
+
+    /// Result committed by the guest for `tx_hash`.
+    pub fn from_result(tx_hash: Option<H256>, result: &EvmResult) -> Self {
+        Self::new(
+            tx_hash,
+            result.exit_reason,
+            result.gas_used,
+            &result.logs,
+            result.state.clone(),
+        )
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("failed to serialize result")
+    }
+}
+
+/// Execute `tx_hash` against the state of the block it was mined in, recording every value it
+/// reads for the guest.
+pub async fn preflight(
+    client: Arc<Provider<Http>>,
+    tx_hash: H256,
+) -> Result<Preflight, Box<dyn Error>> {
+    let tx = client
+        .get_transaction(tx_hash)
+        .await?
+        .ok_or("transaction not found")?;
+    let tx_type = tx.transaction_type.map(|tx_type| tx_type.as_u64());
+    let block_number = tx
+        .block_number
+        .ok_or("transaction is not mined yet")?
+        .as_u64();
+
+    let mut env = Env::default();
+    env.block.number = block_number.into();
+    env.tx = txenv_from_tx(tx);
+    let trace_db =
+        TraceTx::new(client, Some(block_number)).ok_or("failed to query the block number")?;
+
+    let mut evm = EVM::new();
+    evm.database(OriginalAccounts::new(trace_db));
+    evm.env = env.clone();
+
+    // The trace database blocks on RPC requests, so it cannot run on the async runtime itself.
+    let start = Instant::now();
+    let ((result, state), db) =
+        tokio::task::spawn_blocking(move || (evm.transact(), evm.take_db())).await?;
+    let time = start.elapsed();
+    let (trace_db, original) = db.into_parts();
+
+    Ok(Preflight {
+        witness: EvmWitness {
+            version: EVM_WITNESS_VERSION,
+            tx_hash,
+            tx_type,
+            block_number,
+            env,
+            db: trace_db.create_zkdb(),
+        },
+        result,
+        state: state_diff(&state, &original),
+        time,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_exit_reasons() {
+        assert_eq!(Outcome::from_exit_reason(Return::Stop), Outcome::Success);
+        assert_eq!(Outcome::from_exit_reason(Return::Return), Outcome::Success);
+        assert_eq!(
+            Outcome::from_exit_reason(Return::SelfDestruct),
+            Outcome::Success
+        );
+        assert_eq!(Outcome::from_exit_reason(Return::Revert), Outcome::Revert);
+        assert_eq!(Outcome::from_exit_reason(Return::OutOfGas), Outcome::Halt);
+        assert_eq!(
+            Outcome::from_exit_reason(Return::InvalidOpcode),
+            Outcome::Halt
+        );
+    }
+
+    #[test]
+    fn result_json_is_hex() {
+        let result = EvmResult {
+            exit_reason: Return::Stop,
+            gas_used: 21000,
+            logs: vec![Log {
+                address: Address::repeat_byte(0x11),
+                topics: vec![H256::repeat_byte(0x22)],
+                data: vec![0xab, 0xcd].into(),
+            }],
+            state: Default::default(),
+        };
+        let json: serde_json::Value =
+            serde_json::from_str(&ResultJson::from_result(None, &result).to_json()).unwrap();
+        assert_eq!(json["outcome"], "success");
+        assert_eq!(json["gas_used"], 21000);
+        assert_eq!(json["logs"][0]["data"], "0xabcd");
+        assert_eq!(
+            json["logs"][0]["address"],
+            "0x1111111111111111111111111111111111111111"
+        );
+    }
+
+    #[test]
+    fn witness_round_trips_through_disk() -> Result<(), Box<dyn Error>> {
+        let path = std::env::temp_dir().join("hello-bonsai-evm-witness.bin");
+        let witness = EvmWitness {
+            version: EVM_WITNESS_VERSION,
+            tx_hash: H256::repeat_byte(0xab),
+            tx_type: Some(2),
+            block_number: 12,
+            env: Env::default(),
+            db: ZkDb::default(),
+        };
+        witness.save(&path)?;
+        let loaded = EvmWitness::load(&path)?;
+        fs::remove_file(&path)?;
+
+        assert_eq!(loaded.tx_hash, witness.tx_hash);
+        assert_eq!(loaded.tx_type, witness.tx_type);
+        assert_eq!(loaded.block_number, 12);
+        assert_eq!(loaded.db.reads(), Default::default());
+        Ok(())
+    }
+}
@@ -0,0 +1,306 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Proving many L3 transactions with the EVM replay guest from a job list.
+//!
+//! A job list has one job per line: either the hash of a transaction, which is run in preflight
+//! against the L3 node, or the path of a witness saved by the `witness` command. Blank lines and
+//! lines starting with `#` are ignored. Preflight runs with a bounded number of concurrent RPC
+//! clients, proving on a bounded number of workers, and a failing job does not stop the others.
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use ethers_core::types::H256;
+use evm_core::ether_trace::{Http, Provider};
+use futures::future::join_all;
+use log::warn;
+use serde::Serialize;
+use tokio::sync::Semaphore;
+
+use crate::evm::{preflight, EvmWitness, Outcome};
+use crate::prover::ProvingClient;
+use crate::receipt::ReceiptFile;
+
+/// Where a job gets its witness from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JobSource {
+    /// Run the transaction in preflight against the L3 node.
+    Tx(H256),
+    /// Load a witness saved by the `witness` command.
+    Witness(PathBuf),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Job {
+    /// Name of the job, used for its receipt file: the transaction hash or the witness file stem.
+    pub name: String,
+    pub source: JobSource,
+}
+
+impl Job {
+    /// Parse a job list. Relative witness paths are resolved against `base`, usually the
+    /// directory of the list.
+    pub fn parse_list(list: &str, base: &Path) -> Result<Vec<Job>, Box<dyn Error>> {
+        let mut jobs: Vec<Job> = Vec::new();
+        for (number, line) in list.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let job = match line.starts_with("0x") && line.len() == 66 {
+                true => {
+                    let tx_hash: H256 = line
+                        .parse()
+                        .map_err(|err| format!("line {}: invalid hash: {err}", number + 1))?;
+                    Job {
+                        name: format!("{tx_hash:?}"),
+                        source: JobSource::Tx(tx_hash),
+                    }
+                }
+                false => {
+                    let path = base.join(line);
+                    let name = path
+                        .file_stem()
+                        .ok_or_else(|| format!("line {}: not a file: {line}", number + 1))?
+                        .to_string_lossy()
+                        .into_owned();
+                    Job {
+                        name,
+                        source: JobSource::Witness(path),
+                    }
+                }
+            };
+            if jobs.iter().any(|other| other.name == job.name) {
+                return Err(format!("line {}: duplicate job {}", number + 1, job.name).into());
+            }
+            jobs.push(job);
+        }
+        Ok(jobs)
+    }
+
+    pub fn load_list(path: impl AsRef<Path>) -> Result<Vec<Job>, Box<dyn Error>> {
+        let path = path.as_ref();
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+        Self::parse_list(&fs::read_to_string(path)?, base)
+    }
+}
+
+/// Limits and output of a run of [run_jobs].
+#[derive(Clone, Debug)]
+pub struct JobOptions {
+    /// Directory to write one receipt per job and the summary to.
+    pub out_dir: PathBuf,
+    /// Maximum number of preflights querying the L3 node at once.
+    pub rpc_concurrency: usize,
+    /// Maximum number of jobs proving at once.
+    pub workers: usize,
+}
+
+impl JobOptions {
+    /// Options with a default of 4 concurrent preflights and one proving worker per CPU core.
+    pub fn new(out_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            out_dir: out_dir.into(),
+            rpc_concurrency: 4,
+            workers: std::thread::available_parallelism()
+                .map(usize::from)
+                .unwrap_or(1),
+        }
+    }
+}
+
+/// Result of a single job.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct JobReport {
+    pub name: String,
+    pub tx_hash: Option<H256>,
+    /// How the transaction ended in the guest, or `None` if the job failed.
+    pub outcome: Option<Outcome>,
+    pub cycles: Option<usize>,
+    /// Whether the receipt came from the proof cache.
+    pub cached: bool,
+    /// Time spent in preflight, or `None` for jobs proven from a saved witness.
+    pub preflight_time: Option<Duration>,
+    pub prove_time: Option<Duration>,
+    /// Receipt written for the job.
+    pub receipt: Option<PathBuf>,
+    /// Why the job failed, if it did.
+    pub error: Option<String>,
+}
+
+/// Summary of a run of [run_jobs], written as `summary.json` next to the receipts.
+#[derive(Clone, Debug, Serialize)]
+pub struct JobSummary {
+    pub proved: usize,
+    pub failed: usize,
+    pub total_time: Duration,
+    pub jobs: Vec<JobReport>,
+}
+
+impl JobSummary {
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Prove every job, writing `<name>.json` receipts and `summary.json` to the output directory.
+///
+/// `rpc` is only needed for jobs that run a transaction in preflight; those jobs fail without it.
+pub async fn run_jobs(
+    jobs: Vec<Job>,
+    rpc: Option<Arc<Provider<Http>>>,
+    client: ProvingClient,
+    options: &JobOptions,
+) -> Result<JobSummary, Box<dyn Error>> {
+    fs::create_dir_all(&options.out_dir)?;
+    let runner = JobRunner {
+        rpc,
+        client,
+        rpc_permits: Semaphore::new(options.rpc_concurrency.max(1)),
+        prove_permits: Semaphore::new(options.workers.max(1)),
+        out_dir: &options.out_dir,
+    };
+
+    let start = Instant::now();
+    let reports = join_all(jobs.into_iter().map(|job| runner.run(job))).await;
+
+    let failed = reports
+        .iter()
+        .filter(|report| report.error.is_some())
+        .count();
+    let summary = JobSummary {
+        proved: reports.len() - failed,
+        failed,
+        total_time: start.elapsed(),
+        jobs: reports,
+    };
+    summary.save(options.out_dir.join("summary.json"))?;
+    Ok(summary)
+}
+
+/// State shared by the jobs of a run.
+struct JobRunner<'a> {
+    rpc: Option<Arc<Provider<Http>>>,
+    client: ProvingClient,
+    rpc_permits: Semaphore,
+    prove_permits: Semaphore,
+    out_dir: &'a Path,
+}
+
+impl JobRunner<'_> {
+    async fn run(&self, job: Job) -> JobReport {
+        let mut report = JobReport {
+            name: job.name.clone(),
+            ..Default::default()
+        };
+        if let Err(err) = self.prove(job, &mut report).await {
+            warn!("job {} failed: {err}", report.name);
+            report.error = Some(err);
+        }
+        report
+    }
+
+    /// Prove a single job, filling in `report` as it goes. Errors are strings so that they can
+    /// cross the blocking proving task.
+    async fn prove(&self, job: Job, report: &mut JobReport) -> Result<(), String> {
+        let witness = match job.source {
+            JobSource::Tx(tx_hash) => {
+                report.tx_hash = Some(tx_hash);
+                let rpc = self
+                    .rpc
+                    .clone()
+                    .ok_or("no RPC URL to run the transaction in preflight")?;
+                let _permit = self
+                    .rpc_permits
+                    .acquire()
+                    .await
+                    .map_err(|err| err.to_string())?;
+                let preflight = preflight(rpc, tx_hash)
+                    .await
+                    .map_err(|err| format!("preflight failed: {err}"))?;
+                report.preflight_time = Some(preflight.time);
+                preflight.witness
+            }
+            JobSource::Witness(path) => {
+                let witness = EvmWitness::load(&path)
+                    .map_err(|err| format!("failed to load {}: {err}", path.display()))?;
+                report.tx_hash = Some(witness.tx_hash);
+                witness
+            }
+        };
+
+        let _permit = self
+            .prove_permits
+            .acquire()
+            .await
+            .map_err(|err| err.to_string())?;
+        let client = self.client.clone();
+        let proof = tokio::task::spawn_blocking(move || {
+            client
+                .prove_evm(&witness.env, &witness.db)
+                .map_err(|err| format!("proving failed: {err}"))
+        })
+        .await
+        .map_err(|err| format!("proving task failed: {err}"))??;
+
+        let path = self.out_dir.join(format!("{}.json", job.name));
+        ReceiptFile::from_proof(&proof)
+            .save(&path)
+            .map_err(|err| format!("failed to save receipt: {err}"))?;
+
+        report.outcome = Some(Outcome::from_exit_reason(proof.journal.exit_reason));
+        report.cycles = Some(proof.cycles);
+        report.cached = proof.cached;
+        report.prove_time = Some(proof.prove_time);
+        report.receipt = Some(path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_job_list() {
+        let hash = format!("0x{}", "ab".repeat(32));
+        let list = format!("# jobs\n{hash}\n\n  witnesses/transfer.bin  \n");
+        let jobs = Job::parse_list(&list, Path::new("/jobs")).unwrap();
+        assert_eq!(
+            jobs,
+            vec![
+                Job {
+                    name: hash.clone(),
+                    source: JobSource::Tx(H256::repeat_byte(0xab)),
+                },
+                Job {
+                    name: "transfer".into(),
+                    source: JobSource::Witness("/jobs/witnesses/transfer.bin".into()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_jobs() {
+        let hash = format!("0x{}", "ab".repeat(32));
+        let list = format!("{hash}\n{hash}\n");
+        assert!(Job::parse_list(&list, Path::new(".")).is_err());
+    }
+}
@@ -12,34 +12,145 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Generated create containing the image ID and ELF binary of the build guest.
+//! Generated create containing the image IDs and ELF binaries of the built guests.
 
 include!(concat!(env!("OUT_DIR"), "/methods.rs"));
 
+pub mod aggregate;
+pub mod batch;
+pub mod cache;
+pub mod deployment;
+pub mod evm;
+pub mod jobs;
+pub mod manifest;
+pub mod prover;
+pub mod receipt;
+pub mod witness;
+
+use escrow_core::{AssetKind, EscrowClaim};
+use risc0_zkvm::sha::Digest;
+
+/// Guest programs built from `methods/guest`, one per proof type.
+///
+/// Each has its own image ID, so simple payments do not pay for the cycles of a full EVM replay.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GuestImage {
+    /// Checks an escrow claim against a native L3 value transfer.
+    NativeTransfer,
+    /// Checks an escrow claim against an ERC-20 `Transfer` event in the L3 transaction receipt.
+    Erc20Transfer,
+    /// Checks an escrow claim against an ERC-721 or ERC-1155 transfer event in the L3 transaction
+    /// receipt.
+    NftTransfer,
+    /// Checks a batch of escrow claims of any asset kind in a single run.
+    Batch,
+    /// Checks an escrow claim paid in parts, summing several L3 transactions.
+    Aggregate,
+    /// Replays an L3 transaction and commits the resulting state changes.
+    EvmReplay,
+    /// Executes a read-only call against L3 state and commits the return data.
+    ViewCall,
+}
+
+impl GuestImage {
+    pub const ALL: [GuestImage; 7] = [
+        GuestImage::NativeTransfer,
+        GuestImage::Erc20Transfer,
+        GuestImage::NftTransfer,
+        GuestImage::Batch,
+        GuestImage::Aggregate,
+        GuestImage::EvmReplay,
+        GuestImage::ViewCall,
+    ];
+
+    /// Guest that checks `claim`, depending on the kind of asset it is paid in.
+    pub fn for_claim(claim: &EscrowClaim) -> Self {
+        match claim.kind {
+            AssetKind::Native => GuestImage::NativeTransfer,
+            AssetKind::Erc20 => GuestImage::Erc20Transfer,
+            AssetKind::Erc721 | AssetKind::Erc1155 => GuestImage::NftTransfer,
+        }
+    }
+
+    /// Built-in guest with the given image ID.
+    pub fn from_image_id(image_id: impl Into<Digest>) -> Option<Self> {
+        let image_id = image_id.into();
+        Self::ALL
+            .into_iter()
+            .find(|guest| Digest::from(guest.image_id()) == image_id)
+    }
+
+    /// Built-in guest with the given [name](Self::name).
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|guest| guest.name() == name)
+    }
+
+    /// Name of the guest binary in `methods/guest/src/bin`.
+    pub fn name(self) -> &'static str {
+        match self {
+            GuestImage::NativeTransfer => "native_transfer",
+            GuestImage::Erc20Transfer => "erc20_transfer",
+            GuestImage::NftTransfer => "nft_transfer",
+            GuestImage::Batch => "batch",
+            GuestImage::Aggregate => "aggregate",
+            GuestImage::EvmReplay => "evm",
+            GuestImage::ViewCall => "view_call",
+        }
+    }
+
+    pub fn elf(self) -> &'static [u8] {
+        match self {
+            GuestImage::NativeTransfer => NATIVE_TRANSFER_ELF,
+            GuestImage::Erc20Transfer => ERC20_TRANSFER_ELF,
+            GuestImage::NftTransfer => NFT_TRANSFER_ELF,
+            GuestImage::Batch => BATCH_ELF,
+            GuestImage::Aggregate => AGGREGATE_ELF,
+            GuestImage::EvmReplay => EVM_ELF,
+            GuestImage::ViewCall => VIEW_CALL_ELF,
+        }
+    }
+
+    pub fn image_id(self) -> [u32; 8] {
+        match self {
+            GuestImage::NativeTransfer => NATIVE_TRANSFER_ID,
+            GuestImage::Erc20Transfer => ERC20_TRANSFER_ID,
+            GuestImage::NftTransfer => NFT_TRANSFER_ID,
+            GuestImage::Batch => BATCH_ID,
+            GuestImage::Aggregate => AGGREGATE_ID,
+            GuestImage::EvmReplay => EVM_ID,
+            GuestImage::ViewCall => VIEW_CALL_ID,
+        }
+    }
+
+    pub fn path(self) -> &'static str {
+        match self {
+            GuestImage::NativeTransfer => NATIVE_TRANSFER_PATH,
+            GuestImage::Erc20Transfer => ERC20_TRANSFER_PATH,
+            GuestImage::NftTransfer => NFT_TRANSFER_PATH,
+            GuestImage::Batch => BATCH_PATH,
+            GuestImage::Aggregate => AGGREGATE_PATH,
+            GuestImage::EvmReplay => EVM_PATH,
+            GuestImage::ViewCall => VIEW_CALL_PATH,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::error::Error;
     use std::str::FromStr;
-    use std::sync::Arc;
 
-    use clap::Parser;
-    use ethers_core::types::{H256};
-    use ethers_providers::{Middleware};
-    use evm_core::ether_trace::{Http, Provider};
-    use evm_core::{Env, EvmResult, EVM};
+    use escrow_core::verify::{check_encoded, check_native};
+    use escrow_core::{Address, AssetKind, ClaimStatus, EscrowClaim, EscrowJournal, U256};
+    use ethers_core::types::H256;
+    use ethers_providers::{Http, Provider};
     use log::info;
-    use risc0_zkvm::serde::{from_slice, to_vec};
 
-    use ethabi::ethereum_types::U256;
-    use ethabi::ParamType::Address;
-    use ethabi::Token;
-    use risc0_zkvm::{Prover, ProverOpts};
-    use hex;
-
-    use super::{EVM_ID, EVM_PATH};
+    use super::prover::ProvingClient;
+    use super::witness::fetch_payment_witness;
 
     #[tokio::test]
-    async fn evm() -> Result<(), Box<dyn Error>> {
+    async fn native_transfer() -> Result<(), Box<dyn Error>> {
         env_logger::init();
 
         // Add a transaction hash that contains a simple ETH transfer from creditor to depositor with the correct amount based on the L3L1Escrow on Layer 1
@@ -48,63 +159,44 @@ mod tests {
 
         // RPC of Layer 3 (add your own RPC node here)
         let client = Provider::<Http>::try_from("https://xxxx-xxx-xxx.eu.ngrok.io").expect("Invalid RPC url");
-        let client = Arc::new(client);
-
-        let tx = client.get_transaction(tx_hash).await.unwrap().unwrap();
-        let block_numb = tx.block_number.unwrap();
-        info!("Running TX: 0x{:x} at block {}", tx_hash, block_numb);
-
-        let mut env = Env::default();
-        env.block.number = U256::from(block_numb.as_u64());
-        env.tx = evm_core::ether_trace::txenv_from_tx(tx);
-        let trace_db = evm_core::ether_trace::TraceTx::new(client, Some(block_numb.as_u64())).unwrap();
-
-        let mut evm = EVM::new();
-        evm.database(trace_db);
-        evm.env = env.clone();
-
-        let ((res, _state), trace_db) =
-            tokio::task::spawn_blocking(move || (evm.transact(), evm.take_db()))
-                .await
-                .unwrap();
-
-        // Stop for simple transfers (success code without return val)
-        if res.exit_reason != evm_core::Return::Return && res.exit_reason != evm_core::Return::Stop {
-            println!("TX failed in pre-flight, {:?}", res.exit_reason);
-            panic!("TX failed in pre-flight");
-        }
 
-        let zkdb = trace_db.create_zkdb();
+        let witness = fetch_payment_witness(&client, tx_hash).await?;
+
+        let account = Address::from_str("0x4B45C30b8c4fAEC1c8eAaD5398F8b8e91BFbac15").unwrap();
+        let claim = EscrowClaim {
+            escrow: Address::zero(),
+            escrow_id: U256::zero(),
+            tx_hash,
+            kind: AssetKind::Native,
+            token: Address::zero(),
+            token_id: U256::zero(),
+            depositor: account,
+            creditor: account,
+            amount: U256::from(10),
+            // Any L3 block will do here; `deposit_claim` fills the window of a real deposit.
+            not_before: 0,
+            not_after: u64::MAX,
+        };
 
         // Skip seal as it is not needed to test the guest code.
-        let mut prover = Prover::new_with_opts(
-            &std::fs::read(EVM_PATH)?,
-            EVM_ID,
-            ProverOpts::default().with_skip_seal(true),
-        )?;
-
-        prover.add_input_u8_slice(&ethabi::encode(
-            &[Token::Uint(U256::from(128)), Token::String(String::from("0x671a3b40ecb7d51b209e68392df2d38c098aae03febd3a88be0f1fa77725bbd7")),
-                Token::Address(ethabi::ethereum_types::Address::from_str("0x4B45C30b8c4fAEC1c8eAaD5398F8b8e91BFbac15").unwrap()),
-                Token::Address(ethabi::ethereum_types::Address::from_str("0x4B45C30b8c4fAEC1c8eAaD5398F8b8e91BFbac15").unwrap()),
-                Token::Uint(U256::from(10))],));
-
         info!("Running zkvm...");
-        let receipt = prover.run().expect("Failed to run guest");
-
-        // SKIPPED SEAL! so no verification
-        //info!("Verifying receipt...");
-        //receipt.verify(&EVM_ID).expect("failed to verify receipt");
-
-        let res: EvmResult = from_slice(&receipt.journal).expect("Failed to deserialize EvmResult");
-        info!("exit reason: {:?}", res.exit_reason);
-        info!("state updates: {}", res.state.len());
-
-
-        assert_eq!(
-            &receipt.journal,
-            &ethabi::encode(&[Token::Bool(true), Token::Address(ethabi::ethereum_types::Address::from_str("0x4B45C30b8c4fAEC1c8eAaD5398F8b8e91BFbac15").unwrap())])
-        );
+        let client = ProvingClient::new().with_skip_seal(true);
+        let journal: EscrowJournal = client.prove_claim(&claim, &witness)?.journal;
+        info!("journal: {:?}", journal);
+
+        // The guest commits exactly what the host-side checks compute.
+        let expected = check_encoded(&claim.abi_encode(), Ok(witness), check_native);
+        assert_eq!(journal, expected);
+
+        // The example transaction predates escrow references, so it pays the right amount but is
+        // not tied to this deposit. Use a payment sent with the `pay` command to see it accepted.
+        assert_eq!(journal.status, ClaimStatus::MissingReference);
+        assert_eq!(journal.reference, H256::zero());
+        assert_eq!(journal.tx_hash, tx_hash);
+        assert_eq!(journal.token, Address::zero());
+        assert_eq!(journal.payer, account);
+        assert_eq!(journal.payee, account);
+        assert_eq!(journal.amount, claim.amount);
         Ok(())
     }
 }
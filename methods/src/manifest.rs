@@ -0,0 +1,151 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Manifest of the guests, written by `build.rs` as `guests.json`.
+//!
+//! Records the image ID and ELF hash of every guest with the RISC Zero version and build profile,
+//! so deployments and receipts can be matched against a build. The manifest only lives in
+//! `OUT_DIR` and is embedded into this crate; tools outside this workspace get a copy on disk from
+//! `deploy --write-manifest`.
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use ethers_core::types::H256;
+use serde::{Deserialize, Serialize};
+
+use crate::GuestImage;
+
+/// Guests of a build of this crate.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuestManifest {
+    pub risc0_version: String,
+    /// Cargo profile the guests were built with.
+    pub profile: String,
+    pub guests: Vec<ManifestEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// [Name](GuestImage::name) of the guest.
+    pub name: String,
+    pub image_id: H256,
+    /// SHA-256 of the guest ELF, as uploaded to Bonsai.
+    pub elf_sha256: H256,
+}
+
+/// Image ID deployed for a guest that differs from the one in the manifest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImageIdMismatch {
+    pub name: String,
+    /// Image ID in the manifest, or `None` if the manifest has no such guest.
+    pub expected: Option<H256>,
+    pub actual: H256,
+}
+
+impl GuestManifest {
+    /// Manifest of the guests built into this crate.
+    pub fn embedded() -> Self {
+        serde_json::from_str(include_str!(concat!(env!("OUT_DIR"), "/guests.json")))
+            .expect("build.rs wrote an invalid manifest")
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ManifestEntry> {
+        self.guests.iter().find(|entry| entry.name == name)
+    }
+
+    pub fn image_id(&self, guest: GuestImage) -> Option<H256> {
+        self.get(guest.name()).map(|entry| entry.image_id)
+    }
+
+    /// Compare deployed image IDs, by guest name, against the manifest.
+    pub fn mismatches<'a>(
+        &self,
+        deployed: impl IntoIterator<Item = (&'a str, H256)>,
+    ) -> Vec<ImageIdMismatch> {
+        deployed
+            .into_iter()
+            .filter_map(|(name, actual)| {
+                let expected = self.get(name).map(|entry| entry.image_id);
+                match expected == Some(actual) {
+                    true => None,
+                    false => Some(ImageIdMismatch {
+                        name: name.into(),
+                        expected,
+                        actual,
+                    }),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkvm::sha::{self, Digest, Sha256};
+
+    use super::*;
+
+    #[test]
+    fn matches_built_guests() {
+        let manifest = GuestManifest::embedded();
+        assert_eq!(manifest.guests.len(), GuestImage::ALL.len());
+        for guest in GuestImage::ALL {
+            let entry = manifest
+                .get(guest.name())
+                .expect("guest missing from manifest");
+            assert_eq!(entry.image_id, H256(Digest::from(guest.image_id()).into()));
+            assert_eq!(
+                entry.elf_sha256.as_bytes(),
+                sha::Impl::hash_bytes(guest.elf()).as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn reports_mismatched_image_ids() {
+        let manifest = GuestManifest::embedded();
+        let batch = manifest.image_id(GuestImage::Batch).unwrap();
+        let mismatches = manifest.mismatches([
+            ("batch", batch),
+            ("aggregate", H256::zero()),
+            ("fibonacci", batch),
+        ]);
+        assert_eq!(
+            mismatches,
+            vec![
+                ImageIdMismatch {
+                    name: "aggregate".into(),
+                    expected: manifest.image_id(GuestImage::Aggregate),
+                    actual: H256::zero(),
+                },
+                ImageIdMismatch {
+                    name: "fibonacci".into(),
+                    expected: None,
+                    actual: batch,
+                },
+            ]
+        );
+    }
+}
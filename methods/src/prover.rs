@@ -0,0 +1,213 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Local proving of the guests, shared by the host binary, the CLI and the test harnesses.
+//!
+//! [ProvingClient] builds the input of a guest from typed claims, runs it in the zkVM, verifies
+//! the receipt against the image ID of that guest and decodes the journal it committed.
+
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use escrow_core::input::frame_input;
+use escrow_core::{AggregateJournal, BatchJournal, EscrowClaim, EscrowJournal, PaymentWitness};
+use evm_core::{Env, EvmResult, ZkDb};
+use log::warn;
+use risc0_zkvm::serde::{from_slice, to_vec};
+use risc0_zkvm::sha::Digest;
+use risc0_zkvm::{Prover, ProverOpts, Receipt};
+
+use crate::aggregate::Aggregate;
+use crate::batch::Batch;
+use crate::cache::{CacheKey, ProofCache};
+use crate::receipt::ReceiptFile;
+use crate::witness::witness_input;
+use crate::GuestImage;
+
+/// Receipt of a guest run, with the journal decoded into the type the guest commits.
+#[derive(Clone, Debug)]
+pub struct Proof<J> {
+    pub journal: J,
+    pub receipt: Receipt,
+    /// Image ID of the guest that produced the receipt.
+    pub image_id: Digest,
+    /// Size of the input read by the guest.
+    pub input_bytes: usize,
+    /// Total number of zkVM cycles executed by the guest.
+    pub cycles: usize,
+    pub prove_time: Duration,
+    /// Time spent verifying the seal, or `None` if the seal was skipped.
+    pub verify_time: Option<Duration>,
+    /// Whether the receipt came from the [ProofCache] rather than a run of the zkVM.
+    pub cached: bool,
+}
+
+impl<J> Proof<J> {
+    fn map<T>(self, journal: T) -> Proof<T> {
+        Proof {
+            journal,
+            receipt: self.receipt,
+            image_id: self.image_id,
+            input_bytes: self.input_bytes,
+            cycles: self.cycles,
+            prove_time: self.prove_time,
+            verify_time: self.verify_time,
+            cached: self.cached,
+        }
+    }
+}
+
+/// Runs the guests locally in the zkVM.
+#[derive(Clone, Debug, Default)]
+pub struct ProvingClient {
+    skip_seal: bool,
+    cache: Option<ProofCache>,
+}
+
+impl ProvingClient {
+    /// Client that proves each run and verifies the seal.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Skip generating and verifying the seal. The journal is still computed by the guest, which
+    /// is all tests and the Bonsai mock need, at a fraction of the cost.
+    pub fn with_skip_seal(self, skip_seal: bool) -> Self {
+        Self { skip_seal, ..self }
+    }
+
+    /// Look up receipts in `cache` before proving, and store new ones in it.
+    pub fn with_cache(self, cache: ProofCache) -> Self {
+        Self {
+            cache: Some(cache),
+            ..self
+        }
+    }
+
+    /// Check `claim` against `witness` with the guest for its asset kind.
+    pub fn prove_claim(
+        &self,
+        claim: &EscrowClaim,
+        witness: &PaymentWitness,
+    ) -> Result<Proof<EscrowJournal>, Box<dyn Error>> {
+        let mut input = claim.abi_encode();
+        input.extend(witness_input(witness));
+        let proof = self.run(GuestImage::for_claim(claim), &frame_input(&input))?;
+        let journal = EscrowJournal::abi_decode(&proof.journal)?;
+        Ok(proof.map(journal))
+    }
+
+    /// Check every claim of `batch` in a single run of the batch guest.
+    pub fn prove_batch(&self, batch: &Batch) -> Result<Proof<BatchJournal>, Box<dyn Error>> {
+        let proof = self.run(GuestImage::Batch, &batch.guest_input())?;
+        let journal = BatchJournal::abi_decode(&proof.journal)?;
+        Ok(proof.map(journal))
+    }
+
+    /// Check the payments of `aggregate` towards its claim with the aggregate guest.
+    pub fn prove_aggregate(
+        &self,
+        aggregate: &Aggregate,
+    ) -> Result<Proof<AggregateJournal>, Box<dyn Error>> {
+        let proof = self.run(GuestImage::Aggregate, &aggregate.guest_input())?;
+        let journal = AggregateJournal::abi_decode(&proof.journal)?;
+        Ok(proof.map(journal))
+    }
+
+    /// Replay the transaction in `env` against the state recorded in `db` during preflight.
+    pub fn prove_evm(&self, env: &Env, db: &ZkDb) -> Result<Proof<EvmResult>, Box<dyn Error>> {
+        let mut input = words_to_bytes(&to_vec(env)?);
+        input.extend(words_to_bytes(&to_vec(db)?));
+        let proof = self.run(GuestImage::EvmReplay, &input)?;
+        let journal = from_slice(&proof.journal)?;
+        Ok(proof.map(journal))
+    }
+
+    /// Run `guest` on raw `input`, returning the journal bytes it committed.
+    pub fn run(&self, guest: GuestImage, input: &[u8]) -> Result<Proof<Vec<u8>>, Box<dyn Error>> {
+        self.run_elf(guest.elf(), guest.image_id(), input)
+    }
+
+    /// Run an arbitrary guest ELF, for harnesses that resolve image IDs themselves.
+    pub fn run_elf(
+        &self,
+        elf: &[u8],
+        image_id: impl Into<Digest>,
+        input: &[u8],
+    ) -> Result<Proof<Vec<u8>>, Box<dyn Error>> {
+        let image_id = image_id.into();
+        let key = CacheKey::new(image_id, input);
+        let (receipt, cycles, prove_time, cached) = match self.cached(&key) {
+            Some(file) => (file.receipt(), file.metadata.cycles, Duration::ZERO, true),
+            None => {
+                let opts = ProverOpts::default().with_skip_seal(self.skip_seal);
+                let mut prover = Prover::new_with_opts(elf, image_id, opts)?;
+                prover.add_input_u8_slice(input);
+
+                let prove_start = Instant::now();
+                let receipt = prover.run()?;
+                (receipt, prover.cycles, prove_start.elapsed(), false)
+            }
+        };
+
+        let verify_time = match self.skip_seal {
+            true => None,
+            false => {
+                let verify_start = Instant::now();
+                receipt.verify(&image_id)?;
+                Some(verify_start.elapsed())
+            }
+        };
+
+        let proof = Proof {
+            journal: receipt.journal.clone(),
+            receipt,
+            image_id,
+            input_bytes: input.len(),
+            cycles,
+            prove_time,
+            verify_time,
+            cached,
+        };
+        if let (Some(cache), false) = (&self.cache, cached) {
+            // A failure to cache is not a failure to prove.
+            if let Err(err) = cache.insert(&key, &ReceiptFile::from_proof(&proof)) {
+                warn!("failed to cache receipt: {err}");
+            }
+        }
+        Ok(proof)
+    }
+
+    /// Cached receipt for `key` that this client can use. Receipts cached without a seal are
+    /// ignored unless the seal is skipped.
+    fn cached(&self, key: &CacheKey) -> Option<ReceiptFile> {
+        let file = match self.cache.as_ref()?.get(key) {
+            Ok(file) => file?,
+            Err(err) => {
+                warn!("failed to read cached receipt: {err}");
+                return None;
+            }
+        };
+        match self.skip_seal || !file.seal.is_empty() {
+            true => Some(file),
+            false => None,
+        }
+    }
+}
+
+/// Little-endian bytes of input serialized with `risc0_zkvm::serde::to_vec`, as the guest reads
+/// them with `env::read`.
+pub fn words_to_bytes(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
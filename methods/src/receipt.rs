@@ -0,0 +1,191 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! On-disk format for receipts, so settlements can be re-checked after the fact.
+//!
+//! A [ReceiptFile] is JSON holding the image ID the receipt was produced for, the journal, the
+//! seal and metadata about the run. Byte fields are `0x`-prefixed hex.
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use escrow_core::EscrowClaim;
+use ethers_core::types::{Bytes, H256};
+use risc0_zkvm::sha::Digest;
+use risc0_zkvm::Receipt;
+use serde::{Deserialize, Serialize};
+
+use crate::prover::{words_to_bytes, Proof};
+use crate::GuestImage;
+
+/// Version of the [ReceiptFile] format. Bump it on any incompatible change.
+pub const RECEIPT_FILE_VERSION: u32 = 1;
+
+/// Receipt of a guest run as saved to disk.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiptFile {
+    pub version: u32,
+    /// Image ID of the guest that produced the receipt.
+    pub image_id: H256,
+    pub journal: Bytes,
+    /// Little-endian bytes of the seal words. Empty if the seal was skipped.
+    pub seal: Bytes,
+    pub metadata: ReceiptMetadata,
+}
+
+/// Information about a run that is not covered by the seal. Informational only: nothing in it is
+/// proven.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiptMetadata {
+    /// [Name](GuestImage::name) of the built-in guest with the image ID of the receipt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guest: Option<String>,
+    /// ABI-encoded claim the guest checked, as submitted by `L3L1Escrow`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claim: Option<Bytes>,
+    pub cycles: usize,
+    pub prove_time_ms: u64,
+    /// Seconds since the Unix epoch at which the receipt was saved.
+    pub created_at: u64,
+}
+
+impl ReceiptFile {
+    pub fn from_proof<J>(proof: &Proof<J>) -> Self {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self {
+            version: RECEIPT_FILE_VERSION,
+            image_id: H256(proof.image_id.into()),
+            journal: proof.receipt.journal.clone().into(),
+            seal: words_to_bytes(&proof.receipt.seal).into(),
+            metadata: ReceiptMetadata {
+                guest: GuestImage::from_image_id(proof.image_id).map(|guest| guest.name().into()),
+                claim: None,
+                cycles: proof.cycles,
+                prove_time_ms: proof.prove_time.as_millis() as u64,
+                created_at,
+            },
+        }
+    }
+
+    /// Record the claim the receipt was produced for.
+    pub fn with_claim(mut self, claim: &EscrowClaim) -> Self {
+        self.metadata.claim = Some(claim.abi_encode().into());
+        self
+    }
+
+    /// Built-in guest with the image ID of the receipt, if any.
+    pub fn guest(&self) -> Option<GuestImage> {
+        GuestImage::from_image_id(self.image_id.0)
+    }
+
+    /// Claim recorded in the metadata, if any.
+    pub fn claim(&self) -> Option<Result<EscrowClaim, Box<dyn Error>>> {
+        let claim = self.metadata.claim.as_ref()?;
+        Some(EscrowClaim::abi_decode(claim).map_err(Into::into))
+    }
+
+    pub fn receipt(&self) -> Receipt {
+        let seal: Vec<u32> = self
+            .seal
+            .chunks(4)
+            .map(|word| u32::from_le_bytes(word.try_into().unwrap_or_default()))
+            .collect();
+        Receipt::new(&self.journal, &seal)
+    }
+
+    /// Check the seal against `image_id`. Pass the image ID you trust rather than the one recorded
+    /// in the file, which is not itself proven.
+    pub fn verify(&self, image_id: impl Into<Digest>) -> Result<(), Box<dyn Error>> {
+        if self.seal.is_empty() {
+            return Err("receipt has no seal; it was produced with the seal skipped".into());
+        }
+        if self.seal.len() % 4 != 0 {
+            return Err("seal is not a whole number of words".into());
+        }
+        self.receipt().verify(&image_id.into())?;
+        Ok(())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let file: Self = serde_json::from_slice(&fs::read(path)?)?;
+        if file.version != RECEIPT_FILE_VERSION {
+            return Err(format!("unsupported receipt file version {}", file.version).into());
+        }
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn receipt_file() -> ReceiptFile {
+        ReceiptFile {
+            version: RECEIPT_FILE_VERSION,
+            image_id: H256(Digest::from(GuestImage::Batch.image_id()).into()),
+            journal: vec![1, 2, 3].into(),
+            seal: Bytes::default(),
+            metadata: ReceiptMetadata {
+                guest: Some("batch".into()),
+                claim: Some(EscrowClaim::default().abi_encode().into()),
+                cycles: 1 << 20,
+                prove_time_ms: 1500,
+                created_at: 1_680_000_000,
+            },
+        }
+    }
+
+    #[test]
+    fn round_trips_through_disk() -> Result<(), Box<dyn Error>> {
+        let path = env::temp_dir().join("hello-bonsai-receipt-round-trip.json");
+        receipt_file().save(&path)?;
+        let loaded = ReceiptFile::load(&path)?;
+        fs::remove_file(&path)?;
+
+        assert_eq!(loaded, receipt_file());
+        assert_eq!(loaded.guest(), Some(GuestImage::Batch));
+        assert_eq!(loaded.claim().transpose()?, Some(EscrowClaim::default()));
+        Ok(())
+    }
+
+    #[test]
+    fn refuses_unsealed_receipt() {
+        let file = receipt_file();
+        assert!(file.verify(GuestImage::Batch.image_id()).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_version() -> Result<(), Box<dyn Error>> {
+        let path = env::temp_dir().join("hello-bonsai-receipt-version.json");
+        let mut file = receipt_file();
+        file.version = RECEIPT_FILE_VERSION + 1;
+        file.save(&path)?;
+        let loaded = ReceiptFile::load(&path);
+        fs::remove_file(&path)?;
+        assert!(loaded.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Host-side construction of the witnesses read by the escrow guest.
+
+use std::error::Error;
+
+use escrow_core::{keccak256, mpt, PaymentWitness};
+use ethers_core::types::{Block, Transaction, TransactionReceipt, H256};
+use ethers_core::utils::rlp::{self, RlpStream};
+use ethers_providers::{Http, Middleware, Provider};
+use risc0_zkvm::serde::to_vec;
+
+use crate::prover::words_to_bytes;
+
+/// Fetch the L3 block including `tx_hash` and prove the transaction and its receipt against its
+/// header.
+pub async fn fetch_payment_witness(
+    client: &Provider<Http>,
+    tx_hash: H256,
+) -> Result<PaymentWitness, Box<dyn Error>> {
+    let tx = client
+        .get_transaction(tx_hash)
+        .await?
+        .ok_or("transaction not found")?;
+    let block_hash = tx.block_hash.ok_or("transaction is still pending")?;
+    let block = client
+        .get_block_with_txs(block_hash)
+        .await?
+        .ok_or("block not found")?;
+
+    let header = encode_header(&block);
+    if keccak256(&header) != block_hash {
+        return Err("re-encoded header does not match the block hash".into());
+    }
+
+    let items: Vec<_> = block
+        .transactions
+        .iter()
+        .enumerate()
+        .map(|(index, tx)| (rlp::encode(&(index as u64)).to_vec(), tx.rlp().to_vec()))
+        .collect();
+    let tx_index = tx
+        .transaction_index
+        .ok_or("transaction has no index")?
+        .as_u64();
+    let (root, tx_proof) = mpt::build_proof(&items, &rlp::encode(&tx_index));
+    if root != block.transactions_root {
+        return Err("rebuilt transactions trie does not match the block".into());
+    }
+
+    // Nodes do not serve receipt proofs, so rebuild the receipts trie from every receipt in the
+    // block.
+    let mut items = Vec::with_capacity(block.transactions.len());
+    for (index, tx) in block.transactions.iter().enumerate() {
+        let receipt = client
+            .get_transaction_receipt(tx.hash)
+            .await?
+            .ok_or("receipt not found")?;
+        items.push((
+            rlp::encode(&(index as u64)).to_vec(),
+            encode_receipt(&receipt),
+        ));
+    }
+    let (root, receipt_proof) = mpt::build_proof(&items, &rlp::encode(&tx_index));
+    if root != block.receipts_root {
+        return Err("rebuilt receipts trie does not match the block".into());
+    }
+    let receipt = items.swap_remove(tx_index as usize).1;
+
+    Ok(PaymentWitness {
+        header,
+        transaction: tx.rlp().to_vec(),
+        tx_index,
+        tx_proof,
+        receipt,
+        receipt_proof,
+    })
+}
+
+/// Serialize the witness the way the guest deserializes it with `decode_witnesses`.
+///
+/// The result can be added to the prover input before framing it with `frame_input`, or passed
+/// to `L3L1Escrow.checkPaymentStatus` which appends it to the claim and frames both.
+pub fn witness_input(witness: &PaymentWitness) -> Vec<u8> {
+    words_to_bytes(&to_vec(witness).expect("failed to serialize witness"))
+}
+
+fn encode_header(block: &Block<Transaction>) -> Vec<u8> {
+    let mut stream = RlpStream::new();
+    stream.begin_unbounded_list();
+    stream
+        .append(&block.parent_hash)
+        .append(&block.uncles_hash)
+        .append(&block.author.unwrap_or_default())
+        .append(&block.state_root)
+        .append(&block.transactions_root)
+        .append(&block.receipts_root)
+        .append(&block.logs_bloom.unwrap_or_default())
+        .append(&block.difficulty)
+        .append(&block.number.unwrap_or_default())
+        .append(&block.gas_limit)
+        .append(&block.gas_used)
+        .append(&block.timestamp)
+        .append(&block.extra_data.as_ref())
+        .append(&block.mix_hash.unwrap_or_default())
+        .append(&block.nonce.unwrap_or_default());
+    if let Some(base_fee) = block.base_fee_per_gas {
+        stream.append(&base_fee);
+    }
+    stream.finalize_unbounded_list();
+    stream.out().to_vec()
+}
+
+/// Encode a receipt as it is stored in the receipts trie, with the EIP-2718 type prefix for typed
+/// transactions.
+fn encode_receipt(receipt: &TransactionReceipt) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    match receipt.transaction_type {
+        Some(tx_type) if !tx_type.is_zero() => encoded.push(tx_type.as_u64() as u8),
+        _ => {}
+    }
+    encoded.extend_from_slice(&rlp::encode(receipt));
+    encoded
+}